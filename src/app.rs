@@ -1,20 +1,62 @@
-use std::{io::Stdout, sync::Arc};
-
-use bsky_sdk::BskyAgent;
+use atrium_api::types::string::{Did, Language};
 use ratatui::{
-    crossterm::event,
-    layout::{Constraint, Layout},
-    prelude::CrosstermBackend,
+    backend::Backend,
+    crossterm::event::{
+        self, Event, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+    },
+    layout::{Constraint, Layout, Position, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    Terminal,
+    Frame, Terminal,
+};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    columns::{Column, ColumnStack},
-    components::logger::LOGSTORE,
+    client::Client,
+    columns::{
+        outbox_view::OutboxView,
+        profile_page::ProfilePage,
+        saved_feeds::SavedFeeds,
+        search_view::SearchView,
+        stats_view::StatsView,
+        thread_view::ThreadView,
+        updating_feed::{FeedSource, UpdatingFeed},
+        Column, ColumnKind, ColumnStack, Workspace,
+    },
+    command::{self, Command, FilterRule, Setting},
+    components::{
+        composer::{
+            textarea::{Input, Key},
+            vim::{InputMode, Vim},
+        },
+        logger::{LOGSTORE, LOG_SECONDS},
+    },
+    keymap,
+    keymap::{Action, KeyPress, PendingKeys},
+    moderation, post_manager,
 };
 
+// Wakes the main loop to render again. Called by workers after changing
+// what columns show.
+#[macro_export]
+macro_rules! redraw {
+    () => {
+        $crate::REDRAW.notify_one()
+    };
+}
+
+// Longest time between two clicks on the same cell to count as a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+#[allow(
+    clippy::large_enum_variant,
+    reason = "a new layer is moved into its stack right away, boxing it only \
+              adds an allocation"
+)]
 pub enum AppEvent {
     None,
     Quit,
@@ -26,151 +68,603 @@ pub trait EventReceiver {
     async fn handle_events(
         self,
         event: event::Event,
-        agent: BskyAgent,
+        agent: Client,
     ) -> AppEvent;
 }
 
+// Receives actions resolved from key presses by the keymap
+pub trait ActionReceiver {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent;
+}
+
+// Receives left clicks at a screen position
+pub trait ClickReceiver {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent;
+}
+
 pub struct App {
-    pub column: ColumnStack,
+    pub workspaces: Vec<Workspace>,
+    // index of the visible workspace
+    pub current: usize,
+    pending_keys: PendingKeys,
+    // `:` prompt, takes every event while open
+    command_line: Option<Vim>,
+    // where the stacks of the current workspace were last rendered
+    stack_areas: Vec<Rect>,
+    last_click: Option<(Instant, Position)>,
 }
 
 impl App {
-    pub fn new(column: ColumnStack) -> App {
-        App { column }
+    pub fn new(workspaces: Vec<Workspace>) -> App {
+        App {
+            workspaces,
+            current: 0,
+            pending_keys: PendingKeys::default(),
+            command_line: None,
+            stack_areas: vec![],
+            last_click: None,
+        }
+    }
+
+    pub fn workspace(&mut self) -> &mut Workspace {
+        &mut self.workspaces[self.current]
+    }
+
+    pub fn focused_stack(&mut self) -> &mut ColumnStack {
+        self.workspace().focused_stack()
     }
 
-    pub async fn render(
+    // `offline` shows that what is drawn may be out of date
+    pub async fn render<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
+        offline: bool,
     ) {
         let logs = Arc::clone(&LOGSTORE.logs);
         let logs = logs.lock().await;
 
         terminal
             .draw(|f| {
-                let last_log = logs
-                    .last()
-                    .map(|(time, log)| {
-                        let delta_time = chrono::Local::now() - time;
-                        if delta_time.num_seconds() < 5 {
-                            Some(log)
-                        } else {
-                            None
-                        }
-                    })
-                    .flatten();
+                let last_log = logs.last().and_then(|(time, log)| {
+                    let delta_time = chrono::Local::now() - time;
+                    if delta_time.num_seconds() < LOG_SECONDS as i64 {
+                        Some(log)
+                    } else {
+                        None
+                    }
+                });
 
-                let [_, area, _] = Layout::horizontal([
-                    Constraint::Fill(1),
-                    Constraint::Max(80),
+                let [tab_area, main_area, log_area] = Layout::vertical([
+                    Constraint::Length(1),
                     Constraint::Fill(1),
+                    Constraint::Length(
+                        (last_log.is_some() || self.command_line.is_some())
+                            as u16,
+                    ),
                 ])
                 .areas(f.area());
 
-                let [top_area, main_area, log_area] = Layout::vertical([
-                    Constraint::Length(1),
-                    Constraint::Fill(1),
-                    Constraint::Length(last_log.is_some() as u16),
-                ])
-                .areas(area);
-
-                let mut top_items = self
-                    .column
-                    .stack
-                    .iter()
-                    .map(Column::name)
-                    .map(|c| {
-                        Span::styled(
-                            c,
+                let mut tabs = Line::default();
+                for (i, workspace) in self.workspaces.iter().enumerate() {
+                    let tab = format!(" {} {} ", i + 1, workspace.name);
+                    if i == self.current {
+                        tabs += Span::styled(
+                            tab,
                             Style::default().bg(Color::Rgb(45, 50, 55)),
-                        )
-                    })
-                    .peekable();
-                let mut top = Line::from(top_items.next().unwrap_or_default());
-                while top_items.peek().is_some() {
-                    top += Span::styled(" > ", Color::DarkGray);
-                    top += top_items.next().unwrap();
+                        );
+                    } else {
+                        tabs += Span::styled(tab, Color::DarkGray);
+                    }
+                }
+                f.render_widget(tabs, tab_area);
+                if offline {
+                    f.render_widget(
+                        Line::from(" offline ")
+                            .style(
+                                Style::default()
+                                    .fg(Color::Black)
+                                    .bg(Color::Yellow),
+                            )
+                            .right_aligned(),
+                        tab_area,
+                    );
                 }
-                top += top_items.next().unwrap_or_default();
-                f.render_widget(top, top_area);
 
-                let last = self.column.pop();
-                let (mut modal, mut last) =
-                    if let Some(Column::FacetModal(f)) = last {
-                        (Some(f), self.column.pop())
-                    } else {
-                        (None, last)
-                    };
+                let workspace = &mut self.workspaces[self.current];
+                let constraints = [Constraint::Fill(1)]
+                    .into_iter()
+                    .chain(
+                        workspace
+                            .stacks
+                            .iter()
+                            .map(|s| Constraint::Max(s.width)),
+                    )
+                    .chain([Constraint::Fill(1)]);
+                let areas =
+                    Layout::horizontal(constraints).spacing(1).split(main_area);
+                let focus = workspace.focus;
+                for (i, stack) in workspace.stacks.iter_mut().enumerate() {
+                    render_stack(f, stack, areas[i + 1], i == focus);
+                }
+                self.stack_areas = areas[1..areas.len() - 1].to_vec();
 
-                match &mut last {
-                    None => {}
-                    Some(Column::UpdatingFeed(feed)) => {
-                        f.render_widget(feed, main_area);
+                if let Some(command_line) = &mut self.command_line {
+                    let [prompt_area, input_area] = Layout::horizontal([
+                        Constraint::Length(1),
+                        Constraint::Fill(1),
+                    ])
+                    .areas(log_area);
+                    f.render_widget(":", prompt_area);
+                    command_line.textarea.focused(true);
+                    f.render_widget(&mut command_line.textarea, input_area);
+                } else {
+                    if let Some(log) = last_log {
+                        f.render_widget(
+                            Span::styled(log, Style::default().reversed()),
+                            log_area,
+                        );
                     }
-                    Some(Column::Thread(thread)) => {
-                        f.render_widget(thread, main_area);
+                }
+            })
+            .unwrap();
+    }
+
+    async fn handle_command_line(
+        &mut self,
+        event: event::Event,
+        agent: Client,
+    ) -> AppEvent {
+        let Some(command_line) = &mut self.command_line else {
+            return AppEvent::None;
+        };
+        match &event {
+            Event::Paste(s) => {
+                command_line.textarea.insert_string(s.clone());
+                return AppEvent::None;
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {}
+            _ => return AppEvent::None,
+        }
+
+        match event.clone().into() {
+            Input { key: Key::Enter, .. } => {
+                let line = command_line.textarea.lines().join("");
+                self.command_line = None;
+                match command::parse(&line) {
+                    Ok(command) => {
+                        return self.run_command(command, agent).await
                     }
-                    Some(Column::Composer(composer)) => {
-                        f.render_widget(composer, main_area);
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return AppEvent::None;
                     }
-                    Some(Column::FacetModal(_)) => {
-                        panic!("FacetModal on top of FacetModal?")
+                }
+            }
+            Input { key: Key::Esc, .. }
+                if matches!(command_line.mode, InputMode::Normal) =>
+            {
+                self.command_line = None;
+                return AppEvent::None;
+            }
+            _ => {}
+        }
+        if let AppEvent::ColumnPopLayer =
+            command_line.handle_events(event, agent).await
+        {
+            self.command_line = None;
+        }
+        return AppEvent::None;
+    }
+
+    async fn run_command(
+        &mut self,
+        command: Command,
+        agent: Client,
+    ) -> AppEvent {
+        match command {
+            Command::Profile(actor) => {
+                return open_profile(&actor, agent).await
+            }
+
+            Command::Open(uri) => {
+                let rest = uri.trim_start_matches("at://");
+                let parts = rest.split('/').collect::<Vec<_>>();
+                match parts.as_slice() {
+                    [actor] | [actor, ""] => {
+                        return open_profile(actor, agent).await
                     }
-                    Some(Column::Notifications(notifications)) => {
-                        f.render_widget(notifications, main_area);
+                    [_, "app.bsky.feed.generator", _] => {
+                        return AppEvent::ColumnNewLayer(Column::feed(
+                            FeedSource::Generator(uri),
+                            agent,
+                        ));
                     }
-                    Some(Column::PostLikes(post_likes)) => {
-                        f.render_widget(post_likes, main_area);
+                    [_, "app.bsky.graph.list", _] => {
+                        return AppEvent::ColumnNewLayer(Column::feed(
+                            FeedSource::List(uri),
+                            agent,
+                        ));
+                    }
+                    [_, "app.bsky.feed.post", _] => {
+                        match ThreadView::from_uri(uri, agent).await {
+                            Ok(view) => {
+                                return AppEvent::ColumnNewLayer(
+                                    Column::Thread(view),
+                                )
+                            }
+                            Err(e) => {
+                                log::error!("{}", e);
+                                return AppEvent::None;
+                            }
+                        }
                     }
-                    Some(Column::ProfilePage(profile)) => {
-                        f.render_widget(profile, main_area);
+                    _ => {
+                        log::error!("Cannot open {}", uri);
+                        return AppEvent::None;
                     }
-                    Some(Column::SearchView(search)) => {
-                        f.render_widget(search, main_area);
+                }
+            }
+
+            Command::Search(q) => {
+                let mut search = SearchView::new(agent);
+                search.search(q);
+                return AppEvent::ColumnNewLayer(Column::SearchView(Box::new(
+                    search,
+                )));
+            }
+
+            Command::SearchPosts(q) => {
+                let mut search = SearchView::new(agent.clone());
+                search.search_posts(q, agent);
+                return AppEvent::ColumnNewLayer(Column::SearchView(Box::new(
+                    search,
+                )));
+            }
+
+            Command::Feed(None) => {
+                return Column::open_layer(ColumnKind::Feed, agent).await;
+            }
+
+            Command::Feed(Some(uri)) => {
+                let parts = uri.split('/').collect::<Vec<_>>();
+                let is_generator = matches!(
+                    parts.as_slice(),
+                    ["at:", "", _, "app.bsky.feed.generator", _]
+                );
+                if !is_generator {
+                    log::error!(
+                        "Cannot open {}: not a feed generator at:// uri",
+                        uri
+                    );
+                    return AppEvent::None;
+                }
+                return AppEvent::ColumnNewLayer(Column::feed(
+                    FeedSource::Generator(uri),
+                    agent,
+                ));
+            }
+
+            Command::List(uri) => {
+                return AppEvent::ColumnNewLayer(Column::feed(
+                    FeedSource::List(uri),
+                    agent,
+                ));
+            }
+
+            Command::Like => {
+                let Some(column) = self.focused_stack().last_mut() else {
+                    return AppEvent::None;
+                };
+                return column.handle_action(Action::LikeToggle, agent).await;
+            }
+
+            Command::Stats => {
+                return AppEvent::ColumnNewLayer(Column::Stats(StatsView));
+            }
+
+            Command::Outbox => {
+                return AppEvent::ColumnNewLayer(Column::Outbox(
+                    OutboxView::new(),
+                ));
+            }
+
+            Command::Feeds => {
+                return AppEvent::ColumnNewLayer(Column::SavedFeeds(
+                    SavedFeeds::new(agent),
+                ));
+            }
+
+            Command::Quit => return AppEvent::Quit,
+
+            Command::Set(setting) => {
+                let mut config = crate::CONFIG.write().unwrap();
+                match setting {
+                    Setting::FeedRefreshRate(n) => config.feed.refresh_rate = n,
+                    Setting::NotificationsRefreshRate(n) => {
+                        config.notifications.refresh_rate = n
                     }
                 }
+                return AppEvent::None;
+            }
 
-                match &mut modal {
-                    None => {}
-                    Some(modal) => f.render_widget(modal, main_area),
+            Command::Mute(None) => {
+                let words = moderation!().muted_words();
+                if words.is_empty() {
+                    log::info!("No muted words");
+                } else {
+                    log::info!("Muted words: {}", words.join(", "));
                 }
+                return AppEvent::None;
+            }
 
-                if last.is_some() {
-                    self.column.push(last.unwrap());
+            Command::Mute(Some(word)) => {
+                match moderation!().mute(&agent, &word).await {
+                    Ok(()) => log::info!("Muted {}", word),
+                    Err(e) => log::error!("Cannot mute {}: {}", word, e),
                 }
-                if modal.is_some() {
-                    self.column.push(Column::FacetModal(modal.unwrap()));
+                return AppEvent::None;
+            }
+
+            Command::Unmute(word) => {
+                match moderation!().unmute(&agent, &word).await {
+                    Ok(()) => log::info!("Unmuted {}", word),
+                    Err(e) => log::error!("Cannot unmute {}: {}", word, e),
                 }
+                return AppEvent::None;
+            }
 
-                last_log.map(|log| {
-                    f.render_widget(
-                        Span::styled(log, Style::default().reversed()),
-                        log_area,
-                    );
-                });
-            })
-            .unwrap();
+            Command::Filter(rule) => {
+                let Some(Column::UpdatingFeed(feed)) =
+                    self.focused_stack().last_mut()
+                else {
+                    log::error!("Filters only apply to feed columns");
+                    return AppEvent::None;
+                };
+                if let Err(e) = filter_feed(feed, rule, &agent).await {
+                    log::error!("{}", e);
+                }
+                return AppEvent::None;
+            }
+        }
     }
 
-    pub async fn refresh(&mut self) {
-        let last = self.column.pop();
-        if last.is_none() {
-            return;
-        }
-        match last {
-            Some(Column::Composer(mut composer)) => {
-                if !composer.post_finished().await {
-                    self.column.push(Column::Composer(composer));
+    // Wheel scrolls the column under the cursor, a click focuses it and
+    // selects or opens what is under the cursor
+    async fn handle_mouse(
+        &mut self,
+        mouse: MouseEvent,
+        agent: Client,
+    ) -> AppEvent {
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(i) =
+            self.stack_areas.iter().position(|a| a.contains(position))
+        else {
+            return AppEvent::None;
+        };
+        let workspace = &mut self.workspaces[self.current];
+        let action = match mouse.kind {
+            MouseEventKind::ScrollDown => Action::Next,
+            MouseEventKind::ScrollUp => Action::Previous,
+            MouseEventKind::Down(MouseButton::Left) => {
+                workspace.focus = i;
+                self.pending_keys = PendingKeys::default();
+                let Some(column) = workspace.stacks[i].last_mut() else {
+                    return AppEvent::None;
+                };
+
+                let now = Instant::now();
+                let double_click = matches!(
+                    self.last_click,
+                    Some((t, p)) if p == position && now - t < DOUBLE_CLICK
+                );
+                if !double_click {
+                    self.last_click = Some((now, position));
+                    return column.handle_click(position, agent).await;
                 }
+                self.last_click = None;
+                column.open_action()
             }
-            Some(Column::SearchView(mut search)) => {
-                search.refresh();
-                self.column.push(Column::SearchView(search));
+            _ => return AppEvent::None,
+        };
+        let Some(column) = workspace.stacks[i].last_mut() else {
+            return AppEvent::None;
+        };
+        return column.handle_action(action, agent).await;
+    }
+
+    // Refreshes hidden workspaces too, so that posts sent from a composer
+    // finish there
+    pub async fn refresh(&mut self) {
+        for workspace in &mut self.workspaces {
+            for stack in &mut workspace.stacks {
+                refresh_stack(stack).await;
             }
-            _ => {
-                self.column.push(last.unwrap());
+        }
+
+        if post_manager!().over_capacity() {
+            let mut referenced = HashSet::new();
+            self.workspaces
+                .iter()
+                .flat_map(|w| &w.stacks)
+                .flat_map(|s| &s.stack)
+                .for_each(|c| c.post_uris(&mut referenced));
+            post_manager!().evict(&referenced);
+        }
+    }
+}
+
+async fn open_profile(actor: &str, agent: Client) -> AppEvent {
+    let did = match command::resolve_actor(actor, &agent).await {
+        Ok(did) => did,
+        Err(e) => {
+            log::error!("{}", e);
+            return AppEvent::None;
+        }
+    };
+    return ProfilePage::open(did, agent).await;
+}
+
+async fn filter_feed(
+    feed: &UpdatingFeed,
+    rule: FilterRule,
+    agent: &Client,
+) -> Result<(), String> {
+    // adds the account if missing, removes it otherwise
+    fn toggle(accounts: &mut Vec<Did>, did: Did) {
+        match accounts.iter().position(|d| *d == did) {
+            Some(i) => {
+                accounts.remove(i);
             }
+            None => accounts.push(did),
+        }
+    }
+
+    let mut filter = feed.filter.lock().unwrap().clone();
+    match rule {
+        FilterRule::Show => {
+            log::info!("Feed filter: {}", filter.describe());
+            return Ok(());
+        }
+        FilterRule::Reset => {
+            feed.reset_filter();
+            return Ok(());
+        }
+        FilterRule::Reposts(None) => filter.hide_reposts = !filter.hide_reposts,
+        FilterRule::Reposts(Some(actor)) => {
+            let did = command::resolve_actor(&actor, agent).await?;
+            toggle(&mut filter.hide_reposts_by, did);
+        }
+        FilterRule::Replies(replies) => filter.replies = Some(replies),
+        FilterRule::Quotes => filter.hide_quotes = !filter.hide_quotes,
+        FilterRule::Langs(langs) => {
+            let invalid = langs
+                .iter()
+                .filter(|l| Language::new(l.to_string()).is_err())
+                .collect::<Vec<_>>();
+            if !invalid.is_empty() {
+                return Err(format!("Langs {:?} are invalid", invalid));
+            }
+            filter.langs = langs;
+        }
+        FilterRule::Hide(actor) => {
+            let did = command::resolve_actor(&actor, agent).await?;
+            toggle(&mut filter.hide_accounts, did);
+        }
+    }
+    feed.set_filter(filter);
+    return Ok(());
+}
+
+async fn refresh_stack(stack: &mut ColumnStack) {
+    let last = stack.pop();
+    if last.is_none() {
+        return;
+    }
+    match last {
+        Some(Column::Composer(mut composer)) => {
+            if !composer.post_finished() {
+                stack.push(Column::Composer(composer));
+            }
+        }
+        Some(Column::SearchView(mut search)) => {
+            search.refresh();
+            stack.push(Column::SearchView(search));
         }
+        _ => {
+            stack.push(last.unwrap());
+        }
+    }
+}
+
+// Draws the names of the columns in the stack and the top column below them
+pub fn render_stack(
+    f: &mut Frame,
+    stack: &mut ColumnStack,
+    area: Rect,
+    focused: bool,
+) {
+    let [top_area, main_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)])
+            .areas(area);
+
+    let item_style = if focused {
+        Style::default().bg(Color::Rgb(45, 50, 55))
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    let mut top_items = stack
+        .stack
+        .iter()
+        .map(Column::name)
+        .map(|c| Span::styled(c, item_style))
+        .peekable();
+    let mut top = Line::from(top_items.next().unwrap_or_default());
+    while top_items.peek().is_some() {
+        top += Span::styled(" > ", Color::DarkGray);
+        top += top_items.next().unwrap();
+    }
+    f.render_widget(top, top_area);
+
+    let last = stack.pop();
+    let (mut modal, mut last) = if let Some(Column::FacetModal(f)) = last {
+        (Some(f), stack.pop())
+    } else {
+        (None, last)
+    };
+
+    match &mut last {
+        None => {}
+        Some(Column::UpdatingFeed(feed)) => {
+            f.render_widget(feed, main_area);
+        }
+        Some(Column::Thread(thread)) => {
+            f.render_widget(thread, main_area);
+        }
+        Some(Column::Composer(composer)) => {
+            f.render_widget(composer.as_mut(), main_area);
+        }
+        Some(Column::FacetModal(_)) => {
+            panic!("FacetModal on top of FacetModal?")
+        }
+        Some(Column::Notifications(notifications)) => {
+            f.render_widget(notifications, main_area);
+        }
+        Some(Column::Interactions(interactions)) => {
+            f.render_widget(interactions, main_area);
+        }
+        Some(Column::ActorLists(lists)) => {
+            f.render_widget(lists, main_area);
+        }
+        Some(Column::ProfilePage(profile)) => {
+            f.render_widget(profile, main_area);
+        }
+        Some(Column::SearchView(search)) => {
+            f.render_widget(search.as_mut(), main_area);
+        }
+        Some(Column::RawView(raw)) => {
+            f.render_widget(raw, main_area);
+        }
+        Some(Column::Stats(stats)) => {
+            f.render_widget(stats, main_area);
+        }
+        Some(Column::Outbox(outbox)) => {
+            f.render_widget(outbox, main_area);
+        }
+        Some(Column::SavedFeeds(saved)) => {
+            f.render_widget(saved, main_area);
+        }
+    }
+
+    match &mut modal {
+        None => {}
+        Some(modal) => f.render_widget(modal, main_area),
+    }
+
+    if let Some(last) = last {
+        stack.push(last);
+    }
+    if let Some(modal) = modal {
+        stack.push(Column::FacetModal(modal));
     }
 }
 
@@ -178,34 +672,158 @@ impl EventReceiver for &mut App {
     async fn handle_events(
         self,
         event: event::Event,
-        agent: BskyAgent,
+        agent: Client,
     ) -> AppEvent {
-        match self.column.last_mut() {
-            None => return AppEvent::None,
-            Some(Column::UpdatingFeed(feed)) => {
-                return feed.handle_events(event, agent).await
+        if self.command_line.is_some() {
+            return self.handle_command_line(event, agent).await;
+        }
+        if let Event::Mouse(mouse) = event {
+            return self.handle_mouse(mouse, agent).await;
+        }
+
+        let workspace = &mut self.workspaces[self.current];
+        let Some(column) = workspace.focused_stack().last_mut() else {
+            return AppEvent::None;
+        };
+        let (contexts, key) = match (column.keymap_contexts(), &event) {
+            (Some(contexts), Event::Key(key))
+                if key.kind == KeyEventKind::Press =>
+            {
+                (contexts, KeyPress::from(key))
             }
-            Some(Column::Thread(thread)) => {
-                return thread.handle_events(event, agent).await
+            (Some(_), _) => return AppEvent::None,
+            (None, _) => {
+                self.pending_keys = PendingKeys::default();
+                return column.handle_events(event, agent).await;
             }
-            Some(Column::Composer(composer)) => {
-                return composer.handle_events(event, agent).await
+        };
+        let Some(action) = self.pending_keys.push(&keymap!(), &contexts, key)
+        else {
+            return AppEvent::None;
+        };
+        if let Some(i) = action.workspace() {
+            if i < self.workspaces.len() {
+                self.current = i;
             }
-            Some(Column::FacetModal(modal)) => {
-                return modal.handle_events(event, agent).await
+            return AppEvent::None;
+        }
+        match action {
+            Action::CommandLine => {
+                self.command_line = Some(Vim::new(|i| {
+                    !matches!(i, Input { key: Key::Enter, .. })
+                }));
+                return AppEvent::None;
             }
-            Some(Column::Notifications(notifications)) => {
-                return notifications.handle_events(event, agent).await
+            Action::FocusLeft => {
+                workspace.focus_left();
+                return AppEvent::None;
             }
-            Some(Column::PostLikes(post_likes)) => {
-                return post_likes.handle_events(event, agent).await
+            Action::FocusRight => {
+                workspace.focus_right();
+                return AppEvent::None;
             }
-            Some(Column::ProfilePage(profile)) => {
-                return profile.handle_events(event, agent).await
+            _ => return column.handle_action(action, agent).await,
+        }
+    }
+}
+
+impl EventReceiver for &mut Column {
+    async fn handle_events(
+        self,
+        event: event::Event,
+        agent: Client,
+    ) -> AppEvent {
+        match self {
+            Column::Composer(composer) => {
+                return composer.handle_events(event, agent).await
             }
-            Some(Column::SearchView(search)) => {
+            Column::SearchView(search) => {
                 return search.handle_events(event, agent).await
             }
-        };
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl ClickReceiver for &mut Column {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        match self {
+            Column::UpdatingFeed(feed) => {
+                return feed.handle_click(position, agent).await
+            }
+            Column::Thread(thread) => {
+                return thread.handle_click(position, agent).await
+            }
+            Column::Composer(_) => return AppEvent::None,
+            Column::FacetModal(modal) => {
+                return modal.handle_click(position, agent).await
+            }
+            Column::Notifications(notifications) => {
+                return notifications.handle_click(position, agent).await
+            }
+            Column::Interactions(interactions) => {
+                return interactions.handle_click(position, agent).await
+            }
+            Column::ActorLists(lists) => {
+                return lists.handle_click(position, agent).await
+            }
+            Column::ProfilePage(profile) => {
+                return profile.handle_click(position, agent).await
+            }
+            Column::SearchView(search) => {
+                return search.handle_click(position, agent).await
+            }
+            Column::Outbox(outbox) => {
+                return outbox.handle_click(position, agent).await
+            }
+            Column::SavedFeeds(saved) => {
+                return saved.handle_click(position, agent).await
+            }
+            Column::RawView(_) | Column::Stats(_) => return AppEvent::None,
+        }
+    }
+}
+
+impl ActionReceiver for &mut Column {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match self {
+            Column::UpdatingFeed(feed) => {
+                return feed.handle_action(action, agent).await
+            }
+            Column::Thread(thread) => {
+                return thread.handle_action(action, agent).await
+            }
+            Column::Composer(_) => return AppEvent::None,
+            Column::FacetModal(modal) => {
+                return modal.handle_action(action, agent).await
+            }
+            Column::Notifications(notifications) => {
+                return notifications.handle_action(action, agent).await
+            }
+            Column::Interactions(interactions) => {
+                return interactions.handle_action(action, agent).await
+            }
+            Column::ActorLists(lists) => {
+                return lists.handle_action(action, agent).await
+            }
+            Column::ProfilePage(profile) => {
+                return profile.handle_action(action, agent).await
+            }
+            Column::SearchView(search) => {
+                return search.handle_action(action, agent).await
+            }
+            Column::RawView(raw) => {
+                return raw.handle_action(action, agent).await
+            }
+            Column::Stats(stats) => {
+                return stats.handle_action(action, agent).await
+            }
+            Column::Outbox(outbox) => {
+                return outbox.handle_action(action, agent).await
+            }
+            Column::SavedFeeds(saved) => {
+                return saved.handle_action(action, agent).await
+            }
+        }
     }
 }
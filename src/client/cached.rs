@@ -0,0 +1,500 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use atrium_api::{
+    app::bsky::{
+        actor::{
+            get_preferences, get_profile, put_preferences,
+            search_actors_typeahead,
+        },
+        feed::{
+            get_actor_feeds, get_actor_likes, get_author_feed, get_feed,
+            get_feed_generators, get_likes, get_list_feed, get_post_thread,
+            get_posts, get_quotes, get_reposted_by, get_timeline, search_posts,
+        },
+        graph::{get_list, get_lists},
+        notification::{list_notifications, update_seen},
+    },
+    com::atproto::{
+        identity::resolve_handle,
+        repo::{create_record, upload_blob},
+    },
+    record::KnownRecord,
+    types::string::{AtIdentifier, Did},
+};
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{Api, Client};
+
+// cached threads, profiles, author feeds, feeds and lists kept on disk each
+const MAX_ENTRIES: usize = 500;
+
+// Keeps the last response of the timeline, threads, author feeds, feeds,
+// lists and profiles as JSON files, and answers with them when a request fails.
+// Without an inner client every request fails, which is how tsky runs
+// offline. Each account has its own directory.
+pub struct CachedApi {
+    inner: Option<Client>,
+    did: Did,
+    dir: PathBuf,
+    // set while what is shown comes from the cache because the AppView could
+    // not be reached
+    stale: AtomicBool,
+    // hash of the JSON last written to each file
+    written: Mutex<HashMap<PathBuf, u64>>,
+}
+
+impl CachedApi {
+    pub async fn new(inner: Client, dir: PathBuf) -> Result<CachedApi, String> {
+        let did = inner.did().await?;
+        return Ok(CachedApi {
+            inner: Some(inner),
+            dir: account_dir(&dir, &did),
+            did,
+            stale: AtomicBool::new(false),
+            written: Mutex::new(HashMap::new()),
+        });
+    }
+
+    pub fn offline(did: Did, dir: PathBuf) -> CachedApi {
+        return CachedApi {
+            inner: None,
+            dir: account_dir(&dir, &did),
+            did,
+            stale: AtomicBool::new(true),
+            written: Mutex::new(HashMap::new()),
+        };
+    }
+
+    fn inner(&self) -> Result<&Client, String> {
+        return self.inner.as_ref().ok_or("Offline".to_string());
+    }
+
+    fn path(&self, kind: &str, key: &str) -> PathBuf {
+        let key = key
+            .chars()
+            .map(
+                |c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' },
+            )
+            .collect::<String>();
+        return self.dir.join(kind).join(format!("{}.json", key));
+    }
+
+    // Stores a successful response, or answers with the stored one
+    async fn cached<T>(
+        &self,
+        path: PathBuf,
+        live: Result<T, String>,
+    ) -> Result<T, String>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let e = match live {
+            Ok(output) => {
+                self.stale.store(false, Ordering::Relaxed);
+                if let Err(e) = self.write(&path, &output).await {
+                    log::warn!("Cannot write cache {}: {}", path.display(), e);
+                }
+                return Ok(output);
+            }
+            Err(e) => e,
+        };
+        let Some(output) = read(&path).await else {
+            return Err(e);
+        };
+        if !self.stale.swap(true, Ordering::Relaxed) {
+            log::warn!("{}, showing cached posts", e);
+        }
+        return Ok(output);
+    }
+
+    async fn write<T: Serialize>(
+        &self,
+        path: &Path,
+        output: &T,
+    ) -> Result<(), String> {
+        let json = serde_json::to_vec(output).map_err(|e| e.to_string())?;
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.written.lock().unwrap().get(path) == Some(&hash) {
+            return Ok(());
+        }
+
+        let dir = path.parent().unwrap();
+        tokio::fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+        tokio::fs::write(path, json).await.map_err(|e| e.to_string())?;
+        self.written.lock().unwrap().insert(path.to_path_buf(), hash);
+        if dir != self.dir {
+            prune(dir).await;
+        }
+        return Ok(());
+    }
+}
+
+fn account_dir(dir: &Path, did: &Did) -> PathBuf {
+    return dir.join(did.as_str().replace(':', "_"));
+}
+
+async fn read<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let json = tokio::fs::read(path).await.ok()?;
+    return serde_json::from_slice(&json).ok();
+}
+
+// Removes the least recently written entries over MAX_ENTRIES
+async fn prune(dir: &Path) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    let mut files = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(modified) = entry.metadata().await.and_then(|m| m.modified())
+        else {
+            continue;
+        };
+        files.push((modified, entry.path()));
+    }
+    if files.len() <= MAX_ENTRIES {
+        return;
+    }
+    files.sort();
+    for (_, path) in &files[..files.len() - MAX_ENTRIES] {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
+
+fn identifier(actor: &AtIdentifier) -> String {
+    match actor {
+        AtIdentifier::Did(did) => did.to_string(),
+        AtIdentifier::Handle(handle) => handle.to_string(),
+    }
+}
+
+impl Api for CachedApi {
+    fn did(&self) -> BoxFuture<'_, Result<Did, String>> {
+        Box::pin(async move { Ok(self.did.clone()) })
+    }
+
+    fn get_timeline(
+        &self,
+        params: get_timeline::Parameters,
+    ) -> BoxFuture<'_, Result<get_timeline::Output, String>> {
+        Box::pin(async move {
+            // only the newest page is kept, older pages are fetched again
+            if params.cursor.is_some() {
+                return self.inner()?.get_timeline(params).await;
+            }
+            let live = match self.inner() {
+                Ok(inner) => inner.get_timeline(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(self.path("", "timeline"), live).await
+        })
+    }
+
+    fn get_post_thread(
+        &self,
+        params: get_post_thread::Parameters,
+    ) -> BoxFuture<'_, Result<get_post_thread::Output, String>> {
+        Box::pin(async move {
+            let path = self.path("threads", &params.uri);
+            let live = match self.inner() {
+                Ok(inner) => inner.get_post_thread(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn get_posts(
+        &self,
+        params: get_posts::Parameters,
+    ) -> BoxFuture<'_, Result<get_posts::Output, String>> {
+        Box::pin(async move { self.inner()?.get_posts(params).await })
+    }
+
+    fn get_author_feed(
+        &self,
+        params: get_author_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_author_feed::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return self.inner()?.get_author_feed(params).await;
+            }
+            // each filter is a tab of the profile page
+            let key = format!(
+                "{}.{}",
+                identifier(&params.actor),
+                params.filter.as_deref().unwrap_or("posts_with_replies")
+            );
+            let path = self.path("author_feeds", &key);
+            let live = match self.inner() {
+                Ok(inner) => inner.get_author_feed(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn get_actor_likes(
+        &self,
+        params: get_actor_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_likes::Output, String>> {
+        Box::pin(async move { self.inner()?.get_actor_likes(params).await })
+    }
+
+    fn get_actor_feeds(
+        &self,
+        params: get_actor_feeds::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_feeds::Output, String>> {
+        Box::pin(async move { self.inner()?.get_actor_feeds(params).await })
+    }
+
+    fn get_feed(
+        &self,
+        params: get_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return self.inner()?.get_feed(params).await;
+            }
+            let path = self.path("feeds", &params.feed);
+            let live = match self.inner() {
+                Ok(inner) => inner.get_feed(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn get_list_feed(
+        &self,
+        params: get_list_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_list_feed::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return self.inner()?.get_list_feed(params).await;
+            }
+            let path = self.path("feeds", &params.list);
+            let live = match self.inner() {
+                Ok(inner) => inner.get_list_feed(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn get_lists(
+        &self,
+        params: get_lists::Parameters,
+    ) -> BoxFuture<'_, Result<get_lists::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return self.inner()?.get_lists(params).await;
+            }
+            let path = self.path("lists", &identifier(&params.actor));
+            let live = match self.inner() {
+                Ok(inner) => inner.get_lists(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn get_feed_generators(
+        &self,
+        params: get_feed_generators::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed_generators::Output, String>> {
+        Box::pin(async move { self.inner()?.get_feed_generators(params).await })
+    }
+
+    fn get_list(
+        &self,
+        params: get_list::Parameters,
+    ) -> BoxFuture<'_, Result<get_list::Output, String>> {
+        Box::pin(async move { self.inner()?.get_list(params).await })
+    }
+
+    fn get_preferences(
+        &self,
+        params: get_preferences::Parameters,
+    ) -> BoxFuture<'_, Result<get_preferences::Output, String>> {
+        Box::pin(async move { self.inner()?.get_preferences(params).await })
+    }
+
+    fn put_preferences(
+        &self,
+        input: put_preferences::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move { self.inner()?.put_preferences(input).await })
+    }
+
+    fn get_quotes(
+        &self,
+        params: get_quotes::Parameters,
+    ) -> BoxFuture<'_, Result<get_quotes::Output, String>> {
+        Box::pin(async move { self.inner()?.get_quotes(params).await })
+    }
+
+    fn get_reposted_by(
+        &self,
+        params: get_reposted_by::Parameters,
+    ) -> BoxFuture<'_, Result<get_reposted_by::Output, String>> {
+        Box::pin(async move { self.inner()?.get_reposted_by(params).await })
+    }
+
+    fn get_likes(
+        &self,
+        params: get_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_likes::Output, String>> {
+        Box::pin(async move { self.inner()?.get_likes(params).await })
+    }
+
+    fn get_profile(
+        &self,
+        params: get_profile::Parameters,
+    ) -> BoxFuture<'_, Result<get_profile::Output, String>> {
+        Box::pin(async move {
+            let path = self.path("profiles", &identifier(&params.actor));
+            let live = match self.inner() {
+                Ok(inner) => inner.get_profile(params).await,
+                Err(e) => Err(e),
+            };
+            self.cached(path, live).await
+        })
+    }
+
+    fn search_actors_typeahead(
+        &self,
+        params: search_actors_typeahead::Parameters,
+    ) -> BoxFuture<'_, Result<search_actors_typeahead::Output, String>> {
+        Box::pin(
+            async move { self.inner()?.search_actors_typeahead(params).await },
+        )
+    }
+
+    fn search_posts(
+        &self,
+        params: search_posts::Parameters,
+    ) -> BoxFuture<'_, Result<search_posts::Output, String>> {
+        Box::pin(async move { self.inner()?.search_posts(params).await })
+    }
+
+    fn list_notifications(
+        &self,
+        params: list_notifications::Parameters,
+    ) -> BoxFuture<'_, Result<list_notifications::Output, String>> {
+        Box::pin(async move { self.inner()?.list_notifications(params).await })
+    }
+
+    fn update_seen(
+        &self,
+        input: update_seen::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move { self.inner()?.update_seen(input).await })
+    }
+
+    fn resolve_handle(
+        &self,
+        params: resolve_handle::Parameters,
+    ) -> BoxFuture<'_, Result<resolve_handle::Output, String>> {
+        Box::pin(async move { self.inner()?.resolve_handle(params).await })
+    }
+
+    fn create_record(
+        &self,
+        record: KnownRecord,
+    ) -> BoxFuture<'_, Result<create_record::Output, String>> {
+        Box::pin(async move { self.inner()?.create_record(record).await })
+    }
+
+    fn delete_record(&self, uri: String) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move { self.inner()?.delete_record(uri).await })
+    }
+
+    fn upload_blob(
+        &self,
+        data: Vec<u8>,
+    ) -> BoxFuture<'_, Result<upload_blob::Output, String>> {
+        Box::pin(async move { self.inner()?.upload_blob(data).await })
+    }
+
+    // Stale until the first request returns
+    fn cached_timeline(&self) -> BoxFuture<'_, Option<get_timeline::Output>> {
+        Box::pin(async move {
+            let output = read(&self.path("", "timeline")).await;
+            if output.is_some() {
+                self.stale.store(true, Ordering::Relaxed);
+            }
+            output
+        })
+    }
+
+    fn is_stale(&self) -> bool {
+        return self.stale.load(Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    fn timeline_params() -> get_timeline::Parameters {
+        get_timeline::ParametersData {
+            algorithm: None,
+            cursor: None,
+            limit: None,
+        }
+        .into()
+    }
+
+    #[tokio::test]
+    async fn offline_reads_cache() {
+        let dir = std::env::temp_dir()
+            .join(format!("tsky-cache-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let online = CachedApi::new(Arc::new(FakeApi::new()), dir.clone())
+            .await
+            .unwrap();
+        let live = online.get_timeline(timeline_params()).await.unwrap();
+
+        let offline = CachedApi::offline(online.did.clone(), dir.clone());
+        let cached = offline.get_timeline(timeline_params()).await.unwrap();
+        assert_eq!(cached, live);
+        assert!(offline.cached_timeline().await.is_some());
+        assert!(offline.is_stale());
+        assert!(!online.is_stale());
+        // nothing saved for this post
+        let thread = offline
+            .get_post_thread(
+                get_post_thread::ParametersData {
+                    depth: None,
+                    parent_height: None,
+                    uri: "at://did:plc:none/app.bsky.feed.post/none".into(),
+                }
+                .into(),
+            )
+            .await;
+        assert!(thread.is_err());
+
+        // another account sees nothing of this one
+        let other = Did::new("did:plc:other".to_string()).unwrap();
+        let other = CachedApi::offline(other, dir.clone());
+        assert!(other.get_timeline(timeline_params()).await.is_err());
+        assert!(other.cached_timeline().await.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
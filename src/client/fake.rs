@@ -0,0 +1,797 @@
+use std::sync::Mutex;
+
+use atrium_api::{
+    app::bsky::{
+        actor::{
+            get_preferences, get_profile, put_preferences,
+            search_actors_typeahead,
+        },
+        feed::{
+            get_actor_feeds, get_actor_likes, get_author_feed, get_feed,
+            get_feed_generators, get_likes, get_list_feed, get_post_thread,
+            get_posts, get_quotes, get_reposted_by, get_timeline, search_posts,
+        },
+        graph::{get_list, get_lists},
+        notification::{list_notifications, update_seen},
+    },
+    com::atproto::{
+        identity::resolve_handle,
+        repo::{create_record, upload_blob},
+    },
+    record::KnownRecord,
+    types::string::{AtIdentifier, Did},
+};
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::Api;
+
+const TIMELINE: &str = include_str!("fixtures/timeline.json");
+// posts not in the timeline, shown in threads and profiles
+const POSTS: &str = include_str!("fixtures/posts.json");
+const PROFILES: &str = include_str!("fixtures/profiles.json");
+const NOTIFICATIONS: &str = include_str!("fixtures/notifications.json");
+const LIKES: &str = include_str!("fixtures/likes.json");
+
+// fixtures are dated shortly before this
+pub const NOW: &str = "2024-12-01T12:00:00Z";
+
+const ME: &str = "did:plc:tskyme";
+const CID: &str = "bafyreiclp443lavogvhj3d2ob2cxbfuscni2k5jk7bebjzg7khl3esabwq";
+const BLOB_CID: &str =
+    "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+const THREAD_VIEW_POST: &str = "app.bsky.feed.defs#threadViewPost";
+
+// In-process AppView serving fixture JSON. Threads and author feeds are
+// built from the known posts, records created are forgotten.
+pub struct FakeApi {
+    timeline: Value,
+    posts: Vec<Value>,
+    profiles: Vec<Value>,
+    notifications: Value,
+    likes: Value,
+    // replaced by putPreferences
+    preferences: Mutex<Value>,
+    // number of records created, used as record keys
+    created: Mutex<u64>,
+    // uris of the generators and lists asked for, in order
+    requested: Mutex<Vec<String>>,
+}
+
+fn parse(fixture: &str) -> Value {
+    serde_json::from_str(fixture).expect("Invalid fixture")
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+fn identifier(actor: &AtIdentifier) -> String {
+    match actor {
+        AtIdentifier::Did(did) => did.to_string(),
+        AtIdentifier::Handle(handle) => handle.to_string(),
+    }
+}
+
+fn is_actor(value: &Value, actor: &str) -> bool {
+    value["did"] == actor || value["handle"] == actor
+}
+
+impl FakeApi {
+    pub fn new() -> FakeApi {
+        let timeline = parse(TIMELINE);
+        let mut posts = timeline["feed"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["post"].clone())
+            .collect::<Vec<_>>();
+        posts.extend(parse(POSTS)["posts"].as_array().unwrap().clone());
+        posts.sort_by(|l, r| {
+            r["indexedAt"].as_str().cmp(&l["indexedAt"].as_str())
+        });
+
+        FakeApi {
+            timeline,
+            posts,
+            profiles: parse(PROFILES)["profiles"].as_array().unwrap().clone(),
+            notifications: parse(NOTIFICATIONS),
+            likes: parse(LIKES),
+            preferences: Mutex::new(json!([
+                {
+                    "$type": "app.bsky.actor.defs#savedFeedsPrefV2",
+                    "items": [
+                        {
+                            "id": "1",
+                            "pinned": true,
+                            "type": "timeline",
+                            "value": "following",
+                        },
+                        {
+                            "id": "2",
+                            "pinned": true,
+                            "type": "feed",
+                            "value": "at://did:plc:alice/app.bsky.feed.generator/cats",
+                        },
+                        {
+                            "id": "3",
+                            "pinned": false,
+                            "type": "list",
+                            "value": "at://did:plc:bob/app.bsky.graph.list/people",
+                        },
+                    ],
+                },
+                {
+                    "$type": "app.bsky.actor.defs#adultContentPref",
+                    "enabled": false,
+                },
+                {
+                    "$type": "app.bsky.actor.defs#mutedWordsPref",
+                    "items": [
+                        {
+                            "value": "spoilers",
+                            "targets": ["content", "tag"],
+                            "actorTarget": "exclude-following",
+                        },
+                    ],
+                },
+                {
+                    "$type": "app.bsky.actor.defs#hiddenPostsPref",
+                    "items": [],
+                },
+            ])),
+            created: Mutex::new(0),
+            requested: Mutex::new(vec![]),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn requested(&self) -> Vec<String> {
+        self.requested.lock().unwrap().clone()
+    }
+
+    fn post(&self, uri: &str) -> Option<&Value> {
+        self.posts.iter().find(|p| p["uri"] == uri)
+    }
+
+    fn profile(&self, actor: &str) -> Option<&Value> {
+        self.profiles.iter().find(|p| is_actor(p, actor))
+    }
+
+    // Creator of a record, falling back to the first profile for accounts
+    // without a fixture
+    fn creator(&self, uri: &str) -> &Value {
+        let did = uri.split('/').nth(2).unwrap_or_default();
+        self.profile(did).unwrap_or(&self.profiles[0])
+    }
+
+    fn generator(&self, uri: &str) -> Value {
+        json!({
+            "uri": uri,
+            "cid": CID,
+            "did": "did:web:feeds.tsky.test",
+            "creator": self.creator(uri),
+            "displayName": uri.rsplit('/').next().unwrap_or_default(),
+            "indexedAt": NOW,
+        })
+    }
+
+    fn list(&self, uri: &str) -> Value {
+        json!({
+            "uri": uri,
+            "cid": CID,
+            "creator": self.creator(uri),
+            "name": uri.rsplit('/').next().unwrap_or_default(),
+            "purpose": "app.bsky.graph.defs#curatelist",
+            "indexedAt": NOW,
+        })
+    }
+
+    fn thread(&self, uri: &str) -> Option<Value> {
+        let mut thread = self.parent_thread(uri)?;
+        let replies = self
+            .posts
+            .iter()
+            .filter(|p| p["record"]["reply"]["parent"]["uri"] == uri)
+            .map(|p| json!({ "$type": THREAD_VIEW_POST, "post": p }))
+            .collect::<Vec<_>>();
+        thread["replies"] = Value::Array(replies);
+        Some(thread)
+    }
+
+    fn parent_thread(&self, uri: &str) -> Option<Value> {
+        let post = self.post(uri)?;
+        let mut thread = json!({ "$type": THREAD_VIEW_POST, "post": post });
+        if let Some(parent) = post["record"]["reply"]["parent"]["uri"].as_str()
+        {
+            thread["parent"] = self.parent_thread(parent).unwrap_or(json!({
+                "$type": "app.bsky.feed.defs#notFoundPost",
+                "uri": parent,
+                "notFound": true,
+            }));
+        }
+        Some(thread)
+    }
+}
+
+impl Api for FakeApi {
+    fn did(&self) -> BoxFuture<'_, Result<Did, String>> {
+        Box::pin(async move { Ok(Did::new(ME.to_string()).unwrap()) })
+    }
+
+    fn get_timeline(
+        &self,
+        params: get_timeline::Parameters,
+    ) -> BoxFuture<'_, Result<get_timeline::Output, String>> {
+        Box::pin(async move {
+            // Everything fits in the first page
+            if params.cursor.is_some() {
+                return decode(json!({ "feed": [] }));
+            }
+            decode(self.timeline.clone())
+        })
+    }
+
+    fn get_post_thread(
+        &self,
+        params: get_post_thread::Parameters,
+    ) -> BoxFuture<'_, Result<get_post_thread::Output, String>> {
+        Box::pin(async move {
+            let thread = self
+                .thread(&params.uri)
+                .ok_or(format!("Post not found: {}", params.uri))?;
+            decode(json!({ "thread": thread }))
+        })
+    }
+
+    fn get_posts(
+        &self,
+        params: get_posts::Parameters,
+    ) -> BoxFuture<'_, Result<get_posts::Output, String>> {
+        Box::pin(async move {
+            let posts = params
+                .uris
+                .iter()
+                .filter_map(|uri| self.post(uri))
+                .collect::<Vec<_>>();
+            decode(json!({ "posts": posts }))
+        })
+    }
+
+    fn get_author_feed(
+        &self,
+        params: get_author_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_author_feed::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "feed": [] }));
+            }
+            let actor = identifier(&params.actor);
+            let filter = params.filter.as_deref();
+            let feed = self
+                .posts
+                .iter()
+                .filter(|p| is_actor(&p["author"], &actor))
+                .filter(|p| {
+                    let reply = &p["record"]["reply"];
+                    let embed = p["record"]["embed"]["$type"].as_str();
+                    let video = embed == Some("app.bsky.embed.video");
+                    match filter {
+                        Some("posts_no_replies") => reply.is_null(),
+                        Some("posts_and_author_threads") => {
+                            reply.is_null()
+                                || reply["parent"]["uri"]
+                                    .as_str()
+                                    .is_some_and(|uri| uri.contains(&actor))
+                        }
+                        Some("posts_with_media") => {
+                            video || embed == Some("app.bsky.embed.images")
+                        }
+                        Some("posts_with_video") => video,
+                        _ => true,
+                    }
+                })
+                .map(|p| json!({ "post": p }))
+                .collect::<Vec<_>>();
+            decode(json!({ "feed": feed }))
+        })
+    }
+
+    // Posts the actor is listed as liking in the likes fixture
+    fn get_actor_likes(
+        &self,
+        params: get_actor_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_likes::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "feed": [] }));
+            }
+            let actor = identifier(&params.actor);
+            let liked = |uri: &Value| {
+                let likes = &self.likes["likes"][uri.as_str().unwrap_or("")];
+                likes.as_array().is_some_and(|likes| {
+                    likes.iter().any(|l| is_actor(&l["actor"], &actor))
+                })
+            };
+            let feed = self
+                .posts
+                .iter()
+                .filter(|p| liked(&p["uri"]))
+                .map(|p| json!({ "post": p }))
+                .collect::<Vec<_>>();
+            decode(json!({ "feed": feed }))
+        })
+    }
+
+    // Every known account has made a "cats" feed
+    fn get_actor_feeds(
+        &self,
+        params: get_actor_feeds::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_feeds::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "feeds": [] }));
+            }
+            let actor = identifier(&params.actor);
+            let Some(creator) = self.profile(&actor) else {
+                return decode(json!({ "feeds": [] }));
+            };
+            let did = creator["did"].as_str().unwrap();
+            let uri = format!("at://{}/app.bsky.feed.generator/cats", did);
+            let mut generator = self.generator(&uri);
+            generator["description"] = json!("Posts with cats");
+            decode(json!({ "feeds": [generator] }))
+        })
+    }
+
+    // Every feed generator serves the known posts that are not replies
+    fn get_feed(
+        &self,
+        params: get_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed::Output, String>> {
+        Box::pin(async move {
+            self.requested.lock().unwrap().push(params.feed.clone());
+            if params.cursor.is_some() {
+                return decode(json!({ "feed": [] }));
+            }
+            let feed = self
+                .posts
+                .iter()
+                .filter(|p| p["record"]["reply"].is_null())
+                .map(|p| json!({ "post": p }))
+                .collect::<Vec<_>>();
+            decode(json!({ "feed": feed }))
+        })
+    }
+
+    // Every list has the known accounts other than the user
+    fn get_list_feed(
+        &self,
+        params: get_list_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_list_feed::Output, String>> {
+        Box::pin(async move {
+            self.requested.lock().unwrap().push(params.list.clone());
+            if params.cursor.is_some() {
+                return decode(json!({ "feed": [] }));
+            }
+            let feed = self
+                .posts
+                .iter()
+                .filter(|p| !is_actor(&p["author"], ME))
+                .map(|p| json!({ "post": p }))
+                .collect::<Vec<_>>();
+            decode(json!({ "feed": feed }))
+        })
+    }
+
+    // Generators are named after their record key
+    fn get_feed_generators(
+        &self,
+        params: get_feed_generators::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed_generators::Output, String>> {
+        Box::pin(async move {
+            let feeds = params
+                .feeds
+                .iter()
+                .map(|uri| self.generator(uri))
+                .collect::<Vec<_>>();
+            decode(json!({ "feeds": feeds }))
+        })
+    }
+
+    fn get_list(
+        &self,
+        params: get_list::Parameters,
+    ) -> BoxFuture<'_, Result<get_list::Output, String>> {
+        Box::pin(async move {
+            decode(json!({ "items": [], "list": self.list(&params.list) }))
+        })
+    }
+
+    // A single curated list per actor
+    fn get_lists(
+        &self,
+        params: get_lists::Parameters,
+    ) -> BoxFuture<'_, Result<get_lists::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "lists": [] }));
+            }
+            let actor = identifier(&params.actor);
+            let Some(creator) = self.profile(&actor) else {
+                return decode(json!({ "lists": [] }));
+            };
+            let did = creator["did"].as_str().unwrap();
+            let mut list =
+                self.list(&format!("at://{}/app.bsky.graph.list/people", did));
+            list["name"] = json!("People");
+            list["description"] = json!("Accounts worth following");
+            decode(json!({ "lists": [list] }))
+        })
+    }
+
+    fn get_likes(
+        &self,
+        params: get_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_likes::Output, String>> {
+        Box::pin(async move {
+            let likes = self.likes["likes"][&params.uri].clone();
+            let likes = if likes.is_null() { json!([]) } else { likes };
+            decode(json!({ "uri": params.uri, "likes": likes }))
+        })
+    }
+
+    fn get_preferences(
+        &self,
+        _: get_preferences::Parameters,
+    ) -> BoxFuture<'_, Result<get_preferences::Output, String>> {
+        Box::pin(async move {
+            let preferences = self.preferences.lock().unwrap().clone();
+            decode(json!({ "preferences": preferences }))
+        })
+    }
+
+    fn put_preferences(
+        &self,
+        input: put_preferences::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            let preferences = serde_json::to_value(&input.preferences)
+                .map_err(|e| e.to_string())?;
+            *self.preferences.lock().unwrap() = preferences;
+            Ok(())
+        })
+    }
+
+    // Known posts embedding the post
+    fn get_quotes(
+        &self,
+        params: get_quotes::Parameters,
+    ) -> BoxFuture<'_, Result<get_quotes::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "uri": params.uri, "posts": [] }));
+            }
+            let posts = self
+                .posts
+                .iter()
+                .filter(|p| {
+                    p["record"]["embed"]["record"]["uri"] == params.uri
+                        || p["record"]["embed"]["record"]["record"]["uri"]
+                            == params.uri
+                })
+                .collect::<Vec<_>>();
+            decode(json!({ "uri": params.uri, "posts": posts }))
+        })
+    }
+
+    // Authors of the reposts in the timeline
+    fn get_reposted_by(
+        &self,
+        params: get_reposted_by::Parameters,
+    ) -> BoxFuture<'_, Result<get_reposted_by::Output, String>> {
+        Box::pin(async move {
+            let reposted_by = self.timeline["feed"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|item| {
+                    item["post"]["uri"] == params.uri
+                        && item["reason"]["$type"]
+                            == "app.bsky.feed.defs#reasonRepost"
+                })
+                .map(|item| item["reason"]["by"].clone())
+                .collect::<Vec<_>>();
+            decode(json!({ "uri": params.uri, "repostedBy": reposted_by }))
+        })
+    }
+
+    fn get_profile(
+        &self,
+        params: get_profile::Parameters,
+    ) -> BoxFuture<'_, Result<get_profile::Output, String>> {
+        Box::pin(async move {
+            let actor = identifier(&params.actor);
+            let profile = self
+                .profile(&actor)
+                .ok_or(format!("Profile not found: {}", actor))?;
+            decode(profile.clone())
+        })
+    }
+
+    fn search_actors_typeahead(
+        &self,
+        params: search_actors_typeahead::Parameters,
+    ) -> BoxFuture<'_, Result<search_actors_typeahead::Output, String>> {
+        Box::pin(async move {
+            let q = params.q.clone().unwrap_or_default().to_lowercase();
+            let actors = self
+                .profiles
+                .iter()
+                .filter(|p| {
+                    [&p["handle"], &p["displayName"]].iter().any(|s| {
+                        s.as_str()
+                            .is_some_and(|s| s.to_lowercase().contains(&q))
+                    })
+                })
+                .collect::<Vec<_>>();
+            decode(json!({ "actors": actors }))
+        })
+    }
+
+    // Words of the query are matched against the text, other than author,
+    // lang, tag, since and until the filters are ignored
+    fn search_posts(
+        &self,
+        params: search_posts::Parameters,
+    ) -> BoxFuture<'_, Result<search_posts::Output, String>> {
+        Box::pin(async move {
+            if params.cursor.is_some() {
+                return decode(json!({ "posts": [] }));
+            }
+            let words = params
+                .q
+                .split_whitespace()
+                .filter(|w| !w.contains(':'))
+                .map(|w| w.trim_matches('"').to_lowercase())
+                .collect::<Vec<_>>();
+            let author = params.author.as_ref().map(identifier);
+            let mut posts = self
+                .posts
+                .iter()
+                .filter(|p| {
+                    let record = &p["record"];
+                    let text = record["text"].as_str().unwrap_or_default();
+                    let text = text.to_lowercase();
+                    let created_at = record["createdAt"].as_str();
+                    words.iter().all(|w| text.contains(w))
+                        && author
+                            .as_ref()
+                            .is_none_or(|a| is_actor(&p["author"], a))
+                        && params.lang.as_ref().is_none_or(|l| {
+                            record["langs"]
+                                .as_array()
+                                .is_some_and(|langs| langs.contains(&json!(l)))
+                        })
+                        && params.tag.iter().flatten().all(|t| {
+                            record["tags"]
+                                .as_array()
+                                .is_some_and(|tags| tags.contains(&json!(t)))
+                        })
+                        && params
+                            .since
+                            .as_deref()
+                            .is_none_or(|s| created_at >= Some(s))
+                        && params
+                            .until
+                            .as_deref()
+                            .is_none_or(|u| created_at < Some(u))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if params.sort.as_deref() != Some("latest") {
+                posts.sort_by_key(|p| -p["likeCount"].as_i64().unwrap_or(0));
+            }
+            decode(json!({ "posts": posts }))
+        })
+    }
+
+    fn list_notifications(
+        &self,
+        _: list_notifications::Parameters,
+    ) -> BoxFuture<'_, Result<list_notifications::Output, String>> {
+        Box::pin(async move { decode(self.notifications.clone()) })
+    }
+
+    fn update_seen(
+        &self,
+        _: update_seen::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn resolve_handle(
+        &self,
+        params: resolve_handle::Parameters,
+    ) -> BoxFuture<'_, Result<resolve_handle::Output, String>> {
+        Box::pin(async move {
+            let profile = self.profile(params.handle.as_str()).ok_or(
+                format!("Unable to resolve handle {:?}", params.handle),
+            )?;
+            decode(json!({ "did": profile["did"] }))
+        })
+    }
+
+    fn create_record(
+        &self,
+        record: KnownRecord,
+    ) -> BoxFuture<'_, Result<create_record::Output, String>> {
+        Box::pin(async move {
+            let record =
+                serde_json::to_value(&record).map_err(|e| e.to_string())?;
+            let collection = record["$type"].as_str().unwrap_or("unknown");
+            let rkey = {
+                let mut created = self.created.lock().unwrap();
+                *created += 1;
+                *created
+            };
+            let uri = format!("at://{}/{}/fake{}", ME, collection, rkey);
+            decode(json!({ "uri": uri, "cid": CID }))
+        })
+    }
+
+    fn delete_record(&self, _: String) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn upload_blob(
+        &self,
+        data: Vec<u8>,
+    ) -> BoxFuture<'_, Result<upload_blob::Output, String>> {
+        Box::pin(async move {
+            decode(json!({
+                "blob": {
+                    "$type": "blob",
+                    "ref": { "$link": BLOB_CID },
+                    "mimeType": tree_magic_mini::from_u8(&data),
+                    "size": data.len(),
+                }
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use atrium_api::app::bsky::feed::get_post_thread::OutputThreadRefs;
+    use atrium_api::types::Union;
+
+    use super::*;
+    use crate::components::post::Post;
+
+    #[tokio::test]
+    async fn fixtures_decode() {
+        let api = FakeApi::new();
+        let timeline = api
+            .get_timeline(
+                get_timeline::ParametersData {
+                    algorithm: None,
+                    cursor: None,
+                    limit: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(timeline.feed.len(), 4);
+        timeline.feed.iter().for_each(|p| {
+            Post::try_from(&p.post).unwrap();
+        });
+
+        let notifications = api
+            .list_notifications(
+                list_notifications::ParametersData {
+                    cursor: None,
+                    limit: None,
+                    priority: None,
+                    reasons: None,
+                    seen_at: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(notifications.notifications.len(), 3);
+
+        let profile = api
+            .get_profile(
+                get_profile::ParametersData {
+                    actor: "alice.tsky.test".parse().unwrap(),
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(profile.did.as_str(), "did:plc:alice");
+
+        let actors = api
+            .search_actors_typeahead(
+                search_actors_typeahead::ParametersData {
+                    limit: None,
+                    q: Some("BO".to_string()),
+                    term: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(actors.actors.len(), 1);
+
+        let did = api
+            .resolve_handle(
+                resolve_handle::ParametersData {
+                    handle: "bob.tsky.test".parse().unwrap(),
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(did.did.as_str(), "did:plc:bob");
+    }
+
+    #[tokio::test]
+    async fn thread_has_parent_and_replies() {
+        let api = FakeApi::new();
+        let thread = |uri: &str| {
+            api.get_post_thread(
+                get_post_thread::ParametersData {
+                    depth: None,
+                    parent_height: None,
+                    uri: uri.to_string(),
+                }
+                .into(),
+            )
+        };
+
+        let root =
+            thread("at://did:plc:alice/app.bsky.feed.post/1").await.unwrap();
+        let Union::Refs(OutputThreadRefs::AppBskyFeedDefsThreadViewPost(root)) =
+            &root.thread
+        else {
+            panic!("Not a thread view post");
+        };
+        assert!(root.parent.is_none());
+        assert_eq!(root.replies.as_ref().unwrap().len(), 2);
+
+        let reply =
+            thread("at://did:plc:bob/app.bsky.feed.post/2").await.unwrap();
+        let Union::Refs(OutputThreadRefs::AppBskyFeedDefsThreadViewPost(reply)) =
+            &reply.thread
+        else {
+            panic!("Not a thread view post");
+        };
+        assert!(reply.parent.is_some());
+
+        assert!(thread("at://did:plc:bob/app.bsky.feed.post/404")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn created_records_have_unique_uris() {
+        let api = FakeApi::new();
+        let like = atrium_api::app::bsky::feed::like::RecordData {
+            created_at: atrium_api::types::string::Datetime::now(),
+            subject: atrium_api::com::atproto::repo::strong_ref::MainData {
+                cid: CID.parse().unwrap(),
+                uri: "at://did:plc:alice/app.bsky.feed.post/1".to_string(),
+            }
+            .into(),
+        };
+        let a = api.create_record(like.clone().into()).await.unwrap();
+        let b = api.create_record(like.into()).await.unwrap();
+        assert!(a.uri.starts_with("at://did:plc:tskyme/app.bsky.feed.like/"));
+        assert_ne!(a.uri, b.uri);
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use atrium_api::xrpc::{
+    http::{Request, Response, StatusCode},
+    HttpClient, XrpcClient,
+};
+
+// Unix time in seconds given by the last 429 response, 0 if none
+static RATE_LIMIT_RESET: AtomicU64 = AtomicU64::new(0);
+
+// When the AppView accepts requests again after answering 429
+pub fn rate_limit_reset() -> Option<SystemTime> {
+    let reset = RATE_LIMIT_RESET.load(Ordering::Relaxed);
+    let reset = UNIX_EPOCH + Duration::from_secs(reset);
+    if reset <= SystemTime::now() {
+        return None;
+    }
+    return Some(reset);
+}
+
+// HTTP client of the agent. Same as the default one, but remembers the
+// `ratelimit-reset` header of 429 responses, which atrium drops.
+#[derive(Clone)]
+pub struct RateLimitClient {
+    base_uri: String,
+    client: reqwest::Client,
+}
+
+impl RateLimitClient {
+    pub fn new(base_uri: &str) -> RateLimitClient {
+        RateLimitClient {
+            base_uri: base_uri.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl HttpClient for RateLimitClient {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let response = self.client.execute(request.try_into()?).await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let reset = response
+                .headers()
+                .get("ratelimit-reset")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+            if let Some(reset) = reset {
+                RATE_LIMIT_RESET.store(reset, Ordering::Relaxed);
+            }
+        }
+
+        let mut builder = Response::builder().status(response.status());
+        for (k, v) in response.headers() {
+            builder = builder.header(k, v);
+        }
+        let body = response.bytes().await?.to_vec();
+        return builder.body(body).map_err(Into::into);
+    }
+}
+
+impl XrpcClient for RateLimitClient {
+    fn base_uri(&self) -> String {
+        self.base_uri.clone()
+    }
+}
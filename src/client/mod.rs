@@ -0,0 +1,578 @@
+pub mod cached;
+pub mod fake;
+pub mod http;
+
+use std::sync::Arc;
+
+use atrium_api::{
+    app::bsky::{
+        actor::{
+            defs::{Preferences, PreferencesItem},
+            get_preferences, get_profile, put_preferences,
+            search_actors_typeahead,
+        },
+        feed::{
+            get_actor_feeds, get_actor_likes, get_author_feed, get_feed,
+            get_feed_generators, get_likes, get_list_feed, get_post_thread,
+            get_posts, get_quotes, get_reposted_by, get_timeline, search_posts,
+        },
+        graph::{get_list, get_lists},
+        notification::{list_notifications, update_seen},
+    },
+    com::atproto::{
+        identity::resolve_handle,
+        repo::{create_record, upload_blob},
+    },
+    record::KnownRecord,
+    types::{string::Did, Union},
+};
+use bsky_sdk::BskyAgent;
+use futures::future::BoxFuture;
+use http::RateLimitClient;
+
+pub type Client = Arc<dyn Api + Send + Sync>;
+
+pub type Agent = BskyAgent<RateLimitClient>;
+
+// XRPC calls made by tsky. Errors are turned into strings, as everywhere
+// else in the client.
+pub trait Api {
+    // did of the logged in user, an error once the session is gone
+    fn did(&self) -> BoxFuture<'_, Result<Did, String>>;
+
+    fn get_timeline(
+        &self,
+        params: get_timeline::Parameters,
+    ) -> BoxFuture<'_, Result<get_timeline::Output, String>>;
+
+    fn get_post_thread(
+        &self,
+        params: get_post_thread::Parameters,
+    ) -> BoxFuture<'_, Result<get_post_thread::Output, String>>;
+
+    fn get_posts(
+        &self,
+        params: get_posts::Parameters,
+    ) -> BoxFuture<'_, Result<get_posts::Output, String>>;
+
+    fn get_author_feed(
+        &self,
+        params: get_author_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_author_feed::Output, String>>;
+
+    fn get_actor_likes(
+        &self,
+        params: get_actor_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_likes::Output, String>>;
+
+    fn get_actor_feeds(
+        &self,
+        params: get_actor_feeds::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_feeds::Output, String>>;
+
+    fn get_feed(
+        &self,
+        params: get_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed::Output, String>>;
+
+    fn get_list_feed(
+        &self,
+        params: get_list_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_list_feed::Output, String>>;
+
+    fn get_lists(
+        &self,
+        params: get_lists::Parameters,
+    ) -> BoxFuture<'_, Result<get_lists::Output, String>>;
+
+    fn get_feed_generators(
+        &self,
+        params: get_feed_generators::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed_generators::Output, String>>;
+
+    fn get_list(
+        &self,
+        params: get_list::Parameters,
+    ) -> BoxFuture<'_, Result<get_list::Output, String>>;
+
+    fn get_preferences(
+        &self,
+        params: get_preferences::Parameters,
+    ) -> BoxFuture<'_, Result<get_preferences::Output, String>>;
+
+    fn put_preferences(
+        &self,
+        input: put_preferences::Input,
+    ) -> BoxFuture<'_, Result<(), String>>;
+
+    fn get_likes(
+        &self,
+        params: get_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_likes::Output, String>>;
+
+    fn get_quotes(
+        &self,
+        params: get_quotes::Parameters,
+    ) -> BoxFuture<'_, Result<get_quotes::Output, String>>;
+
+    fn get_reposted_by(
+        &self,
+        params: get_reposted_by::Parameters,
+    ) -> BoxFuture<'_, Result<get_reposted_by::Output, String>>;
+
+    fn get_profile(
+        &self,
+        params: get_profile::Parameters,
+    ) -> BoxFuture<'_, Result<get_profile::Output, String>>;
+
+    fn search_actors_typeahead(
+        &self,
+        params: search_actors_typeahead::Parameters,
+    ) -> BoxFuture<'_, Result<search_actors_typeahead::Output, String>>;
+
+    fn search_posts(
+        &self,
+        params: search_posts::Parameters,
+    ) -> BoxFuture<'_, Result<search_posts::Output, String>>;
+
+    fn list_notifications(
+        &self,
+        params: list_notifications::Parameters,
+    ) -> BoxFuture<'_, Result<list_notifications::Output, String>>;
+
+    fn update_seen(
+        &self,
+        input: update_seen::Input,
+    ) -> BoxFuture<'_, Result<(), String>>;
+
+    fn resolve_handle(
+        &self,
+        params: resolve_handle::Parameters,
+    ) -> BoxFuture<'_, Result<resolve_handle::Output, String>>;
+
+    fn create_record(
+        &self,
+        record: KnownRecord,
+    ) -> BoxFuture<'_, Result<create_record::Output, String>>;
+
+    fn delete_record(&self, uri: String) -> BoxFuture<'_, Result<(), String>>;
+
+    fn upload_blob(
+        &self,
+        data: Vec<u8>,
+    ) -> BoxFuture<'_, Result<upload_blob::Output, String>>;
+
+    // Timeline saved by a previous run, if the client keeps one
+    fn cached_timeline(&self) -> BoxFuture<'_, Option<get_timeline::Output>> {
+        Box::pin(async { None })
+    }
+
+    // Whether what is shown comes from a cache because the AppView could not
+    // be reached
+    fn is_stale(&self) -> bool {
+        return false;
+    }
+}
+
+pub async fn preferences(agent: &Client) -> Result<Preferences, String> {
+    let output = agent
+        .get_preferences(get_preferences::ParametersData {}.into())
+        .await?;
+    return Ok(output.data.preferences);
+}
+
+// Replaces the preference of the same kind as `item`, keeping the others as
+// they are on the server
+pub async fn put_preference(
+    agent: &Client,
+    item: PreferencesItem,
+) -> Result<(), String> {
+    let mut preferences = preferences(agent).await?;
+    let kind = std::mem::discriminant(&item);
+    let existing = preferences.iter().position(
+        |p| matches!(p, Union::Refs(p) if std::mem::discriminant(p) == kind),
+    );
+    match existing {
+        Some(i) => preferences[i] = Union::Refs(item),
+        None => preferences.push(Union::Refs(item)),
+    }
+    return agent
+        .put_preferences(put_preferences::InputData { preferences }.into())
+        .await;
+}
+
+impl Api for Agent {
+    fn did(&self) -> BoxFuture<'_, Result<Did, String>> {
+        Box::pin(async move {
+            let session = self.get_session().await;
+            return session
+                .map(|s| s.did.clone())
+                .ok_or("Not logged in".into());
+        })
+    }
+
+    fn get_timeline(
+        &self,
+        params: get_timeline::Parameters,
+    ) -> BoxFuture<'_, Result<get_timeline::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_timeline(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_post_thread(
+        &self,
+        params: get_post_thread::Parameters,
+    ) -> BoxFuture<'_, Result<get_post_thread::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_post_thread(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_posts(
+        &self,
+        params: get_posts::Parameters,
+    ) -> BoxFuture<'_, Result<get_posts::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_posts(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_actor_likes(
+        &self,
+        params: get_actor_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_likes::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_actor_likes(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_actor_feeds(
+        &self,
+        params: get_actor_feeds::Parameters,
+    ) -> BoxFuture<'_, Result<get_actor_feeds::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_actor_feeds(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_author_feed(
+        &self,
+        params: get_author_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_author_feed::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_author_feed(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_feed(
+        &self,
+        params: get_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_feed(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_list_feed(
+        &self,
+        params: get_list_feed::Parameters,
+    ) -> BoxFuture<'_, Result<get_list_feed::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_list_feed(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_lists(
+        &self,
+        params: get_lists::Parameters,
+    ) -> BoxFuture<'_, Result<get_lists::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .graph
+                .get_lists(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_feed_generators(
+        &self,
+        params: get_feed_generators::Parameters,
+    ) -> BoxFuture<'_, Result<get_feed_generators::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_feed_generators(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_list(
+        &self,
+        params: get_list::Parameters,
+    ) -> BoxFuture<'_, Result<get_list::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .graph
+                .get_list(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_preferences(
+        &self,
+        params: get_preferences::Parameters,
+    ) -> BoxFuture<'_, Result<get_preferences::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .actor
+                .get_preferences(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn put_preferences(
+        &self,
+        input: put_preferences::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .actor
+                .put_preferences(input)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_quotes(
+        &self,
+        params: get_quotes::Parameters,
+    ) -> BoxFuture<'_, Result<get_quotes::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_quotes(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_reposted_by(
+        &self,
+        params: get_reposted_by::Parameters,
+    ) -> BoxFuture<'_, Result<get_reposted_by::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_reposted_by(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_likes(
+        &self,
+        params: get_likes::Parameters,
+    ) -> BoxFuture<'_, Result<get_likes::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .get_likes(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn get_profile(
+        &self,
+        params: get_profile::Parameters,
+    ) -> BoxFuture<'_, Result<get_profile::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .actor
+                .get_profile(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn search_actors_typeahead(
+        &self,
+        params: search_actors_typeahead::Parameters,
+    ) -> BoxFuture<'_, Result<search_actors_typeahead::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .actor
+                .search_actors_typeahead(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn search_posts(
+        &self,
+        params: search_posts::Parameters,
+    ) -> BoxFuture<'_, Result<search_posts::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .feed
+                .search_posts(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn list_notifications(
+        &self,
+        params: list_notifications::Parameters,
+    ) -> BoxFuture<'_, Result<list_notifications::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .notification
+                .list_notifications(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn update_seen(
+        &self,
+        input: update_seen::Input,
+    ) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            self.api
+                .app
+                .bsky
+                .notification
+                .update_seen(input)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn resolve_handle(
+        &self,
+        params: resolve_handle::Parameters,
+    ) -> BoxFuture<'_, Result<resolve_handle::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .com
+                .atproto
+                .identity
+                .resolve_handle(params)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn create_record(
+        &self,
+        record: KnownRecord,
+    ) -> BoxFuture<'_, Result<create_record::Output, String>> {
+        Box::pin(async move {
+            BskyAgent::create_record(self, record)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn delete_record(&self, uri: String) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            BskyAgent::delete_record(self, uri)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn upload_blob(
+        &self,
+        data: Vec<u8>,
+    ) -> BoxFuture<'_, Result<upload_blob::Output, String>> {
+        Box::pin(async move {
+            self.api
+                .com
+                .atproto
+                .repo
+                .upload_blob(data)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
@@ -0,0 +1,15 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Local};
+
+// Set when rendering has to be reproducible, e.g. snapshots and demo mode
+static FROZEN: RwLock<Option<DateTime<Local>>> = RwLock::new(None);
+
+// Current time used for relative dates
+pub fn now() -> DateTime<Local> {
+    return FROZEN.read().unwrap().unwrap_or_else(Local::now);
+}
+
+pub fn freeze(at: DateTime<Local>) {
+    *FROZEN.write().unwrap() = Some(at);
+}
@@ -0,0 +1,175 @@
+use atrium_api::{
+    app::bsky::graph::get_lists,
+    types::string::{AtIdentifier, Did},
+};
+use ratatui::{
+    layout::Position,
+    widgets::{StatefulWidget, Widget},
+};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::{updating_feed::FeedSource, Column},
+    components::{
+        embed::{embed_widget::EmbedWidget, Embed, EmbededList, Record},
+        list::{List, ListState, Pages},
+    },
+    keymap::Action,
+    redraw, workers,
+};
+
+// Lists made by an actor, each opens as a feed column
+pub struct ActorLists {
+    actor: Did,
+    lists: Arc<Mutex<Pages<EmbededList>>>,
+    state: ListState,
+}
+
+impl ActorLists {
+    pub fn new(agent: Client, actor: Did) -> Self {
+        let lists = Arc::new(Mutex::new(None));
+        let lists_c = Arc::clone(&lists);
+        let actor_c = actor.clone();
+        workers!().spawn(async move {
+            let o = match fetch_lists(agent, actor_c, None).await {
+                Ok(o) => o,
+                Err(e) => {
+                    log::error!("Cannot fetch lists: {}", e);
+                    return;
+                }
+            };
+            let mut lists = lists_c.lock().unwrap();
+            *lists = Some(o);
+            redraw!();
+        });
+        ActorLists { actor, lists, state: ListState::default() }
+    }
+}
+
+async fn fetch_lists(
+    agent: Client,
+    actor: Did,
+    cursor: Option<String>,
+) -> Result<(Vec<EmbededList>, Option<String>), String> {
+    let res = agent
+        .get_lists(
+            get_lists::ParametersData {
+                actor: AtIdentifier::Did(actor),
+                cursor,
+                limit: Some(100.try_into().unwrap()),
+            }
+            .into(),
+        )
+        .await?;
+    let get_lists::OutputData { cursor, lists } = res.data;
+    let lists = lists.iter().map(EmbededList::from).collect();
+    return Ok((lists, cursor));
+}
+
+impl ActionReceiver for &mut ActorLists {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Next => {
+                let Some((lists, cursor)) = self.lists.lock().unwrap().clone()
+                else {
+                    return AppEvent::None;
+                };
+                let Some(selected) = self.state.selected else {
+                    self.state.selected = Some(0);
+                    return AppEvent::None;
+                };
+                if selected + 1 < lists.len() {
+                    self.state.next();
+                    return AppEvent::None;
+                }
+                if cursor.is_none() {
+                    return AppEvent::None;
+                }
+
+                let lists = Arc::clone(&self.lists);
+                let actor = self.actor.clone();
+                workers!().spawn(async move {
+                    let new_lists =
+                        fetch_lists(agent, actor, cursor.clone()).await;
+                    let (mut new_lists, new_cursor) = match new_lists {
+                        Ok(o) => o,
+                        Err(e) => {
+                            log::error!("Cannot fetch lists: {}", e);
+                            return;
+                        }
+                    };
+                    let mut lists = lists.lock().unwrap();
+                    let Some((lists, old_cursor)) = &mut *lists else {
+                        return;
+                    };
+                    if *old_cursor != cursor {
+                        return;
+                    }
+                    lists.append(&mut new_lists);
+                    *old_cursor = new_cursor;
+                    redraw!();
+                });
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                self.state.previous();
+                return AppEvent::None;
+            }
+            Action::Back => return AppEvent::ColumnPopLayer,
+
+            Action::Select => {
+                let lists = self.lists.lock().unwrap();
+                let Some((lists, _)) = &*lists else {
+                    return AppEvent::None;
+                };
+                let Some(list) = self.state.selected.and_then(|i| lists.get(i))
+                else {
+                    return AppEvent::None;
+                };
+                return AppEvent::ColumnNewLayer(Column::feed(
+                    FeedSource::List(list.uri.clone()),
+                    agent,
+                ));
+            }
+
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl ClickReceiver for &mut ActorLists {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
+impl Widget for &mut ActorLists {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let lists = Arc::clone(&self.lists);
+        let lists = lists.lock().unwrap();
+        let Some((lists, _)) = &*lists else {
+            return;
+        };
+
+        let list = List::new(lists.len(), |context| {
+            let item = EmbedWidget::new(
+                Embed::Record(Record::List(lists[context.index].clone())),
+                context.is_selected,
+            );
+            let height = item.line_count(area.width);
+            return (item, height);
+        });
+        list.render(area, buf, &mut self.state);
+    }
+}
@@ -1,11 +1,12 @@
-use std::sync::OnceLock;
+use std::{collections::HashSet, sync::OnceLock};
 
 use crate::{
     app::{AppEvent, EventReceiver},
+    client::Client,
     components::{
         composer::{
             embed::{Embed, EmbedState, EmbedWidget, Media},
-            textarea::{Input, Key, TextStyle},
+            textarea::{Input, Key, TextArea, TextStyle},
             vim::{InputMode, Vim},
         },
         post::{
@@ -14,7 +15,9 @@ use crate::{
             ReplyRef,
         },
     },
-    post_manager,
+    config, outbox,
+    outbox::Op,
+    post_manager, redraw, workers,
 };
 use atrium_api::{
     app::bsky::{
@@ -22,7 +25,7 @@ use atrium_api::{
     },
     types::{string::Language, Union},
 };
-use bsky_sdk::{rich_text::RichText, BskyAgent};
+use bsky_sdk::rich_text::RichText;
 use ratatui::{
     crossterm::event::{self, Event},
     layout::{Constraint, Layout},
@@ -31,12 +34,12 @@ use ratatui::{
     widgets::{Block, BorderType, Widget},
 };
 use regex::Regex;
-use tokio::task::JoinHandle;
+use tokio::sync::oneshot::{self, error::TryRecvError};
 
 enum Focus {
-    TextField,
-    LangField,
-    AttachmentField,
+    Text,
+    Lang,
+    Attachment,
 }
 
 static RE_URL: OnceLock<Regex> = OnceLock::new();
@@ -48,7 +51,8 @@ pub struct ComposerView {
     focus: Focus,
     reply: Option<ReplyRef>,
     embed: EmbedState,
-    post_handle: Option<JoinHandle<AppEvent>>,
+    // result of the post being sent
+    post_handle: Option<oneshot::Receiver<AppEvent>>,
 }
 
 macro_rules! create_quote_ref {
@@ -89,9 +93,7 @@ macro_rules! create_image_refs {
             )
         });
 
-        let h = $images
-            .into_iter()
-            .map(|i| $agent.api.com.atproto.repo.upload_blob(i.data.clone()));
+        let h = $images.into_iter().map(|i| $agent.upload_blob(i.data.clone()));
         let blobs = match futures::future::try_join_all(h).await {
             Ok(r) => r,
             Err(e) => {
@@ -173,7 +175,7 @@ macro_rules! create_external_ref {
             };
 
             log::info!("Uploading thumbnail");
-            let r#ref = $agent.api.com.atproto.repo.upload_blob(blob.to_vec());
+            let r#ref = $agent.upload_blob(blob.to_vec());
             let blob = match r#ref.await {
                 Ok(r) => r,
                 Err(e) => {
@@ -200,10 +202,10 @@ macro_rules! create_external_ref {
 impl ComposerView {
     pub fn new(reply: Option<ReplyRef>, embed: Embed) -> Self {
         let text_field = Vim::new(|_| true);
-        let langs_field = Vim::new(|i| {
+        let mut langs_field = Vim::new(|i| {
             let atoz = |i| {
                 matches!(i, Input { key: Key::Char(c), .. }
-                if ('a'..='z').contains(&c) || c == ',')
+                if c.is_ascii_lowercase() || c == ',')
             };
             let esc_or_backspace = |i| {
                 matches!(
@@ -215,31 +217,50 @@ impl ComposerView {
             atoz(i) || esc_or_backspace(i)
         });
 
+        langs_field.textarea =
+            TextArea::from(config!().composer.langs.join(","));
+
         ComposerView {
             text_field,
             lang_field: langs_field,
-            focus: Focus::TextField,
+            focus: Focus::Text,
             reply,
             embed: EmbedState::new(embed),
             post_handle: None,
         }
     }
 
-    pub async fn post_finished(&mut self) -> bool {
-        if self.post_handle.is_none() {
-            return false;
+    // the post replied to and the quoted post
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        if let Some(reply) = &self.reply {
+            uris.insert(reply.parent.uri.clone());
+        }
+        match &self.embed.embed {
+            Embed::Record(post) | Embed::RecordWithMedia(post, _) => {
+                uris.insert(post.uri.clone());
+            }
+            _ => {}
         }
-        if self.post_handle.as_ref().map(|h| h.is_finished()).unwrap_or(false) {
-            let mut handle = None;
-            std::mem::swap(&mut handle, &mut self.post_handle);
-            if let AppEvent::ColumnPopLayer = handle.unwrap().await.unwrap() {
-                return true;
+    }
+
+    pub fn post_finished(&mut self) -> bool {
+        let Some(handle) = self.post_handle.as_mut() else {
+            return false;
+        };
+        match handle.try_recv() {
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Closed) => {
+                self.post_handle = None;
+                return false;
+            }
+            Ok(event) => {
+                self.post_handle = None;
+                return matches!(event, AppEvent::ColumnPopLayer);
             }
         }
-        return false;
     }
 
-    async fn post(&self, agent: BskyAgent) -> Option<JoinHandle<AppEvent>> {
+    async fn post(&self, agent: Client) -> Option<oneshot::Receiver<AppEvent>> {
         let text = self.text_field.textarea.lines().join("\n");
         if text.is_empty() && matches!(self.embed.embed, Embed::None) {
             return None;
@@ -261,7 +282,7 @@ impl ComposerView {
                 }
             })
             .collect::<Vec<_>>();
-        if invalid_langs.len() != 0 {
+        if !invalid_langs.is_empty() {
             log::error!("Langs {:?} are invalid", invalid_langs);
             return None;
         }
@@ -294,7 +315,8 @@ impl ComposerView {
         });
 
         let embed = self.embed.embed.clone();
-        return Some(tokio::spawn(async move {
+        let (tx, rx) = oneshot::channel();
+        let post = async move {
             let embed = match embed {
                 Embed::None => None,
                 Embed::Record(post) => {
@@ -347,9 +369,9 @@ impl ComposerView {
                 }
             };
 
-            log::info!("Posting");
-            let r = agent
-                .create_record(atrium_api::app::bsky::feed::post::RecordData {
+            // sent by the outbox, kept there if it fails
+            let posted = outbox!().submit(Op::Create(
+                atrium_api::app::bsky::feed::post::RecordData {
                     created_at,
                     embed,
                     entities: None,
@@ -359,18 +381,24 @@ impl ComposerView {
                     reply,
                     tags: None,
                     text,
-                })
-                .await;
-            match r {
-                Ok(_) => {}
-                Err(e) => {
-                    log::error!("Cannot post: {}", e);
-                    return AppEvent::None;
                 }
-            }
-            log::info!("Posted");
+                .into(),
+            ));
+            log::info!("Posting");
+            workers!().spawn(async move {
+                match posted.await {
+                    Ok(Ok(_)) => log::info!("Posted"),
+                    Ok(Err(e)) => log::error!("Cannot post: {}", e),
+                    Err(_) => {}
+                }
+            });
             return AppEvent::ColumnPopLayer;
-        }));
+        };
+        workers!().spawn(async move {
+            let _ = tx.send(post.await);
+            redraw!();
+        });
+        return Some(rx);
     }
 
     fn handle_pasting(&mut self, s: String) {
@@ -379,11 +407,11 @@ impl ComposerView {
             return;
         }
         match self.focus {
-            Focus::TextField => {
+            Focus::Text => {
                 self.text_field.textarea.insert_string(s);
                 self.embed_external();
             }
-            Focus::LangField => {
+            Focus::Lang => {
                 self.lang_field.textarea.insert_string(s);
             }
             _ => {}
@@ -405,7 +433,7 @@ impl ComposerView {
         let m = capture.get(1).expect("invalid capture");
         let mut uri = if let Some(domain) = capture.name("domain") {
             if !psl::suffix(domain.as_str().as_bytes())
-                .map_or(false, |suffix| suffix.is_known())
+                .is_some_and(|suffix| suffix.is_known())
             {
                 return;
             }
@@ -428,7 +456,7 @@ impl EventReceiver for &mut ComposerView {
     async fn handle_events(
         self,
         event: event::Event,
-        agent: BskyAgent,
+        agent: Client,
     ) -> AppEvent {
         if self.post_handle.is_some() {
             return AppEvent::None;
@@ -447,9 +475,9 @@ impl EventReceiver for &mut ComposerView {
         }
 
         match self.focus {
-            Focus::TextField => match event.clone().into() {
+            Focus::Text => match event.clone().into() {
                 Input { key: Key::Tab, .. } => {
-                    self.focus = Focus::LangField;
+                    self.focus = Focus::Lang;
                     return AppEvent::None;
                 }
                 Input { key: Key::Enter, .. }
@@ -462,9 +490,9 @@ impl EventReceiver for &mut ComposerView {
                 }
                 _ => return self.text_field.handle_events(event, agent).await,
             },
-            Focus::LangField => match event.clone().into() {
+            Focus::Lang => match event.clone().into() {
                 Input { key: Key::Tab, .. } => {
-                    self.focus = Focus::AttachmentField;
+                    self.focus = Focus::Attachment;
                     return AppEvent::None;
                 }
                 Input { key: Key::Enter, .. }
@@ -480,9 +508,9 @@ impl EventReceiver for &mut ComposerView {
                 }
                 _ => return self.lang_field.handle_events(event, agent).await,
             },
-            Focus::AttachmentField => match event.clone().into() {
+            Focus::Attachment => match event.clone().into() {
                 Input { key: Key::Tab, .. } => {
-                    self.focus = Focus::TextField;
+                    self.focus = Focus::Text;
                     return AppEvent::None;
                 }
                 _ => return self.embed.handle_events(event, agent).await,
@@ -495,8 +523,8 @@ fn parse_text_styles(lines: &[String]) -> Vec<TextStyle> {
     return lines
         .iter()
         .enumerate()
-        .map(|(i, line)| {
-            detect_facets(&line).into_iter().map(move |f| {
+        .flat_map(|(i, line)| {
+            detect_facets(line).into_iter().map(move |f| {
                 let slice = CharSlice::from(line, f.index);
                 let style = match f.feature {
                     FacetFeature::Mention => Style::default().italic(),
@@ -510,7 +538,6 @@ fn parse_text_styles(lines: &[String]) -> Vec<TextStyle> {
                 }
             })
         })
-        .flatten()
         .collect();
 }
 
@@ -528,7 +555,7 @@ impl Widget for &mut ComposerView {
         });
 
         let embed = EmbedWidget::new(&self.embed)
-            .focused(matches!(self.focus, Focus::AttachmentField));
+            .focused(matches!(self.focus, Focus::Attachment));
 
         let [_, area, _] = Layout::horizontal([
             Constraint::Fill(1),
@@ -559,19 +586,17 @@ impl Widget for &mut ComposerView {
         }
 
         let title = match (&self.focus, &self.text_field.mode) {
-            (Focus::LangField, _) => "New Post",
+            (Focus::Lang, _) => "New Post",
             (_, InputMode::Normal) => "New Post (Normal)",
             (_, InputMode::Insert) => "New Post (Insert)",
             (_, InputMode::Visual) => "New Post (View)",
         };
         let text_lines = self.text_field.textarea.lines();
-        let word_remaining = if text_lines.len() == 0 {
+        let word_remaining = if text_lines.is_empty() {
             300
         } else {
-            300 - text_lines
-                .into_iter()
-                .map(|l| l.chars().count())
-                .sum::<usize>() as i64
+            300 - text_lines.iter().map(|l| l.chars().count()).sum::<usize>()
+                as i64
                 - text_lines.len() as i64
                 + 1
         };
@@ -585,15 +610,13 @@ impl Widget for &mut ComposerView {
                         .right_aligned(),
                 ),
         );
-        self.text_field
-            .textarea
-            .focused(matches!(self.focus, Focus::TextField));
+        self.text_field.textarea.focused(matches!(self.focus, Focus::Text));
         let text_styles = parse_text_styles(self.text_field.textarea.lines());
         self.text_field.textarea.text_styles(text_styles);
         self.text_field.textarea.render(text_area, buf);
 
         let title = match (&self.focus, &self.lang_field.mode) {
-            (Focus::TextField, _) => "Langs",
+            (Focus::Text, _) => "Langs",
             (_, InputMode::Normal) => "Langs (Normal)",
             (_, InputMode::Insert) => "Langs (Insert)",
             (_, InputMode::Visual) => "Langs (View)",
@@ -604,9 +627,7 @@ impl Widget for &mut ComposerView {
                 .border_style(Color::DarkGray)
                 .title(Span::styled(title, Color::Gray)),
         );
-        self.lang_field
-            .textarea
-            .focused(matches!(self.focus, Focus::LangField));
+        self.lang_field.textarea.focused(matches!(self.focus, Focus::Lang));
         self.lang_field.textarea.render(lang_area, buf);
 
         embed.render(embed_area, buf);
@@ -1,24 +1,26 @@
-use std::process::{Command, Stdio};
-
 use atrium_api::types::string::Did;
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Position},
     prelude::StatefulWidget,
     style::{Color, Style},
     text::Span,
     widgets::BorderType,
     widgets::{Block, Clear, Widget},
 };
+use std::process::Stdio;
 
-use crate::app::{AppEvent, EventReceiver};
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    config,
+    keymap::Action,
+};
 use crate::{
     columns::profile_page::ProfilePage,
-    columns::Column,
     components::{
         list::{List, ListState},
         paragraph::Paragraph,
+        post::{Facet, FacetType},
     },
 };
 
@@ -40,54 +42,69 @@ pub enum FacetModalItem {
     Mention(Mention),
 }
 
+impl FacetModalItem {
+    // Tags have nothing to open
+    pub fn from_facet(facet: &Facet, text: &str) -> Option<FacetModalItem> {
+        let text = text[facet.range.clone()].to_string();
+        match &facet.r#type {
+            FacetType::Link(url) => {
+                return Some(FacetModalItem::Link(Link {
+                    text,
+                    url: url.clone(),
+                }));
+            }
+            FacetType::Mention(did) => {
+                return Some(FacetModalItem::Mention(Mention {
+                    text,
+                    did: did.clone(),
+                }));
+            }
+            FacetType::Tag => return None,
+        }
+    }
+
+    pub async fn open(&self, agent: Client) -> AppEvent {
+        match self {
+            FacetModalItem::Link(l) => {
+                if let Result::Err(e) =
+                    config::command(&config!().media.browser)
+                        .arg(&l.url)
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn()
+                {
+                    log::error!("{:?}", e);
+                }
+                return AppEvent::None;
+            }
+            FacetModalItem::Mention(m) => {
+                let actor = m.did.clone();
+                return ProfilePage::open(actor, agent).await;
+            }
+        }
+    }
+}
+
 pub struct FacetModal {
     pub links: Vec<FacetModalItem>,
     pub state: ListState,
 }
 
-impl EventReceiver for &mut FacetModal {
-    async fn handle_events(
-        self,
-        event: event::Event,
-        agent: BskyAgent,
-    ) -> AppEvent {
-        let Event::Key(key) = event else {
-            return AppEvent::None;
-        };
-
-        match key.code {
-            KeyCode::Backspace => return AppEvent::ColumnPopLayer,
-            KeyCode::Char('j') => {
+impl ActionReceiver for &mut FacetModal {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
+            Action::Next => {
                 self.state.next();
             }
-            KeyCode::Char('k') => {
+            Action::Previous => {
                 self.state.previous();
             }
-            KeyCode::Enter => {
+            Action::Select => {
                 let Some(index) = self.state.selected else {
                     return AppEvent::None;
                 };
-                match &self.links[index] {
-                    FacetModalItem::Link(l) => {
-                        let url = &l.url;
-                        if let Result::Err(e) = Command::new("xdg-open")
-                            .arg(url)
-                            .stdout(Stdio::null())
-                            .stderr(Stdio::null())
-                            .spawn()
-                        {
-                            log::error!("{:?}", e);
-                        }
-                    }
-                    FacetModalItem::Mention(m) => {
-                        let actor = m.did.clone();
-                        let me = &agent.get_session().await.unwrap().did;
-                        let profile = ProfilePage::from_did(actor, me, agent);
-                        return AppEvent::ColumnNewLayer(Column::ProfilePage(
-                            profile,
-                        ));
-                    }
-                }
+                return self.links[index].open(agent).await;
             }
             _ => {}
         }
@@ -95,6 +112,15 @@ impl EventReceiver for &mut FacetModal {
     }
 }
 
+impl ClickReceiver for &mut FacetModal {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
 impl Widget for &mut FacetModal {
     fn render(
         self,
@@ -156,7 +182,7 @@ impl Widget for &mut FacetModal {
             let item = items[context.index]
                 .clone()
                 .block(Block::default().style(style));
-            let height = item.line_count(area.width) as u16;
+            let height = item.line_count(area.width);
             return (item, height);
         })
         .render(area, buf, &mut self.state);
@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::{
+        post_likes::PostLikes, post_quotes::PostQuotes,
+        post_reposts::PostReposts,
+    },
+    keymap::{Action, Context},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tab {
+    Likes,
+    Reposts,
+    Quotes,
+}
+
+const TABS: [Tab; 3] = [Tab::Likes, Tab::Reposts, Tab::Quotes];
+
+impl Tab {
+    fn name(&self) -> &'static str {
+        match self {
+            Tab::Likes => "Likes",
+            Tab::Reposts => "Reposts",
+            Tab::Quotes => "Quotes",
+        }
+    }
+}
+
+// Likes, reposts and quotes of a post as tabs. A tab is fetched the first
+// time it is shown.
+pub struct Interactions {
+    uri: String,
+    tab: Tab,
+    likes: Option<PostLikes>,
+    reposts: Option<PostReposts>,
+    quotes: Option<PostQuotes>,
+    // where the tab names were last rendered
+    tab_areas: Vec<(Rect, Tab)>,
+}
+
+impl Interactions {
+    pub fn new(agent: Client, uri: String, tab: Tab) -> Interactions {
+        let mut interactions = Interactions {
+            uri,
+            tab,
+            likes: None,
+            reposts: None,
+            quotes: None,
+            tab_areas: Vec::new(),
+        };
+        interactions.switch(tab, agent);
+        return interactions;
+    }
+
+    fn switch(&mut self, tab: Tab, agent: Client) {
+        self.tab = tab;
+        let uri = self.uri.clone();
+        match tab {
+            Tab::Likes => {
+                self.likes.get_or_insert_with(|| PostLikes::new(agent, uri));
+            }
+            Tab::Reposts => {
+                self.reposts
+                    .get_or_insert_with(|| PostReposts::new(agent, uri));
+            }
+            Tab::Quotes => {
+                self.quotes.get_or_insert_with(|| PostQuotes::new(agent, uri));
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        return self.tab.name();
+    }
+
+    pub fn keymap_contexts(&self) -> Vec<Context> {
+        match self.tab {
+            Tab::Likes => vec![Context::PostLikes, Context::Global],
+            Tab::Reposts => vec![Context::PostReposts, Context::Global],
+            Tab::Quotes => vec![Context::Post, Context::Global],
+        }
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        if let Some(quotes) = &self.quotes {
+            quotes.post_uris(uris);
+        }
+    }
+}
+
+impl ActionReceiver for &mut Interactions {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        let i = TABS.iter().position(|t| *t == self.tab).unwrap();
+        match action {
+            Action::NextTab => {
+                self.switch(TABS[(i + 1) % TABS.len()], agent);
+                return AppEvent::None;
+            }
+            Action::PreviousTab => {
+                self.switch(TABS[(i + TABS.len() - 1) % TABS.len()], agent);
+                return AppEvent::None;
+            }
+            _ => {}
+        }
+        match self.tab {
+            Tab::Likes => match &mut self.likes {
+                Some(likes) => return likes.handle_action(action, agent).await,
+                None => return AppEvent::None,
+            },
+            Tab::Reposts => match &mut self.reposts {
+                Some(reposts) => {
+                    return reposts.handle_action(action, agent).await
+                }
+                None => return AppEvent::None,
+            },
+            Tab::Quotes => match &mut self.quotes {
+                Some(quotes) => {
+                    return quotes.handle_action(action, agent).await
+                }
+                None => return AppEvent::None,
+            },
+        }
+    }
+}
+
+impl ClickReceiver for &mut Interactions {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        if let Some((_, tab)) =
+            self.tab_areas.iter().find(|(area, _)| area.contains(position))
+        {
+            self.switch(*tab, agent);
+            return AppEvent::None;
+        }
+        match self.tab {
+            Tab::Likes => match &mut self.likes {
+                Some(likes) => {
+                    return likes.handle_click(position, agent).await
+                }
+                None => return AppEvent::None,
+            },
+            Tab::Reposts => match &mut self.reposts {
+                Some(reposts) => {
+                    return reposts.handle_click(position, agent).await
+                }
+                None => return AppEvent::None,
+            },
+            Tab::Quotes => match &mut self.quotes {
+                Some(quotes) => {
+                    return quotes.handle_click(position, agent).await
+                }
+                None => return AppEvent::None,
+            },
+        }
+    }
+}
+
+impl Widget for &mut Interactions {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let [tabs_area, main_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)])
+                .areas(area);
+
+        let mut tabs = Line::default();
+        self.tab_areas.clear();
+        let mut x = tabs_area.x;
+        for tab in TABS {
+            let name = format!(" {} ", tab.name());
+            let width = name.len() as u16;
+            self.tab_areas.push((Rect::new(x, tabs_area.y, width, 1), tab));
+            x += width;
+            if tab == self.tab {
+                tabs += Span::styled(
+                    name,
+                    Style::default().bg(Color::Rgb(45, 50, 55)),
+                );
+            } else {
+                tabs += Span::styled(name, Color::DarkGray);
+            }
+        }
+        tabs.render(tabs_area, buf);
+
+        match self.tab {
+            Tab::Likes => {
+                if let Some(likes) = &mut self.likes {
+                    likes.render(main_area, buf);
+                }
+            }
+            Tab::Reposts => {
+                if let Some(reposts) = &mut self.reposts {
+                    reposts.render(main_area, buf);
+                }
+            }
+            Tab::Quotes => {
+                if let Some(quotes) = &mut self.quotes {
+                    quotes.render(main_area, buf);
+                }
+            }
+        }
+    }
+}
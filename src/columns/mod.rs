@@ -1,33 +1,132 @@
+pub mod actor_lists;
 pub mod composer_view;
 pub mod facet_modal;
+pub mod interactions;
 pub mod notifications;
+pub mod outbox_view;
 pub mod post_likes;
+pub mod post_quotes;
+pub mod post_reposts;
 pub mod profile_page;
+pub mod raw_view;
+pub mod saved_feeds;
 pub mod search_view;
+pub mod stats_view;
 pub mod thread_view;
 pub mod updating_feed;
 
+use actor_lists::ActorLists;
 use composer_view::ComposerView;
 use facet_modal::FacetModal;
+use interactions::Interactions;
 use notifications::Notifications;
-use post_likes::PostLikes;
+use outbox_view::OutboxView;
 use profile_page::ProfilePage;
+use raw_view::RawView;
+use saved_feeds::SavedFeeds;
 use search_view::SearchView;
+use stats_view::StatsView;
 use thread_view::ThreadView;
-use updating_feed::UpdatingFeed;
+use updating_feed::{FeedSource, UpdatingFeed};
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::{
+    app::AppEvent,
+    client::Client,
+    components::feed_filter::FeedFilter,
+    config,
+    keymap::{Action, Context},
+};
 
 pub enum Column {
     UpdatingFeed(UpdatingFeed),
     Thread(ThreadView),
-    Composer(ComposerView),
+    // the two largest columns are boxed, the others are pushed and popped as
+    // often
+    Composer(Box<ComposerView>),
     FacetModal(FacetModal),
     Notifications(Notifications),
-    PostLikes(PostLikes),
+    Interactions(Interactions),
+    ActorLists(ActorLists),
     ProfilePage(ProfilePage),
-    SearchView(SearchView),
+    SearchView(Box<SearchView>),
+    RawView(RawView),
+    Stats(StatsView),
+    Outbox(OutboxView),
+    SavedFeeds(SavedFeeds),
+}
+
+// Columns that can be opened without any selected item
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    Feed,
+    Notifications,
+    Profile,
+    Search,
 }
 
 impl Column {
+    // Creates the column and starts its workers
+    pub async fn open(
+        kind: ColumnKind,
+        agent: Client,
+    ) -> Result<Column, String> {
+        let column = match kind {
+            ColumnKind::Feed => Column::feed(FeedSource::Following, agent),
+            ColumnKind::Notifications => {
+                let notifications = Notifications::new(agent.clone()).await;
+                notifications.spawn_worker(agent);
+                Column::Notifications(notifications)
+            }
+            ColumnKind::Profile => {
+                let me = agent.did().await?;
+                Column::ProfilePage(ProfilePage::from_did(
+                    me.clone(),
+                    &me,
+                    agent,
+                ))
+            }
+            ColumnKind::Search => {
+                Column::SearchView(Box::new(SearchView::new(agent)))
+            }
+        };
+        return Ok(column);
+    }
+
+    // Opens the column as a new layer, logging why it cannot be opened
+    pub async fn open_layer(kind: ColumnKind, agent: Client) -> AppEvent {
+        match Column::open(kind, agent).await {
+            Ok(column) => return AppEvent::ColumnNewLayer(column),
+            Err(e) => {
+                log::error!("Cannot open column: {}", e);
+                return AppEvent::None;
+            }
+        }
+    }
+
+    // Creates a feed column filtered by feed.filter and starts its workers
+    pub fn feed(source: FeedSource, agent: Client) -> Column {
+        let filter = config!().feed.filter.clone();
+        return Column::filtered_feed(source, &filter, agent);
+    }
+
+    pub fn filtered_feed(
+        source: FeedSource,
+        filter: &FeedFilter,
+        agent: Client,
+    ) -> Column {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let feed = UpdatingFeed::new(source, filter, tx);
+        feed.spawn_feed_autoupdate(agent.clone());
+        feed.spawn_request_worker(agent, rx);
+        return Column::UpdatingFeed(feed);
+    }
+
     pub fn name(&self) -> String {
         match self {
             Column::UpdatingFeed(_) => "Feed",
@@ -35,21 +134,93 @@ impl Column {
             Column::Composer(_) => "Composer",
             Column::FacetModal(_) => "Facets",
             Column::Notifications(_) => "Notifications",
-            Column::PostLikes(_) => "Likes",
+            Column::Interactions(interactions) => interactions.name(),
+            Column::ActorLists(_) => "Lists",
             Column::ProfilePage(_) => "Profile",
             Column::SearchView(_) => "Search",
+            Column::RawView(_) => "Raw",
+            Column::Stats(_) => "Stats",
+            Column::Outbox(_) => "Outbox",
+            Column::SavedFeeds(_) => "Saved feeds",
         }
         .to_string()
     }
+
+    // Contexts to resolve key bindings in, in order of priority. None if the
+    // column takes raw key events.
+    pub fn keymap_contexts(&self) -> Option<Vec<Context>> {
+        match self {
+            Column::UpdatingFeed(_) => {
+                Some(vec![Context::Feed, Context::Post, Context::Global])
+            }
+            Column::Thread(_) => {
+                Some(vec![Context::Thread, Context::Post, Context::Global])
+            }
+            Column::Composer(_) => None,
+            Column::FacetModal(_) => {
+                Some(vec![Context::FacetModal, Context::Global])
+            }
+            Column::Notifications(_) => Some(vec![
+                Context::Notifications,
+                Context::Post,
+                Context::Global,
+            ]),
+            Column::Interactions(interactions) => {
+                Some(interactions.keymap_contexts())
+            }
+            Column::ProfilePage(profile) => Some(profile.keymap_contexts()),
+            Column::SearchView(search) => search.keymap_contexts(),
+            Column::ActorLists(_) | Column::RawView(_) | Column::Stats(_) => {
+                Some(vec![Context::Global])
+            }
+            Column::Outbox(_) => Some(vec![Context::Outbox, Context::Global]),
+            Column::SavedFeeds(_) => {
+                Some(vec![Context::SavedFeeds, Context::Global])
+            }
+        }
+    }
+
+    // Adds the uris of posts the column shows, kept in the post manager
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        match self {
+            Column::UpdatingFeed(feed) => feed.post_uris(uris),
+            Column::Thread(thread) => thread.post_uris(uris),
+            Column::Composer(composer) => composer.post_uris(uris),
+            Column::Notifications(notifications) => {
+                notifications.post_uris(uris)
+            }
+            Column::ProfilePage(profile) => profile.post_uris(uris),
+            Column::Interactions(interactions) => interactions.post_uris(uris),
+            Column::SearchView(search) => search.post_uris(uris),
+            Column::FacetModal(_)
+            | Column::ActorLists(_)
+            | Column::RawView(_)
+            | Column::Stats(_)
+            | Column::Outbox(_)
+            | Column::SavedFeeds(_) => {}
+        }
+    }
+
+    // Action run by double clicking an item
+    pub fn open_action(&self) -> Action {
+        match self {
+            Column::FacetModal(_)
+            | Column::SearchView(_)
+            | Column::ActorLists(_)
+            | Column::SavedFeeds(_) => Action::Select,
+            _ => Action::OpenThread,
+        }
+    }
 }
 
 pub struct ColumnStack {
     pub stack: Vec<Column>,
+    pub width: u16,
 }
 
 impl ColumnStack {
-    pub fn from(stack: Vec<Column>) -> ColumnStack {
-        ColumnStack { stack }
+    pub fn from(stack: Vec<Column>, width: u16) -> ColumnStack {
+        ColumnStack { stack, width }
     }
 
     pub fn push(&mut self, column: Column) {
@@ -60,7 +231,40 @@ impl ColumnStack {
         self.stack.pop()
     }
 
+    // Pops the top column unless it is the root of the stack
+    pub fn pop_layer(&mut self) {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+    }
+
     pub fn last_mut(&mut self) -> Option<&mut Column> {
         self.stack.last_mut()
     }
 }
+
+// Named tab of column stacks shown side by side
+pub struct Workspace {
+    pub name: String,
+    pub stacks: Vec<ColumnStack>,
+    // index of the stack receiving key events
+    pub focus: usize,
+}
+
+impl Workspace {
+    pub fn new(name: String, stacks: Vec<ColumnStack>) -> Workspace {
+        Workspace { name, stacks, focus: 0 }
+    }
+
+    pub fn focused_stack(&mut self) -> &mut ColumnStack {
+        &mut self.stacks[self.focus]
+    }
+
+    pub fn focus_left(&mut self) {
+        self.focus = self.focus.saturating_sub(1);
+    }
+
+    pub fn focus_right(&mut self) {
+        self.focus = (self.focus + 1).min(self.stacks.len() - 1);
+    }
+}
@@ -1,29 +1,32 @@
-use std::sync::{Arc, Mutex};
-
 use atrium_api::types::Object;
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{Event, KeyCode},
+    layout::Position,
     style::{Color, Style},
     text::Line,
     widgets::{Block, BorderType, StatefulWidget, Widget},
 };
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, Weak},
+};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
-    columns::{Column, ProfilePage, ThreadView},
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::{raw_view::RawView, Column, ProfilePage, ThreadView},
     components::{
         list::{List, ListState},
         notification::{Notification, NotificationWidget, Record},
         post::Post,
     },
-    post_manager,
+    config,
+    keymap::Action,
+    post_manager, redraw, workers,
 };
 
 pub struct Notifications {
     feed: Arc<Mutex<Feed>>,
     seen_at: Arc<Mutex<Option<atrium_api::types::string::Datetime>>>,
-    is_terminate_worker: Arc<Mutex<bool>>,
 }
 
 struct Feed {
@@ -32,7 +35,7 @@ struct Feed {
 }
 
 impl Notifications {
-    pub async fn new(agent: BskyAgent) -> Self {
+    pub async fn new(agent: Client) -> Self {
         use atrium_api::app::bsky::notification::{
             list_notifications, update_seen,
         };
@@ -40,16 +43,11 @@ impl Notifications {
         let seen_at = Arc::new(Mutex::new(None));
         let feed = Feed { notifs: vec![], state: ListState::default() };
         let feed = Arc::new(Mutex::new(feed));
-        let is_terminate_worker = Arc::new(Mutex::new(false));
 
         let seen_at_ = Arc::clone(&seen_at);
-        let feed_ = Arc::clone(&feed);
-        tokio::spawn(async move {
+        let feed_ = Arc::downgrade(&feed);
+        workers!().spawn(async move {
             let out = agent
-                .api
-                .app
-                .bsky
-                .notification
                 .list_notifications(
                     list_notifications::ParametersData {
                         cursor: None,
@@ -79,11 +77,10 @@ impl Notifications {
             let list_notifications::OutputData { notifications, .. } = data;
             let new_seen_at = atrium_api::types::string::Datetime::now();
 
+            if closed(&feed_) {
+                return;
+            }
             let out = agent
-                .api
-                .app
-                .bsky
-                .notification
                 .update_seen(
                     update_seen::InputData { seen_at: new_seen_at.clone() }
                         .into(),
@@ -113,32 +110,51 @@ impl Notifications {
                 return;
             }
 
+            let Some(feed) = feed_.upgrade() else {
+                return;
+            };
             let mut seen_at = seen_at_.lock().unwrap();
             *seen_at = Some(new_seen_at);
-            let mut feed = feed_.lock().unwrap();
+            let mut feed = feed.lock().unwrap();
             feed.notifs = notifs;
+            redraw!();
         });
 
-        Self { feed, seen_at, is_terminate_worker }
+        Self { feed, seen_at }
     }
 
-    pub fn spawn_worker(&self, agent: BskyAgent) {
-        let feed = Arc::clone(&self.feed);
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        let feed = self.feed.lock().unwrap();
+        uris.extend(feed.notifs.iter().filter_map(|n| match &n.record {
+            Record::Like(u)
+            | Record::Repost(u)
+            | Record::Reply(u)
+            | Record::Mention(u)
+            | Record::Quote(u) => Some(u.clone()),
+            _ => None,
+        }));
+    }
+
+    // Stops once the column is dropped
+    pub fn spawn_worker(&self, agent: Client) {
+        let feed = Arc::downgrade(&self.feed);
         let seen_at = Arc::clone(&self.seen_at);
-        let is_terminate_worker = Arc::clone(&self.is_terminate_worker);
-        tokio::spawn(async move {
+        workers!().spawn(async move {
             use atrium_api::app::bsky::notification::{
                 list_notifications, update_seen,
             };
 
-            while !*is_terminate_worker.lock().unwrap() {
-                tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+            loop {
+                let refresh_rate = config!().notifications.refresh_rate;
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    refresh_rate,
+                ))
+                .await;
+                if closed(&feed) {
+                    return;
+                }
 
                 let out = agent
-                    .api
-                    .app
-                    .bsky
-                    .notification
                     .list_notifications(
                         list_notifications::ParametersData {
                             cursor: None,
@@ -168,11 +184,10 @@ impl Notifications {
                 let list_notifications::OutputData { notifications, .. } = data;
 
                 let new_seen_at = atrium_api::types::string::Datetime::now();
+                if closed(&feed) {
+                    return;
+                }
                 let out = agent
-                    .api
-                    .app
-                    .bsky
-                    .notification
                     .update_seen(
                         update_seen::InputData { seen_at: new_seen_at.clone() }
                             .into(),
@@ -189,7 +204,7 @@ impl Notifications {
                         Notification::new(data)
                     })
                     .collect::<Result<Vec<Notification>, String>>();
-                let new_notifs = match new_notifs {
+                let mut new_notifs = match new_notifs {
                     Ok(o) => o,
                     Err(e) => {
                         log::error!("Cannot decode notification {}", e);
@@ -201,22 +216,40 @@ impl Notifications {
                 if let Err(e) = out {
                     log::error!("Cannot update notification seen time {}", e);
                 }
+                // posts that could not be fetched or read
+                new_notifs.retain(|n| match &n.record {
+                    Record::Like(u)
+                    | Record::Repost(u)
+                    | Record::Reply(u)
+                    | Record::Mention(u)
+                    | Record::Quote(u) => post_manager!().at(u).is_some(),
+                    _ => true,
+                });
 
                 // Lets hope there won't be race conditions
                 {
                     let mut seen_at = seen_at.lock().unwrap();
                     *seen_at = Some(new_seen_at);
+                    let Some(feed) = feed.upgrade() else {
+                        return;
+                    };
                     let mut feed = feed.lock().unwrap();
-                    insert_notifs(&mut *feed, new_notifs);
+                    insert_notifs(&mut feed, new_notifs);
                 }
+                redraw!();
             }
         });
     }
 }
 
+// Notifications are not marked as seen for a closed column or when quitting
+fn closed(feed: &Weak<Mutex<Feed>>) -> bool {
+    return feed.strong_count() == 0 || workers!().stopping();
+}
+
 async fn fetch_missing_posts(
-    notifs: &Vec<Notification>,
-    agent: BskyAgent,
+    notifs: &[Notification],
+    agent: Client,
 ) -> Result<(), String> {
     use atrium_api::app::bsky::feed::get_posts;
 
@@ -237,15 +270,18 @@ async fn fetch_missing_posts(
     while !uris.is_empty() {
         let to_fetch = uris.drain(0..25.clamp(0, uris.len())).collect();
         let Object { data, .. } = agent
-            .api
-            .app
-            .bsky
-            .feed
             .get_posts(get_posts::ParametersData { uris: to_fetch }.into())
             .await
             .map_err(|e| e.to_string())?;
         let get_posts::OutputData { posts } = data;
-        post_manager!().append(posts.iter().map(Post::from).collect());
+        post_manager!().append(
+            posts
+                .iter()
+                .filter_map(|p| {
+                    Post::try_from(p).inspect_err(|e| log::error!("{}", e)).ok()
+                })
+                .collect(),
+        );
     }
 
     Ok(())
@@ -299,41 +335,27 @@ fn insert_notifs(notifs: &mut Feed, new_notifs: Vec<Notification>) {
     notifs.notifs = new_notifs;
 }
 
-impl Drop for Notifications {
-    fn drop(&mut self) {
-        let mut t = self.is_terminate_worker.lock().unwrap();
-        *t = true;
-    }
-}
+impl ActionReceiver for &mut Notifications {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
 
-impl EventReceiver for &mut Notifications {
-    async fn handle_events(
-        self,
-        event: ratatui::crossterm::event::Event,
-        agent: BskyAgent,
-    ) -> crate::app::AppEvent {
-        let Event::Key(key) = event.clone().into() else {
-            return AppEvent::None;
-        };
-        match key.code {
-            KeyCode::Backspace => return AppEvent::ColumnPopLayer,
-
-            KeyCode::Char('j') => {
+            Action::Next => {
                 let mut feed = self.feed.lock().unwrap();
-                if feed.state.selected == None {
+                if feed.state.selected.is_none() {
                     feed.state.selected = Some(0);
                 } else {
                     feed.state.next();
                 }
                 return AppEvent::None;
             }
-            KeyCode::Char('k') => {
+            Action::Previous => {
                 let mut feed = self.feed.lock().unwrap();
                 feed.state.previous();
                 return AppEvent::None;
             }
 
-            KeyCode::Char('A') => {
+            Action::NotificationAuthor => {
                 let n = {
                     let feed = self.feed.lock().unwrap();
                     let Some(i) = feed.state.selected else {
@@ -342,12 +364,10 @@ impl EventReceiver for &mut Notifications {
                     feed.notifs[i].clone()
                 };
                 let did = n.author.basic.did;
-                let me = &agent.get_session().await.unwrap().did;
-                let profile = ProfilePage::from_did(did, me, agent);
-                return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+                return ProfilePage::open(did, agent).await;
             }
 
-            KeyCode::Enter => {
+            Action::OpenThread => {
                 let n = {
                     let feed = self.feed.lock().unwrap();
                     let Some(i) = feed.state.selected else {
@@ -371,13 +391,13 @@ impl EventReceiver for &mut Notifications {
                         };
                         return AppEvent::ColumnNewLayer(Column::Thread(view));
                     }
-                    Record::Follow => {
+                    Record::Follow | Record::Unsupported(_) => {
                         return AppEvent::None;
                     }
                 }
             }
 
-            _ => {
+            action => {
                 let n = {
                     let feed = self.feed.lock().unwrap();
                     let Some(i) = feed.state.selected else {
@@ -392,17 +412,33 @@ impl EventReceiver for &mut Notifications {
                     | Record::Mention(u)
                     | Record::Quote(u) => {
                         let post = post_manager!().at(u).unwrap();
-                        return post.handle_events(event, agent).await;
+                        return post.handle_action(action, agent).await;
                     }
                     Record::Follow => {
                         return AppEvent::None;
                     }
+                    Record::Unsupported(u) if action == Action::ViewRaw => {
+                        return RawView::open(std::slice::from_ref(u));
+                    }
+                    Record::Unsupported(_) => {
+                        return AppEvent::None;
+                    }
                 }
             }
         }
     }
 }
 
+impl ClickReceiver for &mut Notifications {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        let mut feed = self.feed.lock().unwrap();
+        if let Some((i, _)) = feed.state.item_at(position) {
+            feed.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
 impl Widget for &mut Notifications {
     fn render(
         self,
@@ -413,7 +449,7 @@ impl Widget for &mut Notifications {
     {
         {
             let seen_at = self.seen_at.lock().unwrap();
-            if *seen_at == None {
+            if seen_at.is_none() {
                 Line::from("Loading").render(area, buf);
             }
         }
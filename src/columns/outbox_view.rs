@@ -0,0 +1,156 @@
+use std::time::Instant;
+
+use ratatui::{
+    layout::Position,
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    components::list::{List, ListState},
+    keymap::Action,
+    outbox,
+    outbox::Item,
+    post_manager,
+};
+
+// Writes waiting in the outbox, read again on every frame
+pub struct OutboxView {
+    state: ListState,
+}
+
+impl OutboxView {
+    pub fn new() -> OutboxView {
+        OutboxView { state: ListState::new(Some(0)) }
+    }
+
+    fn selected(&self) -> Option<Item> {
+        return outbox!().items().get(self.state.selected?).cloned();
+    }
+}
+
+impl ActionReceiver for &mut OutboxView {
+    async fn handle_action(self, action: Action, _: Client) -> AppEvent {
+        match action {
+            Action::Next => {
+                if self.state.selected.map(|i| i + 1)
+                    < Some(outbox!().items().len())
+                {
+                    self.state.next();
+                }
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                self.state.previous();
+                return AppEvent::None;
+            }
+            Action::Back => return AppEvent::ColumnPopLayer,
+            Action::Retry => {
+                let Some(item) = self.selected() else {
+                    return AppEvent::None;
+                };
+                // likes and reposts are rolled back when they fail, they are
+                // sent again as new writes shown on the post until settled
+                if item.failed && post_manager!().resend(&item.op) {
+                    outbox!().discard(item.id);
+                } else {
+                    outbox!().retry(item.id);
+                }
+                return AppEvent::None;
+            }
+            Action::Discard => {
+                if let Some(item) = self.selected() {
+                    outbox!().discard(item.id);
+                }
+                return AppEvent::None;
+            }
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl ClickReceiver for &mut OutboxView {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
+fn status(item: &Item) -> Line<'static> {
+    let error = item.error.clone().unwrap_or_default();
+    if item.failed {
+        return Line::styled(format!("failed: {}", error), Color::LightRed);
+    }
+    match item.retry_at {
+        Some(at) => {
+            let secs = at.saturating_duration_since(Instant::now()).as_secs();
+            return Line::styled(
+                format!(
+                    "attempt {} failed, retrying in {}s: {}",
+                    item.attempts, secs, error
+                ),
+                Color::Yellow,
+            );
+        }
+        None => return Line::styled("sending", Color::DarkGray),
+    }
+}
+
+impl Widget for &mut OutboxView {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let items = outbox!().items();
+        if items.is_empty() {
+            Line::styled("Nothing to send", Color::DarkGray)
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        let list = List::new(items.len(), |context| {
+            let item = &items[context.index];
+            let block = Block::bordered()
+                .border_type(BorderType::Rounded)
+                .border_style(if context.is_selected {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                });
+            let text = Text::from(vec![
+                Line::from(Span::raw(item.op.describe())),
+                status(item),
+            ]);
+            return (OutboxItem { block, text }, 4);
+        });
+        list.render(area, buf, &mut self.state);
+    }
+}
+
+struct OutboxItem {
+    block: Block<'static>,
+    text: Text<'static>,
+}
+
+impl Widget for OutboxItem {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        self.text.render(inner, buf);
+    }
+}
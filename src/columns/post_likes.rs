@@ -1,35 +1,35 @@
-use std::sync::{Arc, Mutex};
-
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{Event, KeyCode},
+    layout::Position,
     style::Color,
     widgets::{Block, BorderType, StatefulWidget, Widget},
 };
+use std::sync::{Arc, Mutex};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
-    columns::Column,
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
     components::{
         actor::{Actor, ActorWidget},
-        list::{List, ListState},
+        list::{List, ListState, Pages},
     },
+    keymap::Action,
+    redraw, workers,
 };
 
 use super::profile_page::ProfilePage;
 
 pub struct PostLikes {
     uri: String,
-    likes: Arc<Mutex<Option<(Vec<Actor>, Option<String>)>>>,
+    likes: Arc<Mutex<Pages<Actor>>>,
     state: ListState,
 }
 
 impl PostLikes {
-    pub fn new(agent: BskyAgent, uri: String) -> Self {
+    pub fn new(agent: Client, uri: String) -> Self {
         let likes = Arc::new(Mutex::new(None));
         let likes_c = Arc::clone(&likes);
         let uri_c = uri.clone();
-        tokio::spawn(async move {
+        workers!().spawn(async move {
             let o = match fetch_likes(agent, uri_c, None).await {
                 Ok(o) => o,
                 Err(e) => {
@@ -39,21 +39,18 @@ impl PostLikes {
             };
             let mut likes = likes_c.lock().unwrap();
             *likes = Some(o);
+            redraw!();
         });
         PostLikes { uri, likes, state: ListState::default() }
     }
 }
 
 async fn fetch_likes(
-    agent: BskyAgent,
+    agent: Client,
     uri: String,
     cursor: Option<String>,
 ) -> Result<(Vec<Actor>, Option<String>), String> {
     let res = agent
-        .api
-        .app
-        .bsky
-        .feed
         .get_likes(
             atrium_api::app::bsky::feed::get_likes::ParametersData {
                 cid: None,
@@ -77,17 +74,10 @@ async fn fetch_likes(
     return Ok((actors, cursor));
 }
 
-impl EventReceiver for &mut PostLikes {
-    async fn handle_events(
-        self,
-        event: ratatui::crossterm::event::Event,
-        agent: BskyAgent,
-    ) -> crate::app::AppEvent {
-        let Event::Key(key) = event.into() else {
-            return AppEvent::None;
-        };
-        match key.code {
-            KeyCode::Char('j') => {
+impl ActionReceiver for &mut PostLikes {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Next => {
                 let likes = {
                     let likes = Arc::clone(&self.likes);
                     let likes = likes.lock().unwrap();
@@ -100,7 +90,7 @@ impl EventReceiver for &mut PostLikes {
                 let (actors, cursor) = likes.unwrap();
                 let likes = Arc::clone(&self.likes);
 
-                if let None = self.state.selected {
+                if self.state.selected.is_none() {
                     self.state.selected = Some(0);
                     return AppEvent::None;
                 }
@@ -108,7 +98,7 @@ impl EventReceiver for &mut PostLikes {
                     && cursor.is_some()
                 {
                     let uri = self.uri.clone();
-                    tokio::spawn(async move {
+                    workers!().spawn(async move {
                         let new_likes =
                             fetch_likes(agent, uri, cursor.clone()).await;
                         let mut new_likes = match new_likes {
@@ -127,30 +117,29 @@ impl EventReceiver for &mut PostLikes {
                         }
                         likes.as_mut().unwrap().0.append(&mut new_likes.0);
                         likes.as_mut().unwrap().1 = new_likes.1;
+                        redraw!();
                     });
                     return AppEvent::None;
                 }
                 self.state.next();
                 return AppEvent::None;
             }
-            KeyCode::Char('k') => {
+            Action::Previous => {
                 self.state.previous();
                 return AppEvent::None;
             }
-            KeyCode::Backspace => return AppEvent::ColumnPopLayer,
+            Action::Back => return AppEvent::ColumnPopLayer,
 
-            KeyCode::Char('a') => {
-                let likes = Arc::clone(&self.likes);
-                let likes = likes.lock().unwrap();
-                if likes.is_none() || self.state.selected.is_none() {
-                    return AppEvent::None;
-                }
-                let i = self.state.selected.unwrap();
-                let actor = &likes.as_ref().unwrap().0[i];
-                let me = &agent.get_session().await.unwrap().did;
-                let profile =
-                    ProfilePage::from_did(actor.basic.did.clone(), me, agent);
-                return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+            Action::AuthorProfile => {
+                let did = {
+                    let likes = self.likes.lock().unwrap();
+                    if likes.is_none() || self.state.selected.is_none() {
+                        return AppEvent::None;
+                    }
+                    let i = self.state.selected.unwrap();
+                    likes.as_ref().unwrap().0[i].basic.did.clone()
+                };
+                return ProfilePage::open(did, agent).await;
             }
 
             _ => return AppEvent::None,
@@ -158,6 +147,15 @@ impl EventReceiver for &mut PostLikes {
     }
 }
 
+impl ClickReceiver for &mut PostLikes {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
 impl Widget for &mut PostLikes {
     fn render(
         self,
@@ -181,7 +179,7 @@ impl Widget for &mut PostLikes {
                         .border_style(Color::DarkGray),
                 )
                 .focused(context.is_selected);
-            let height = item.line_count(area.width) as u16;
+            let height = item.line_count(area.width);
             return (item, height);
         });
         list.render(area, buf, &mut self.state);
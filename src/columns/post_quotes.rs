@@ -0,0 +1,238 @@
+use atrium_api::app::bsky::feed::get_quotes;
+use ratatui::{layout::Position, style::Color, text::Line, widgets::Widget};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::{facet_modal::FacetModalItem, thread_view::ThreadView, Column},
+    components::feed::{FeedPost, FeedPostWidget, PostFeed},
+    keymap::Action,
+    post_manager, redraw, workers,
+};
+
+// Posts quoting a post, newest first
+pub struct PostQuotes {
+    uri: String,
+    // None until the first page is fetched
+    feed: Arc<Mutex<Option<PostFeed>>>,
+}
+
+impl PostQuotes {
+    pub fn new(agent: Client, uri: String) -> Self {
+        let feed = Arc::new(Mutex::new(None));
+        let feed_c = Arc::clone(&feed);
+        let uri_c = uri.clone();
+        workers!().spawn(async move {
+            let (posts, cursor) = match fetch_quotes(agent, uri_c, None).await {
+                Ok(o) => o,
+                Err(e) => {
+                    log::error!("Cannot fetch quotes: {}", e);
+                    return;
+                }
+            };
+            let mut new_feed = PostFeed::default();
+            new_feed.insert_new_posts(posts.into_iter());
+            new_feed.cursor = cursor;
+            *feed_c.lock().unwrap() = Some(new_feed);
+            redraw!();
+        });
+        PostQuotes { uri, feed }
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        if let Some(feed) = &*self.feed.lock().unwrap() {
+            feed.post_uris(uris);
+        }
+    }
+
+    fn selected(&self) -> Option<String> {
+        let feed = self.feed.lock().unwrap();
+        let feed = feed.as_ref()?;
+        return Some(feed.posts.get(feed.state.selected?)?.post_uri.clone());
+    }
+
+    // Appends the next page, the cursor is None once every quote is fetched
+    fn fetch_next_page(&self, agent: Client, cursor: String) {
+        let feed = Arc::clone(&self.feed);
+        let uri = self.uri.clone();
+        let cursor = Some(cursor);
+        workers!().spawn(async move {
+            let (posts, new_cursor) =
+                match fetch_quotes(agent, uri, cursor.clone()).await {
+                    Ok(o) => o,
+                    Err(e) => {
+                        log::error!("Cannot fetch quotes: {}", e);
+                        return;
+                    }
+                };
+            let mut feed = feed.lock().unwrap();
+            let Some(feed) = &mut *feed else {
+                return;
+            };
+            if feed.cursor != cursor {
+                return;
+            }
+            feed.append_old_posts(posts.into_iter());
+            feed.cursor = new_cursor;
+            redraw!();
+        });
+    }
+}
+
+async fn fetch_quotes(
+    agent: Client,
+    uri: String,
+    cursor: Option<String>,
+) -> Result<(Vec<FeedPost>, Option<String>), String> {
+    let res = agent
+        .get_quotes(
+            get_quotes::ParametersData {
+                cid: None,
+                cursor,
+                limit: Some(50.try_into().unwrap()),
+                uri,
+            }
+            .into(),
+        )
+        .await?;
+    let get_quotes::OutputData { cursor, posts, .. } = res.data;
+    let posts = posts
+        .iter()
+        .filter_map(|p| {
+            FeedPost::from_post(p).inspect_err(|e| log::error!("{}", e)).ok()
+        })
+        .collect();
+    return Ok((posts, cursor));
+}
+
+impl ActionReceiver for &mut PostQuotes {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Next => {
+                let cursor = {
+                    let mut feed = self.feed.lock().unwrap();
+                    let Some(feed) = &mut *feed else {
+                        return AppEvent::None;
+                    };
+                    if feed.state.selected.map(|i| i + 1)
+                        < Some(feed.posts.len())
+                    {
+                        feed.state.next();
+                        return AppEvent::None;
+                    }
+                    feed.cursor.clone()
+                };
+                if let Some(cursor) = cursor {
+                    self.fetch_next_page(agent, cursor);
+                }
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                if let Some(feed) = &mut *self.feed.lock().unwrap() {
+                    feed.state.previous();
+                }
+                return AppEvent::None;
+            }
+            Action::Back => return AppEvent::ColumnPopLayer,
+
+            Action::OpenThread | Action::Select => {
+                let Some(uri) = self.selected() else {
+                    return AppEvent::None;
+                };
+                let view = match ThreadView::from_uri(uri, agent).await {
+                    Ok(view) => view,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return AppEvent::None;
+                    }
+                };
+                return AppEvent::ColumnNewLayer(Column::Thread(view));
+            }
+
+            action => {
+                let Some(post) =
+                    self.selected().and_then(|uri| post_manager!().at(&uri))
+                else {
+                    return AppEvent::None;
+                };
+                return post.handle_action(action, agent).await;
+            }
+        }
+    }
+}
+
+impl ClickReceiver for &mut PostQuotes {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        let facet = {
+            let mut feed = self.feed.lock().unwrap();
+            let Some(feed) = &mut *feed else {
+                return AppEvent::None;
+            };
+            let Some((i, at)) = feed.state.item_at(position) else {
+                return AppEvent::None;
+            };
+            feed.state.select_visible(i);
+            let post = &feed.posts[i];
+            FeedPostWidget::new(post)
+                .facet_at(feed.state.area().width, at.x, at.y)
+                .and_then(|f| {
+                    let post = post_manager!().at(&post.post_uri)?;
+                    FacetModalItem::from_facet(&f, &post.text)
+                })
+        };
+        match facet {
+            Some(facet) => return facet.open(agent).await,
+            None => return AppEvent::None,
+        }
+    }
+}
+
+impl Widget for &mut PostQuotes {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let feed = Arc::clone(&self.feed);
+        let mut feed = feed.lock().unwrap();
+        let Some(feed) = &mut *feed else {
+            return;
+        };
+        if feed.posts.is_empty() {
+            Line::styled("No quotes", Color::DarkGray)
+                .centered()
+                .render(area, buf);
+            return;
+        }
+        feed.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    #[tokio::test]
+    async fn quotes_of_post() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let uri = "at://did:plc:alice/app.bsky.feed.post/1".to_string();
+        let (posts, cursor) =
+            fetch_quotes(agent.clone(), uri.clone(), None).await.unwrap();
+        let uris =
+            posts.iter().map(|p| p.post_uri.as_str()).collect::<Vec<_>>();
+        assert_eq!(uris, vec!["at://did:plc:tskyme/app.bsky.feed.post/4"]);
+        assert!(posts[0].reason.is_none() && posts[0].reply_to.is_none());
+        assert_eq!(cursor, None);
+
+        let (posts, _) =
+            fetch_quotes(agent, uris[0].to_string(), None).await.unwrap();
+        assert!(posts.is_empty());
+    }
+}
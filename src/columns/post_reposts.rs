@@ -0,0 +1,201 @@
+use atrium_api::app::bsky::feed::get_reposted_by;
+use ratatui::{
+    layout::Position,
+    style::Color,
+    widgets::{Block, BorderType, StatefulWidget, Widget},
+};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::profile_page::ProfilePage,
+    components::{
+        actor::{Actor, ActorWidget},
+        list::{List, ListState, Pages},
+    },
+    keymap::Action,
+    redraw, workers,
+};
+
+// Accounts that reposted a post
+pub struct PostReposts {
+    uri: String,
+    reposts: Arc<Mutex<Pages<Actor>>>,
+    state: ListState,
+}
+
+impl PostReposts {
+    pub fn new(agent: Client, uri: String) -> Self {
+        let reposts = Arc::new(Mutex::new(None));
+        let reposts_c = Arc::clone(&reposts);
+        let uri_c = uri.clone();
+        workers!().spawn(async move {
+            let o = match fetch_reposts(agent, uri_c, None).await {
+                Ok(o) => o,
+                Err(e) => {
+                    log::error!("Cannot fetch reposts: {}", e);
+                    return;
+                }
+            };
+            *reposts_c.lock().unwrap() = Some(o);
+            redraw!();
+        });
+        PostReposts { uri, reposts, state: ListState::default() }
+    }
+}
+
+async fn fetch_reposts(
+    agent: Client,
+    uri: String,
+    cursor: Option<String>,
+) -> Result<(Vec<Actor>, Option<String>), String> {
+    let res = agent
+        .get_reposted_by(
+            get_reposted_by::ParametersData {
+                cid: None,
+                cursor,
+                limit: Some(100.try_into().unwrap()),
+                uri,
+            }
+            .into(),
+        )
+        .await?;
+    let get_reposted_by::OutputData { cursor, reposted_by, .. } = res.data;
+    let actors =
+        reposted_by.into_iter().map(|actor| Actor::new(actor.data)).collect();
+    return Ok((actors, cursor));
+}
+
+impl ActionReceiver for &mut PostReposts {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Next => {
+                let Some((actors, cursor)) =
+                    self.reposts.lock().unwrap().clone()
+                else {
+                    return AppEvent::None;
+                };
+                let Some(selected) = self.state.selected else {
+                    self.state.selected = Some(0);
+                    return AppEvent::None;
+                };
+                if selected + 1 < actors.len() {
+                    self.state.next();
+                    return AppEvent::None;
+                }
+                if cursor.is_none() {
+                    return AppEvent::None;
+                }
+
+                let reposts = Arc::clone(&self.reposts);
+                let uri = self.uri.clone();
+                workers!().spawn(async move {
+                    let new_reposts =
+                        fetch_reposts(agent, uri, cursor.clone()).await;
+                    let (mut new_actors, new_cursor) = match new_reposts {
+                        Ok(o) => o,
+                        Err(e) => {
+                            log::error!("Cannot fetch reposts: {}", e);
+                            return;
+                        }
+                    };
+                    let mut reposts = reposts.lock().unwrap();
+                    let Some((actors, old_cursor)) = &mut *reposts else {
+                        return;
+                    };
+                    if *old_cursor != cursor {
+                        return;
+                    }
+                    actors.append(&mut new_actors);
+                    *old_cursor = new_cursor;
+                    redraw!();
+                });
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                self.state.previous();
+                return AppEvent::None;
+            }
+            Action::Back => return AppEvent::ColumnPopLayer,
+
+            Action::AuthorProfile => {
+                let did = {
+                    let reposts = self.reposts.lock().unwrap();
+                    let Some((actors, _)) = &*reposts else {
+                        return AppEvent::None;
+                    };
+                    let Some(actor) =
+                        self.state.selected.and_then(|i| actors.get(i))
+                    else {
+                        return AppEvent::None;
+                    };
+                    actor.basic.did.clone()
+                };
+                return ProfilePage::open(did, agent).await;
+            }
+
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl ClickReceiver for &mut PostReposts {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
+impl Widget for &mut PostReposts {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let reposts = Arc::clone(&self.reposts);
+        let reposts = reposts.lock().unwrap();
+        let Some((actors, _)) = &*reposts else {
+            return;
+        };
+
+        let list = List::new(actors.len(), |context| {
+            let item = ActorWidget::new(&actors[context.index])
+                .block(
+                    Block::bordered()
+                        .border_type(BorderType::Rounded)
+                        .border_style(Color::DarkGray),
+                )
+                .focused(context.is_selected);
+            let height = item.line_count(area.width);
+            return (item, height);
+        });
+        list.render(area, buf, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    #[tokio::test]
+    async fn reposters_of_post() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let uri = "at://did:plc:bob/app.bsky.feed.post/3".to_string();
+        let (actors, cursor) =
+            fetch_reposts(agent.clone(), uri, None).await.unwrap();
+        let handles =
+            actors.iter().map(|a| a.basic.handle.as_str()).collect::<Vec<_>>();
+        assert_eq!(handles, vec!["alice.tsky.test"]);
+        assert_eq!(cursor, None);
+
+        let uri = "at://did:plc:alice/app.bsky.feed.post/1".to_string();
+        let (actors, _) = fetch_reposts(agent, uri, None).await.unwrap();
+        assert!(actors.is_empty());
+    }
+}
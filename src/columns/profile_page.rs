@@ -1,54 +1,180 @@
-use std::sync::{Arc, Mutex};
-
 use atrium_api::{
-    app::bsky::{actor::get_profile, feed::get_author_feed},
+    app::bsky::{
+        actor::get_profile,
+        feed::{
+            defs::FeedViewPost, get_actor_feeds, get_actor_likes,
+            get_author_feed,
+        },
+        graph::get_lists,
+    },
     types::{
         string::{AtIdentifier, Did},
         Object,
     },
 };
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{Event, KeyCode},
+    layout::Position,
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, StatefulWidget, Widget},
 };
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
+    columns::{facet_modal::FacetModalItem, updating_feed::FeedSource},
     components::{
-        actor::{ActorDetailed, ActorDetailedWidget},
-        feed::{FeedPost, FeedPostWidget, PostFeed},
-        list::List,
+        actor::{toggle_follow, ActorDetailed, ActorDetailedWidget},
+        embed::{
+            embed_widget::EmbedWidget, Embed, EmbededGenerator, EmbededList,
+            Record,
+        },
+        feed::{FeedPost, FeedPostWidget},
+        list::{List, ListState},
         separation::Separation,
     },
-    post_manager,
+    keymap::{Action, Context},
+    post_manager, redraw, workers,
 };
 
-use super::thread_view::ThreadView;
+use super::{actor_lists::ActorLists, thread_view::ThreadView, Column};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Tab {
+    Posts,
+    Replies,
+    Media,
+    Videos,
+    // only for the user's own profile
+    Likes,
+    Feeds,
+    Lists,
+}
+
+impl Tab {
+    fn name(&self) -> &'static str {
+        match self {
+            Tab::Posts => "Posts",
+            Tab::Replies => "Replies",
+            Tab::Media => "Media",
+            Tab::Videos => "Videos",
+            Tab::Likes => "Likes",
+            Tab::Feeds => "Feeds",
+            Tab::Lists => "Lists",
+        }
+    }
+
+    // Filter of getAuthorFeed for tabs of the actor's posts
+    fn filter(&self) -> Option<&'static str> {
+        match self {
+            Tab::Posts => Some("posts_no_replies"),
+            Tab::Replies => Some("posts_with_replies"),
+            Tab::Media => Some("posts_with_media"),
+            Tab::Videos => Some("posts_with_video"),
+            Tab::Likes | Tab::Feeds | Tab::Lists => None,
+        }
+    }
+}
+
+enum Items {
+    Posts(Vec<FeedPost>),
+    Feeds(Vec<EmbededGenerator>),
+    Lists(Vec<EmbededList>),
+}
+
+impl Items {
+    fn empty(tab: Tab) -> Items {
+        match tab {
+            Tab::Feeds => Items::Feeds(Vec::new()),
+            Tab::Lists => Items::Lists(Vec::new()),
+            _ => Items::Posts(Vec::new()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Items::Posts(posts) => posts.len(),
+            Items::Feeds(feeds) => feeds.len(),
+            Items::Lists(lists) => lists.len(),
+        }
+    }
+
+    fn append(&mut self, items: Items) {
+        match (self, items) {
+            (Items::Posts(posts), Items::Posts(new)) => posts.extend(new),
+            (Items::Feeds(feeds), Items::Feeds(new)) => feeds.extend(new),
+            (Items::Lists(lists), Items::Lists(new)) => lists.extend(new),
+            _ => {}
+        }
+    }
+
+    fn widget(&self, i: usize, selected: bool) -> ProfilePageItemWidget<'_> {
+        match self {
+            Items::Posts(posts) => ProfilePageItemWidget::Post(
+                FeedPostWidget::new(&posts[i]).selected(selected),
+            ),
+            Items::Feeds(feeds) => {
+                ProfilePageItemWidget::Record(EmbedWidget::new(
+                    Embed::Record(Record::Generator(feeds[i].clone())),
+                    selected,
+                ))
+            }
+            Items::Lists(lists) => {
+                ProfilePageItemWidget::Record(EmbedWidget::new(
+                    Embed::Record(Record::List(lists[i].clone())),
+                    selected,
+                ))
+            }
+        }
+    }
+}
+
+// Items of a tab and where the tab is scrolled to. The actor and the tab bar
+// are items 0 and 1 of the list, nothing is selected while the actor is.
+struct Page {
+    items: Items,
+    state: ListState,
+    // None once every page is fetched
+    cursor: Option<String>,
+}
 
 pub struct ProfilePage {
+    did: Did,
     actor: Arc<Mutex<Option<ActorDetailed>>>,
-    feed: Arc<Mutex<PostFeed>>,
+    tabs: Vec<Tab>,
+    tab: Tab,
+    // tabs are fetched the first time they are shown
+    pages: Arc<Mutex<HashMap<Tab, Page>>>,
     actor_selected: bool,
 }
 
 impl ProfilePage {
-    pub fn from_did(did: Did, me: &Did, agent: BskyAgent) -> ProfilePage {
+    // Opens the profile of did as a new layer
+    pub async fn open(did: Did, agent: Client) -> AppEvent {
+        let me = match agent.did().await {
+            Ok(me) => me,
+            Err(e) => {
+                log::error!("Cannot open profile: {}", e);
+                return AppEvent::None;
+            }
+        };
+        let profile = ProfilePage::from_did(did, &me, agent);
+        return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+    }
+
+    pub fn from_did(did: Did, me: &Did, agent: Client) -> ProfilePage {
         let actor = Arc::new(Mutex::new(None));
-        let feed = Arc::new(Mutex::new(PostFeed::default()));
 
         let actor_ = Arc::clone(&actor);
         let did_ = did.clone();
         let agent_ = agent.clone();
         let is_me = did_ == *me;
-        tokio::spawn(async move {
+        workers!().spawn(async move {
             let out = agent_
-                .api
-                .app
-                .bsky
-                .actor
                 .get_profile(
                     get_profile::ParametersData {
                         actor: AtIdentifier::Did(did_),
@@ -65,110 +191,320 @@ impl ProfilePage {
             };
             let mut actor_lock = actor_.lock().unwrap();
             *actor_lock = Some(ActorDetailed::new(out, is_me));
+            redraw!();
         });
 
-        let feed_ = Arc::clone(&feed);
-        let did_ = did.clone();
-        tokio::spawn(async move {
-            let out = agent
-                .api
-                .app
-                .bsky
-                .feed
-                .get_author_feed(
-                    get_author_feed::ParametersData {
-                        actor: AtIdentifier::Did(did_),
-                        cursor: None,
-                        filter: Some("posts_no_replies".into()),
-                        include_pins: Some(true),
-                        limit: Some(100.try_into().unwrap()),
-                    }
-                    .into(),
-                )
-                .await;
-            let get_author_feed::OutputData { cursor, feed } = match out {
-                Ok(Object { data, .. }) => data,
+        let tabs = [
+            Tab::Posts,
+            Tab::Replies,
+            Tab::Media,
+            Tab::Videos,
+            Tab::Likes,
+            Tab::Feeds,
+            Tab::Lists,
+        ]
+        .into_iter()
+        .filter(|t| *t != Tab::Likes || is_me)
+        .collect();
+        let mut profile = ProfilePage {
+            did,
+            actor,
+            tabs,
+            tab: Tab::Posts,
+            pages: Arc::new(Mutex::new(HashMap::new())),
+            actor_selected: true,
+        };
+        profile.switch(Tab::Posts, agent);
+        return profile;
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        for page in self.pages.lock().unwrap().values() {
+            if let Items::Posts(posts) = &page.items {
+                uris.extend(posts.iter().map(|p| p.post_uri.clone()));
+            }
+        }
+    }
+
+    pub fn keymap_contexts(&self) -> Vec<Context> {
+        if self.actor_selected {
+            vec![Context::Actor, Context::Profile, Context::Global]
+        } else if self.tab.filter().is_some() || self.tab == Tab::Likes {
+            vec![Context::Profile, Context::Post, Context::Global]
+        } else {
+            vec![Context::Profile, Context::Global]
+        }
+    }
+
+    // Shows the tab, fetching its first page if it was never shown
+    fn switch(&mut self, tab: Tab, agent: Client) {
+        self.tab = tab;
+        let mut pages = self.pages.lock().unwrap();
+        if let Some(page) = pages.get(&tab) {
+            self.actor_selected = page.state.selected.is_none();
+            return;
+        }
+        self.actor_selected = true;
+        pages.insert(
+            tab,
+            Page {
+                items: Items::empty(tab),
+                state: ListState::default(),
+                cursor: None,
+            },
+        );
+
+        let pages = Arc::clone(&self.pages);
+        let did = self.did.clone();
+        workers!().spawn(async move {
+            let (items, cursor) = match fetch_page(agent, did, tab, None).await
+            {
+                Ok(o) => o,
                 Err(e) => {
-                    log::error!("Cannot fetch actor feed: {}", e);
+                    log::error!("Cannot fetch {}: {}", tab.name(), e);
                     return;
                 }
             };
-            let mut feed_lock = feed_.lock().unwrap();
-            feed_lock.cursor = cursor;
+            let mut pages = pages.lock().unwrap();
+            let Some(page) = pages.get_mut(&tab) else {
+                return;
+            };
+            page.items = items;
+            page.cursor = cursor;
+            redraw!();
+        });
+    }
 
-            let feed = feed.iter().map(FeedPost::from).peekable();
-            feed_lock.insert_new_posts(feed);
-            feed_lock.state.selected = None;
+    // Appends the next page of the current tab
+    fn fetch_next_page(&self, agent: Client, cursor: String) {
+        let pages = Arc::clone(&self.pages);
+        let did = self.did.clone();
+        let tab = self.tab;
+        let cursor = Some(cursor);
+        workers!().spawn(async move {
+            let (items, new_cursor) =
+                match fetch_page(agent, did, tab, cursor.clone()).await {
+                    Ok(o) => o,
+                    Err(e) => {
+                        log::error!("Cannot fetch {}: {}", tab.name(), e);
+                        return;
+                    }
+                };
+            let mut pages = pages.lock().unwrap();
+            let Some(page) = pages.get_mut(&tab) else {
+                return;
+            };
+            if page.cursor != cursor {
+                return;
+            }
+            page.items.append(items);
+            page.cursor = new_cursor;
+            redraw!();
         });
-        ProfilePage { actor, feed, actor_selected: true }
+    }
+
+    fn switch_by(&mut self, delta: isize, agent: Client) {
+        let len = self.tabs.len() as isize;
+        let i = self.tabs.iter().position(|t| *t == self.tab).unwrap() as isize;
+        let tab = self.tabs[(i + delta).rem_euclid(len) as usize];
+        self.switch(tab, agent);
+    }
+
+    // Tab whose name is drawn at x in the tab bar
+    fn tab_at(&self, x: u16) -> Option<Tab> {
+        let mut right = 0;
+        for tab in &self.tabs {
+            right += tab.name().len() as u16 + 1;
+            if x < right {
+                return Some(*tab);
+            }
+        }
+        return None;
+    }
+
+    fn tab_bar(&self) -> Separation<'static> {
+        let spans = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let name = format!("{} ", tab.name());
+                if *tab == self.tab {
+                    Span::styled(name, Color::Green)
+                } else {
+                    Span::styled(name, Color::DarkGray)
+                }
+            })
+            .collect::<Vec<_>>();
+        return Separation::default()
+            .text(Line::from(spans))
+            .line(BorderType::Double)
+            .padding(1);
     }
 }
 
-impl EventReceiver for &mut ProfilePage {
-    async fn handle_events(
-        self,
-        event: ratatui::crossterm::event::Event,
-        agent: BskyAgent,
-    ) -> crate::app::AppEvent {
-        let Event::Key(key) = event else {
-            return AppEvent::None;
-        };
-        match key.code {
-            KeyCode::Backspace => return AppEvent::ColumnPopLayer,
+fn feed_posts(feed: &[FeedViewPost]) -> Vec<FeedPost> {
+    return feed
+        .iter()
+        .filter_map(|p| {
+            FeedPost::try_from(p).inspect_err(|e| log::error!("{}", e)).ok()
+        })
+        .collect();
+}
+
+async fn fetch_page(
+    agent: Client,
+    did: Did,
+    tab: Tab,
+    cursor: Option<String>,
+) -> Result<(Items, Option<String>), String> {
+    let actor = AtIdentifier::Did(did);
+    let limit = Some(100.try_into().unwrap());
+    match tab {
+        Tab::Likes => {
+            let out = agent
+                .get_actor_likes(
+                    get_actor_likes::ParametersData { actor, cursor, limit }
+                        .into(),
+                )
+                .await?;
+            let get_actor_likes::OutputData { cursor, feed } = out.data;
+            return Ok((Items::Posts(feed_posts(&feed)), cursor));
+        }
+        Tab::Feeds => {
+            let out = agent
+                .get_actor_feeds(
+                    get_actor_feeds::ParametersData { actor, cursor, limit }
+                        .into(),
+                )
+                .await?;
+            let get_actor_feeds::OutputData { cursor, feeds } = out.data;
+            let feeds = feeds.iter().map(EmbededGenerator::from).collect();
+            return Ok((Items::Feeds(feeds), cursor));
+        }
+        Tab::Lists => {
+            let out = agent
+                .get_lists(
+                    get_lists::ParametersData { actor, cursor, limit }.into(),
+                )
+                .await?;
+            let get_lists::OutputData { cursor, lists } = out.data;
+            let lists = lists.iter().map(EmbededList::from).collect();
+            return Ok((Items::Lists(lists), cursor));
+        }
+        Tab::Posts | Tab::Replies | Tab::Media | Tab::Videos => {
+            let out = agent
+                .get_author_feed(
+                    get_author_feed::ParametersData {
+                        actor,
+                        cursor,
+                        filter: tab.filter().map(str::to_string),
+                        include_pins: Some(tab == Tab::Posts),
+                        limit,
+                    }
+                    .into(),
+                )
+                .await?;
+            let get_author_feed::OutputData { cursor, feed } = out.data;
+            return Ok((Items::Posts(feed_posts(&feed)), cursor));
+        }
+    }
+}
+
+impl ActionReceiver for &mut ProfilePage {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
 
-            KeyCode::Char('q') => {
+            Action::Quit => {
                 return AppEvent::Quit;
             }
 
-            KeyCode::Char('j') => {
-                let mut feed = self.feed.lock().unwrap();
-                match (feed.state.selected, self.actor_selected) {
-                    (None, true) if feed.posts.len() > 0 => {
+            Action::NextTab => {
+                self.switch_by(1, agent);
+                return AppEvent::None;
+            }
+
+            Action::PreviousTab => {
+                self.switch_by(-1, agent);
+                return AppEvent::None;
+            }
+
+            Action::Next => {
+                let mut pages = self.pages.lock().unwrap();
+                let page = pages.get_mut(&self.tab).unwrap();
+                let len = page.items.len();
+                match (page.state.selected, self.actor_selected) {
+                    (None, true) if len > 0 => {
                         self.actor_selected = false;
-                        feed.state.selected = Some(0);
-                        feed.state.next();
-                        feed.state.next();
+                        page.state.selected = Some(0);
+                        page.state.next();
+                        page.state.next();
                     }
                     (None, _) => {}
+                    (Some(i), false) if i - 2 + 1 < len => {
+                        page.state.next();
+                    }
                     (Some(_), false) => {
-                        feed.state.next();
+                        if let Some(cursor) = page.cursor.clone() {
+                            drop(pages);
+                            self.fetch_next_page(agent, cursor);
+                        }
                     }
                     (Some(_), true) => panic!("How come?"),
                 }
                 return AppEvent::None;
             }
 
-            KeyCode::Char('k') => {
-                let mut feed = self.feed.lock().unwrap();
-                match (feed.state.selected, self.actor_selected) {
+            Action::Previous => {
+                let mut pages = self.pages.lock().unwrap();
+                let page = pages.get_mut(&self.tab).unwrap();
+                match (page.state.selected, self.actor_selected) {
                     (None, _) => {}
                     (Some(2), false) => {
                         self.actor_selected = true;
-                        feed.state.previous();
-                        feed.state.previous();
-                        feed.state.selected = None;
+                        page.state.previous();
+                        page.state.previous();
+                        page.state.selected = None;
                     }
                     (Some(i), false) if i > 2 => {
-                        feed.state.previous();
+                        page.state.previous();
                     }
                     (Some(_), _) => panic!("How come?"),
                 }
                 return AppEvent::None;
             }
 
-            KeyCode::Enter if !self.actor_selected => {
-                let feed = self.feed.lock().unwrap();
-                if feed.state.selected == None {
-                    return AppEvent::None;
-                }
-                let i = feed.state.selected.unwrap() - 2;
-                let uri = &feed.posts[i].post_uri;
-                let thread = ThreadView::from_uri(uri.clone(), agent).await;
+            Action::OpenThread if !self.actor_selected => {
+                let open = {
+                    let pages = self.pages.lock().unwrap();
+                    let page = &pages[&self.tab];
+                    let Some(i) = page.state.selected else {
+                        return AppEvent::None;
+                    };
+                    match &page.items {
+                        Items::Posts(posts) => {
+                            Ok(posts[i - 2].post_uri.clone())
+                        }
+                        Items::Feeds(feeds) => {
+                            Err(FeedSource::Generator(feeds[i - 2].uri.clone()))
+                        }
+                        Items::Lists(lists) => {
+                            Err(FeedSource::List(lists[i - 2].uri.clone()))
+                        }
+                    }
+                };
+                let uri = match open {
+                    Ok(uri) => uri,
+                    Err(source) => {
+                        return AppEvent::ColumnNewLayer(Column::feed(
+                            source, agent,
+                        ))
+                    }
+                };
+                let thread = ThreadView::from_uri(uri, agent).await;
                 match thread {
                     Ok(o) => {
-                        return AppEvent::ColumnNewLayer(super::Column::Thread(
-                            o,
-                        ))
+                        return AppEvent::ColumnNewLayer(Column::Thread(o))
                     }
                     Err(e) => {
                         log::error!("{}", e);
@@ -177,32 +513,98 @@ impl EventReceiver for &mut ProfilePage {
                 }
             }
 
-            _ => {
-                let feed = self.feed.lock().unwrap();
-                match (feed.state.selected, self.actor_selected) {
-                    (None, false) => return AppEvent::None,
-                    (None, true) => {
-                        let mut actor = self.actor.lock().unwrap();
-                        let Some(actor) = &mut *actor else {
+            Action::Lists => {
+                return AppEvent::ColumnNewLayer(Column::ActorLists(
+                    ActorLists::new(agent, self.did.clone()),
+                ));
+            }
+
+            Action::Follow if self.actor_selected => {
+                toggle_follow(&self.actor);
+                return AppEvent::None;
+            }
+
+            action => {
+                let post_uri = {
+                    let pages = self.pages.lock().unwrap();
+                    let page = &pages[&self.tab];
+                    match (page.state.selected, self.actor_selected) {
+                        (None, false) => return AppEvent::None,
+                        (None, true) => None,
+                        (Some(i), false) if i >= 2 => match &page.items {
+                            Items::Posts(posts) => {
+                                Some(posts[i - 2].post_uri.clone())
+                            }
+                            _ => return AppEvent::None,
+                        },
+                        (Some(_), _) => panic!("How come?"),
+                    }
+                };
+                match post_uri {
+                    None => {
+                        let actor = self.actor.lock().unwrap().clone();
+                        let Some(mut actor) = actor else {
                             return AppEvent::None;
                         };
-                        actor.handle_events(event, agent).await;
+                        actor.handle_action(action, agent).await;
                         return AppEvent::None;
                     }
-                    (Some(i), false) if i >= 2 => {
-                        let post = post_manager!()
-                            .at(&feed.posts[i - 2].post_uri)
-                            .unwrap();
-                        drop(feed);
-                        return post.handle_events(event, agent).await;
+                    Some(uri) => {
+                        let post = post_manager!().at(&uri).unwrap();
+                        return post.handle_action(action, agent).await;
                     }
-                    (Some(_), _) => panic!("How come?"),
                 }
             }
         }
     }
 }
 
+impl ClickReceiver for &mut ProfilePage {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        let facet = {
+            let mut pages = self.pages.lock().unwrap();
+            let page = pages.get_mut(&self.tab).unwrap();
+            let Some((i, at)) = page.state.item_at(position) else {
+                return AppEvent::None;
+            };
+            // the actor is selected with no item selected, 1 is the tab bar
+            match i {
+                0 => {
+                    page.state.select_visible(0);
+                    page.state.selected = None;
+                    self.actor_selected = true;
+                    return AppEvent::None;
+                }
+                1 => {
+                    drop(pages);
+                    if let Some(tab) = self.tab_at(at.x) {
+                        self.switch(tab, agent);
+                    }
+                    return AppEvent::None;
+                }
+                _ => {}
+            }
+            page.state.select_visible(i);
+            self.actor_selected = false;
+
+            let Items::Posts(posts) = &page.items else {
+                return AppEvent::None;
+            };
+            let post = &posts[i - 2];
+            FeedPostWidget::new(post)
+                .facet_at(page.state.area().width, at.x, at.y)
+                .and_then(|f| {
+                    let post = post_manager!().at(&post.post_uri)?;
+                    FacetModalItem::from_facet(&f, &post.text)
+                })
+        };
+        match facet {
+            Some(facet) => return facet.open(agent).await,
+            None => return AppEvent::None,
+        }
+    }
+}
+
 impl Widget for &mut ProfilePage {
     fn render(
         self,
@@ -211,67 +613,59 @@ impl Widget for &mut ProfilePage {
     ) where
         Self: Sized,
     {
-        let mut feed = self.feed.lock().unwrap();
         let actor = self.actor.lock().unwrap();
-        if actor.is_none() {
+        let Some(actor) = &*actor else {
             Line::from("Loading").render(area, buf);
             return;
-        }
+        };
 
-        let feed = &mut *feed as *mut PostFeed;
-        unsafe {
-            let actor_block = Block::bordered()
-                .title(Span::styled("Profile", Color::Gray))
-                .border_type(BorderType::Rounded)
-                .border_style(Color::DarkGray)
-                .style(if self.actor_selected {
-                    Style::default().bg(Color::Rgb(45, 50, 55))
-                } else {
-                    Style::default()
-                });
-            let items =
-                std::iter::once(ProfilePageItemWidget::Actor(
-                    ActorDetailedWidget::new((*actor).as_ref().unwrap())
-                        .focused(self.actor_selected)
-                        .block(actor_block),
-                ))
-                .chain(std::iter::once(ProfilePageItemWidget::Bar(
-                    Separation::default()
-                        .text(Line::from("Posts ").style(Color::Green))
-                        .line(BorderType::Double)
-                        .padding(1),
-                )))
-                .chain((*feed).posts.iter().map(|p| {
-                    ProfilePageItemWidget::Post(FeedPostWidget::new(p))
-                }))
-                .collect::<Vec<_>>();
-
-            let old_selected = (*feed).state.selected;
-            (*feed).state.selected = match old_selected {
-                None => Some(0),
-                Some(s) => Some(s),
-            };
+        let actor_block = Block::bordered()
+            .title(Span::styled("Profile", Color::Gray))
+            .border_type(BorderType::Rounded)
+            .border_style(Color::DarkGray)
+            .style(if self.actor_selected {
+                Style::default().bg(Color::Rgb(45, 50, 55))
+            } else {
+                Style::default()
+            });
+        let tab_bar = self.tab_bar();
+        let actor_selected = self.actor_selected;
 
-            List::new((*feed).posts.len() + 2, move |context| {
-                let item =
-                    items[context.index].clone().select(context.is_selected);
-                let height = item.line_count(area.width);
-                return (item, height);
-            })
-            .render(area, buf, &mut (*feed).state);
+        let mut pages = self.pages.lock().unwrap();
+        let Page { items, state, .. } = pages.get_mut(&self.tab).unwrap();
 
-            if old_selected == None {
-                (*feed).state.selected = None;
-            }
+        let old_selected = state.selected;
+        state.selected = match old_selected {
+            None => Some(0),
+            Some(s) => Some(s),
+        };
+
+        List::new(items.len() + 2, |context| {
+            let item = match context.index {
+                0 => ProfilePageItemWidget::Actor(
+                    ActorDetailedWidget::new(actor)
+                        .focused(actor_selected)
+                        .block(actor_block.clone()),
+                ),
+                1 => ProfilePageItemWidget::Bar(tab_bar.clone()),
+                i => items.widget(i - 2, context.is_selected),
+            };
+            let height = item.line_count(area.width);
+            return (item, height);
+        })
+        .render(area, buf, state);
+
+        if old_selected.is_none() {
+            state.selected = None;
         }
     }
 }
 
-#[derive(Clone)]
 enum ProfilePageItemWidget<'a> {
     Post(FeedPostWidget<'a>),
     Actor(ActorDetailedWidget<'a>),
     Bar(Separation<'a>),
+    Record(EmbedWidget),
 }
 
 impl<'a> ProfilePageItemWidget<'a> {
@@ -280,14 +674,7 @@ impl<'a> ProfilePageItemWidget<'a> {
             Self::Post(p) => p.line_count(width),
             Self::Actor(a) => a.line_count(width),
             Self::Bar(b) => b.line_count(width),
-        }
-    }
-
-    fn select(self, selected: bool) -> Self {
-        match self {
-            Self::Post(p) => Self::Post(p.is_selected(selected)),
-            Self::Actor(a) => Self::Actor(a.focused(selected)),
-            Self::Bar(b) => Self::Bar(b),
+            Self::Record(r) => r.line_count(width),
         }
     }
 }
@@ -304,6 +691,52 @@ impl<'a> Widget for ProfilePageItemWidget<'a> {
             ProfilePageItemWidget::Post(a) => a.render(area, buf),
             ProfilePageItemWidget::Actor(a) => a.render(area, buf),
             ProfilePageItemWidget::Bar(a) => a.render(area, buf),
+            ProfilePageItemWidget::Record(a) => a.render(area, buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    async fn uris(agent: &Client, did: &str, tab: Tab) -> Vec<String> {
+        let did = Did::new(did.to_string()).unwrap();
+        let (items, _) =
+            fetch_page(agent.clone(), did, tab, None).await.unwrap();
+        match items {
+            Items::Posts(posts) => {
+                posts.into_iter().map(|p| p.post_uri).collect()
+            }
+            Items::Feeds(feeds) => feeds.into_iter().map(|f| f.uri).collect(),
+            Items::Lists(lists) => lists.into_iter().map(|l| l.uri).collect(),
         }
     }
+
+    #[tokio::test]
+    async fn tabs() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let bob = "did:plc:bob";
+        assert_eq!(
+            uris(&agent, bob, Tab::Posts).await,
+            vec!["at://did:plc:bob/app.bsky.feed.post/3"]
+        );
+        // with bob's reply to alice
+        assert_eq!(uris(&agent, bob, Tab::Replies).await.len(), 2);
+        assert!(uris(&agent, bob, Tab::Media).await.is_empty());
+        assert!(uris(&agent, bob, Tab::Videos).await.is_empty());
+        assert_eq!(
+            uris(&agent, "did:plc:alice", Tab::Likes).await,
+            vec!["at://did:plc:tskyme/app.bsky.feed.post/4"]
+        );
+        assert_eq!(
+            uris(&agent, bob, Tab::Feeds).await,
+            vec!["at://did:plc:bob/app.bsky.feed.generator/cats"]
+        );
+        assert_eq!(
+            uris(&agent, bob, Tab::Lists).await,
+            vec!["at://did:plc:bob/app.bsky.graph.list/people"]
+        );
+    }
 }
@@ -0,0 +1,91 @@
+use ratatui::{
+    style::Color,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Widget},
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent},
+    client::Client,
+    columns::Column,
+    components::{paragraph::Paragraph, unsupported::Unsupported},
+    keymap::Action,
+};
+
+// JSON of objects tsky cannot show
+pub struct RawView {
+    lines: Vec<String>,
+    scroll: usize,
+}
+
+impl RawView {
+    pub fn new(unsupported: &[Unsupported]) -> RawView {
+        let lines = unsupported
+            .iter()
+            .flat_map(|u| {
+                std::iter::once(u.placeholder())
+                    .chain(u.json.lines().map(str::to_string))
+                    .chain(std::iter::once(String::new()))
+            })
+            .collect();
+        RawView { lines, scroll: 0 }
+    }
+
+    // Opens a layer unless there is nothing to show
+    pub fn open(unsupported: &[Unsupported]) -> AppEvent {
+        if unsupported.is_empty() {
+            log::info!("Nothing unsupported in this post");
+            return AppEvent::None;
+        }
+        return AppEvent::ColumnNewLayer(Column::RawView(RawView::new(
+            unsupported,
+        )));
+    }
+}
+
+impl ActionReceiver for &mut RawView {
+    async fn handle_action(self, action: Action, _: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
+            Action::Next => {
+                self.scroll = self.scroll.saturating_add(1);
+            }
+            Action::Previous => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            Action::Top => {
+                self.scroll = 0;
+            }
+            // clamped when rendering
+            Action::Bottom => {
+                self.scroll = usize::MAX;
+            }
+            _ => {}
+        }
+        return AppEvent::None;
+    }
+}
+
+impl Widget for &mut RawView {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Color::DarkGray)
+            .title(Span::styled("Raw JSON", Color::Gray));
+        let text = Paragraph::new(
+            self.lines
+                .iter()
+                .map(|l| Line::from(l.as_str()))
+                .collect::<Vec<_>>(),
+        );
+        let line_count = text.line_count(block.inner(area).width) as usize;
+        self.scroll = self.scroll.min(line_count.saturating_sub(1));
+        text.block(block).scroll(self.scroll).render(area, buf);
+    }
+}
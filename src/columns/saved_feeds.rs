@@ -0,0 +1,395 @@
+use std::{
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
+
+use atrium_api::{
+    app::bsky::{
+        actor::defs::{PreferencesItem, SavedFeed, SavedFeedsPrefV2Data},
+        feed::get_feed_generators,
+        graph::get_list,
+    },
+    types::Union,
+};
+use ratatui::{
+    layout::Position,
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::{self, Client},
+    columns::{updating_feed::FeedSource, Column},
+    components::list::{List, ListState},
+    config,
+    keymap::Action,
+    redraw, workers,
+};
+
+// A saved feed with what is shown for it
+#[derive(Clone)]
+struct Entry {
+    saved: SavedFeed,
+    name: String,
+    // e.g. "feed by @handle"
+    about: String,
+    avatar: Option<String>,
+}
+
+// Feeds and lists pinned or saved in the user's preferences. Changes to the
+// order and pins are written back to the preferences.
+pub struct SavedFeeds {
+    entries: Arc<Mutex<Option<Vec<Entry>>>>,
+    // writes the preferences one change at a time
+    saving: Arc<tokio::sync::Mutex<()>>,
+    state: ListState,
+}
+
+impl SavedFeeds {
+    pub fn new(agent: Client) -> SavedFeeds {
+        let entries = Arc::new(Mutex::new(None));
+        let entries_c = Arc::clone(&entries);
+        workers!().spawn(async move {
+            match load(&agent).await {
+                Ok(loaded) => {
+                    *entries_c.lock().unwrap() = Some(loaded);
+                    redraw!();
+                }
+                Err(e) => log::error!("Cannot fetch saved feeds: {}", e),
+            }
+        });
+        SavedFeeds {
+            entries,
+            saving: Arc::new(tokio::sync::Mutex::new(())),
+            state: ListState::new(Some(0)),
+        }
+    }
+
+    fn selected(&self) -> Option<Entry> {
+        let entries = self.entries.lock().unwrap();
+        return entries.as_ref()?.get(self.state.selected?).cloned();
+    }
+
+    // Changes the entries and writes them back, restoring them if the write
+    // fails
+    fn change(&mut self, agent: Client, f: impl FnOnce(&mut Vec<Entry>)) {
+        let previous = {
+            let mut entries = self.entries.lock().unwrap();
+            let Some(entries) = entries.as_mut() else {
+                return;
+            };
+            let previous = entries.clone();
+            f(entries);
+            previous
+        };
+        redraw!();
+
+        let entries = Arc::clone(&self.entries);
+        let saving = Arc::clone(&self.saving);
+        workers!().spawn(async move {
+            let _saving = saving.lock().await;
+            let Some(items) =
+                entries.lock().unwrap().as_ref().map(|e| {
+                    e.iter().map(|e| e.saved.clone()).collect::<Vec<_>>()
+                })
+            else {
+                return;
+            };
+            if let Err(e) = save(&agent, items).await {
+                log::error!("Cannot save feeds: {}", e);
+                *entries.lock().unwrap() = Some(previous);
+                redraw!();
+            }
+        });
+    }
+}
+
+async fn saved_feeds(agent: &Client) -> Result<Vec<SavedFeed>, String> {
+    let items =
+        client::preferences(agent).await?.into_iter().find_map(|p| match p {
+            Union::Refs(PreferencesItem::SavedFeedsPrefV2(p)) => {
+                Some(p.data.items)
+            }
+            _ => None,
+        });
+    return Ok(items.unwrap_or_default());
+}
+
+// Reads the saved feeds and resolves the names of generators and lists
+async fn load(agent: &Client) -> Result<Vec<Entry>, String> {
+    let saved = saved_feeds(agent).await?;
+
+    let generators = saved
+        .iter()
+        .filter(|s| s.r#type == "feed")
+        .map(|s| s.value.clone())
+        .collect::<Vec<_>>();
+    let generators = if generators.is_empty() {
+        Vec::new()
+    } else {
+        agent
+            .get_feed_generators(
+                get_feed_generators::ParametersData { feeds: generators }
+                    .into(),
+            )
+            .await?
+            .data
+            .feeds
+    };
+
+    let mut entries = Vec::new();
+    for s in saved {
+        let mut entry = Entry {
+            name: s.value.clone(),
+            about: s.r#type.clone(),
+            avatar: None,
+            saved: s,
+        };
+        match entry.saved.r#type.as_str() {
+            "timeline" => {
+                entry.name = "Following".to_string();
+                entry.about = "timeline".to_string();
+            }
+            "feed" => {
+                if let Some(g) =
+                    generators.iter().find(|g| g.uri == entry.saved.value)
+                {
+                    entry.name = g.display_name.clone();
+                    entry.about =
+                        format!("feed by @{}", g.creator.handle.as_str());
+                    entry.avatar = g.avatar.clone();
+                }
+            }
+            "list" => {
+                let list = agent
+                    .get_list(
+                        get_list::ParametersData {
+                            cursor: None,
+                            limit: Some(1.try_into().unwrap()),
+                            list: entry.saved.value.clone(),
+                        }
+                        .into(),
+                    )
+                    .await;
+                match list {
+                    Ok(o) => {
+                        let list = o.data.list;
+                        entry.name = list.name.clone();
+                        entry.about = format!(
+                            "list by @{}",
+                            list.creator.handle.as_str()
+                        );
+                        entry.avatar = list.avatar.clone();
+                    }
+                    // deleted lists stay in the preferences
+                    Err(e) => log::error!(
+                        "Cannot fetch list {}: {}",
+                        entry.saved.value,
+                        e
+                    ),
+                }
+            }
+            _ => {}
+        }
+        entries.push(entry);
+    }
+    return Ok(entries);
+}
+
+async fn save(agent: &Client, items: Vec<SavedFeed>) -> Result<(), String> {
+    let saved = PreferencesItem::SavedFeedsPrefV2(Box::new(
+        SavedFeedsPrefV2Data { items }.into(),
+    ));
+    return client::put_preference(agent, saved).await;
+}
+
+impl ActionReceiver for &mut SavedFeeds {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        let len = self.entries.lock().unwrap().as_ref().map_or(0, Vec::len);
+        match action {
+            Action::Next => {
+                if self.state.selected.map(|i| i + 1) < Some(len) {
+                    self.state.next();
+                }
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                self.state.previous();
+                return AppEvent::None;
+            }
+            Action::Back => return AppEvent::ColumnPopLayer,
+
+            Action::Select => {
+                let Some(entry) = self.selected() else {
+                    return AppEvent::None;
+                };
+                let value = entry.saved.value.clone();
+                let source = match entry.saved.r#type.as_str() {
+                    "timeline" => FeedSource::Following,
+                    "feed" => FeedSource::Generator(value),
+                    "list" => FeedSource::List(value),
+                    other => {
+                        log::error!("Cannot open saved {} {}", other, value);
+                        return AppEvent::None;
+                    }
+                };
+                return AppEvent::ColumnNewLayer(Column::feed(source, agent));
+            }
+
+            Action::PinToggle => {
+                let Some(i) = self.state.selected.filter(|&i| i < len) else {
+                    return AppEvent::None;
+                };
+                self.change(agent, |entries| {
+                    entries[i].saved.pinned = !entries[i].saved.pinned;
+                });
+                return AppEvent::None;
+            }
+            Action::MoveUp => {
+                let Some(i) = self.state.selected.filter(|&i| i > 0 && i < len)
+                else {
+                    return AppEvent::None;
+                };
+                self.change(agent, |entries| entries.swap(i - 1, i));
+                self.state.previous();
+                return AppEvent::None;
+            }
+            Action::MoveDown => {
+                let Some(i) = self.state.selected.filter(|&i| i + 1 < len)
+                else {
+                    return AppEvent::None;
+                };
+                self.change(agent, |entries| entries.swap(i, i + 1));
+                self.state.next();
+                return AppEvent::None;
+            }
+
+            Action::OpenMedia => {
+                let Some(avatar) = self.selected().and_then(|e| e.avatar)
+                else {
+                    log::info!("Avatar not set");
+                    return AppEvent::None;
+                };
+                if let Err(e) = config::command(&config!().media.image_viewer)
+                    .arg(avatar)
+                    .stderr(Stdio::null())
+                    .stdout(Stdio::null())
+                    .spawn()
+                {
+                    log::error!("{:?}", e);
+                }
+                return AppEvent::None;
+            }
+
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl ClickReceiver for &mut SavedFeeds {
+    async fn handle_click(self, position: Position, _: Client) -> AppEvent {
+        if let Some((i, _)) = self.state.item_at(position) {
+            self.state.select_visible(i);
+        }
+        return AppEvent::None;
+    }
+}
+
+impl Widget for &mut SavedFeeds {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let entries = Arc::clone(&self.entries);
+        let entries = entries.lock().unwrap();
+        let Some(entries) = &*entries else {
+            return;
+        };
+        if entries.is_empty() {
+            Line::styled("No saved feeds", Color::DarkGray)
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        let list = List::new(entries.len(), |context| {
+            let entry = &entries[context.index];
+            let block = Block::bordered()
+                .border_type(BorderType::Rounded)
+                .border_style(if context.is_selected {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                });
+            let pin = if entry.saved.pinned {
+                Span::styled("pinned ", Color::Yellow)
+            } else {
+                Span::raw("")
+            };
+            let text = Text::from(vec![
+                Line::from(vec![pin, Span::raw(entry.name.clone())]),
+                Line::styled(entry.about.clone(), Color::DarkGray),
+            ]);
+            return (SavedFeedItem { block, text }, 4);
+        });
+        list.render(area, buf, &mut self.state);
+    }
+}
+
+struct SavedFeedItem {
+    block: Block<'static>,
+    text: Text<'static>,
+}
+
+impl Widget for SavedFeedItem {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        self.text.render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    #[tokio::test]
+    async fn reorder_and_pin_are_saved() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let entries = load(&agent).await.unwrap();
+        let names = entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Following", "cats", "people"]);
+        assert_eq!(entries[2].about, "list by @bob.tsky.test");
+
+        let mut items =
+            entries.into_iter().map(|e| e.saved).collect::<Vec<_>>();
+        items.swap(0, 1);
+        items[2].pinned = true;
+        save(&agent, items).await.unwrap();
+
+        let saved = saved_feeds(&agent).await.unwrap();
+        let values = saved.iter().map(|s| s.value.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            values[0],
+            "at://did:plc:alice/app.bsky.feed.generator/cats"
+        );
+        assert_eq!(values[1], "following");
+        assert!(saved.iter().all(|s| s.pinned));
+
+        // other preferences are kept
+        let preferences = client::preferences(&agent).await.unwrap();
+        assert_eq!(preferences.len(), 4);
+    }
+}
@@ -1,33 +1,36 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        mpsc::{self, Sender},
-        Arc, Mutex,
-    },
-};
-
-use atrium_api::types::Object;
-use bsky_sdk::BskyAgent;
+use atrium_api::{app::bsky::feed::search_posts, types::Object};
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    layout::{Constraint, Layout},
+    crossterm::event::{self, Event},
+    layout::{Constraint, Layout, Position, Rect},
     style::{Color, Style},
-    text::Span,
+    text::{Line, Span},
     widgets::{Block, BorderType, StatefulWidget, Widget},
 };
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
-    columns::{profile_page::ProfilePage, Column},
+    app::{ActionReceiver, AppEvent, ClickReceiver, EventReceiver},
+    client::Client,
+    columns::{
+        facet_modal::FacetModalItem, profile_page::ProfilePage,
+        thread_view::ThreadView, Column,
+    },
     components::{
         actor::{ActorBasic, ActorBasicWidget},
         composer::{
             textarea::{Input, Key},
             vim::{InputMode, Vim},
         },
+        feed::{FeedPost, FeedPostWidget, PostFeed},
         list::{List, ListState},
         separation::Separation,
     },
+    keymap::{Action, Context},
+    post_manager, redraw, search_query, workers,
 };
 
 struct SearchFeed {
@@ -43,7 +46,6 @@ impl SearchFeed {
 
 enum SearchWorkerMsg {
     Search(String),
-    Close,
 }
 
 enum Focus {
@@ -51,12 +53,44 @@ enum Focus {
     Results,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // users as you type
+    Users,
+    // posts once the query is entered
+    Posts,
+}
+
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Users => "Users",
+            Mode::Posts => "Posts",
+        }
+    }
+}
+
+const MODES: [Mode; 2] = [Mode::Users, Mode::Posts];
+
+// Posts found for a query, the feed cursor is the one of searchPosts
+struct PostResults {
+    query: String,
+    params: search_posts::ParametersData,
+    feed: PostFeed,
+}
+
 pub struct SearchView {
     searchbar: Vim,
     kv: Arc<Mutex<HashMap<String, Vec<ActorBasic>>>>,
     feed: Option<SearchFeed>,
     focus: Focus,
-    tx: Sender<SearchWorkerMsg>,
+    mode: Mode,
+    // None until a post search returns
+    posts: Arc<Mutex<Option<PostResults>>>,
+    // where the mode names were last rendered
+    modes_area: Rect,
+    // the worker stops once this is dropped
+    tx: UnboundedSender<SearchWorkerMsg>,
 }
 
 macro_rules! request_retry {
@@ -79,28 +113,21 @@ macro_rules! request_retry {
 }
 
 impl SearchView {
-    pub fn new(agent: BskyAgent) -> Self {
+    pub fn new(agent: Client) -> Self {
         let searchbar =
             Vim::new(|i| !matches!(i, Input { key: Key::Enter, .. }));
         let kv = Arc::new(Mutex::new(HashMap::new()));
         let feed = None;
-        let (tx, rx) = mpsc::channel();
+        let (tx, mut rx) = mpsc::unbounded_channel();
 
         let kv_ = Arc::clone(&kv);
-        tokio::spawn(async move {
-            let kv = kv_;
-            loop {
-                use atrium_api::app::bsky::actor::search_actors_typeahead::{
-                    OutputData, ParametersData,
-                };
-
-                let Ok(msg) = rx.recv() else {
-                    log::error!(
-                        "Error receiving request message in search view worker"
-                    );
-                    return;
-                };
+        workers!().spawn(async move {
+            use atrium_api::app::bsky::actor::search_actors_typeahead::{
+                OutputData, ParametersData,
+            };
 
+            let kv = kv_;
+            while let Some(msg) = rx.recv().await {
                 match msg {
                     SearchWorkerMsg::Search(s) => {
                         {
@@ -112,10 +139,6 @@ impl SearchView {
                         let Some(Object { data, .. }) = request_retry!(
                             3,
                             agent
-                                .api
-                                .app
-                                .bsky
-                                .actor
                                 .search_actors_typeahead(
                                     ParametersData {
                                         limit: Some(8.try_into().unwrap()),
@@ -135,15 +158,22 @@ impl SearchView {
                             .collect::<Vec<_>>();
                         let mut kv = kv.lock().unwrap();
                         kv.insert(s, actors);
-                    }
-                    SearchWorkerMsg::Close => {
-                        return;
+                        redraw!();
                     }
                 }
             }
         });
 
-        Self { searchbar, kv, feed, focus: Focus::SearchBar, tx }
+        Self {
+            searchbar,
+            kv,
+            feed,
+            focus: Focus::SearchBar,
+            mode: Mode::Users,
+            posts: Arc::new(Mutex::new(None)),
+            modes_area: Rect::default(),
+            tx,
+        }
     }
 
     pub fn refresh(&mut self) {
@@ -165,6 +195,140 @@ impl SearchView {
         }
     }
 
+    // The search bar takes raw key events
+    pub fn keymap_contexts(&self) -> Option<Vec<Context>> {
+        match self.focus {
+            Focus::SearchBar => None,
+            Focus::Results if self.mode == Mode::Posts => {
+                Some(vec![Context::Search, Context::Post, Context::Global])
+            }
+            Focus::Results => Some(vec![Context::Search, Context::Global]),
+        }
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        if let Some(results) = &*self.posts.lock().unwrap() {
+            results.feed.post_uris(uris);
+        }
+    }
+
+    // Fills the search bar and starts searching
+    pub fn search(&mut self, q: String) {
+        self.searchbar.textarea.insert_string(q);
+        self.searchbar.mode = InputMode::Normal;
+        self.send_search_requet();
+    }
+
+    // Fills the search bar and searches posts with it
+    pub fn search_posts(&mut self, q: String, agent: Client) {
+        self.searchbar.textarea.insert_string(q);
+        self.searchbar.mode = InputMode::Normal;
+        self.mode = Mode::Posts;
+        self.focus = Focus::Results;
+        self.send_post_search(agent);
+    }
+
+    fn switch(&mut self, mode: Mode, agent: Client) {
+        self.mode = mode;
+        match mode {
+            Mode::Users => self.send_search_requet(),
+            Mode::Posts => {
+                // search again if the query was edited in the users mode
+                let searched = match &*self.posts.lock().unwrap() {
+                    Some(results) => results.query == self.query(),
+                    None => false,
+                };
+                if !searched {
+                    self.send_post_search(agent);
+                }
+            }
+        }
+    }
+
+    fn query(&self) -> String {
+        return self.searchbar.textarea.lines().join("").trim().to_string();
+    }
+
+    fn selected_post(&self) -> Option<String> {
+        let results = self.posts.lock().unwrap();
+        let feed = &results.as_ref()?.feed;
+        return Some(feed.posts.get(feed.state.selected?)?.post_uri.clone());
+    }
+
+    // Replaces the post results with the first page of the query
+    fn send_post_search(&self, agent: Client) {
+        let q = self.query();
+        if q.is_empty() {
+            return;
+        }
+        let posts = Arc::clone(&self.posts);
+        workers!().spawn(async move {
+            let params =
+                agent.did().await.and_then(|me| search_query::parse(&q, &me));
+            let params = match params {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("{}", e);
+                    return;
+                }
+            };
+            let found = fetch_posts(agent, params.clone()).await;
+            let (found, cursor) = match found {
+                Ok(o) => o,
+                Err(e) => {
+                    log::error!("Cannot search posts: {}", e);
+                    return;
+                }
+            };
+            let mut feed = PostFeed::default();
+            feed.insert_new_posts(found.into_iter());
+            feed.cursor = cursor;
+            *posts.lock().unwrap() =
+                Some(PostResults { query: q, params, feed });
+            redraw!();
+        });
+    }
+
+    // Appends the next page of the post results
+    fn fetch_next_page(&self, agent: Client) {
+        let (query, params) = {
+            let results = self.posts.lock().unwrap();
+            let Some(results) = &*results else {
+                return;
+            };
+            if results.feed.cursor.is_none() {
+                return;
+            }
+            let params = search_posts::ParametersData {
+                cursor: results.feed.cursor.clone(),
+                ..results.params.clone()
+            };
+            (results.query.clone(), params)
+        };
+        let posts = Arc::clone(&self.posts);
+        workers!().spawn(async move {
+            let found = fetch_posts(agent, params.clone()).await;
+            let (found, cursor) = match found {
+                Ok(o) => o,
+                Err(e) => {
+                    log::error!("Cannot search posts: {}", e);
+                    return;
+                }
+            };
+            let mut results = posts.lock().unwrap();
+            let Some(results) = &mut *results else {
+                return;
+            };
+            // a new search or another page came first
+            if results.query != query || results.feed.cursor != params.cursor {
+                return;
+            }
+            results.feed.append_old_posts(found.into_iter());
+            results.feed.cursor = cursor;
+            redraw!();
+        });
+    }
+
     fn handle_pasting(&mut self, s: String) {
         if matches!(self.focus, Focus::SearchBar) {
             self.searchbar.textarea.insert_string(s);
@@ -180,17 +344,26 @@ impl SearchView {
     }
 }
 
-impl Drop for SearchView {
-    fn drop(&mut self) {
-        self.tx.send(SearchWorkerMsg::Close).unwrap();
-    }
+async fn fetch_posts(
+    agent: Client,
+    params: search_posts::ParametersData,
+) -> Result<(Vec<FeedPost>, Option<String>), String> {
+    let res = agent.search_posts(params.into()).await?;
+    let search_posts::OutputData { cursor, posts, .. } = res.data;
+    let posts = posts
+        .iter()
+        .filter_map(|p| {
+            FeedPost::from_post(p).inspect_err(|e| log::error!("{}", e)).ok()
+        })
+        .collect();
+    return Ok((posts, cursor));
 }
 
 impl EventReceiver for &mut SearchView {
     async fn handle_events(
         self,
         event: ratatui::crossterm::event::Event,
-        agent: BskyAgent,
+        agent: Client,
     ) -> crate::app::AppEvent {
         let key = match event.clone() {
             Event::Key(key) => key,
@@ -216,63 +389,198 @@ impl EventReceiver for &mut SearchView {
                 {
                     return AppEvent::ColumnPopLayer;
                 }
+                Input { key: Key::Enter, .. } if self.mode == Mode::Posts => {
+                    self.send_post_search(agent);
+                    self.focus = Focus::Results;
+                    return AppEvent::None;
+                }
                 _ => {
                     let r = self.searchbar.handle_events(event, agent).await;
-                    self.send_search_requet();
+                    if self.mode == Mode::Users {
+                        self.send_search_requet();
+                    }
                     return r;
                 }
             },
-            Focus::Results => match key.code {
-                KeyCode::Tab => {
-                    self.focus = Focus::SearchBar;
+            Focus::Results => return AppEvent::None,
+        }
+    }
+}
+
+impl ActionReceiver for &mut SearchView {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::SwitchFocus => {
+                self.focus = Focus::SearchBar;
+                return AppEvent::None;
+            }
+            Action::Back => {
+                return AppEvent::ColumnPopLayer;
+            }
+            Action::NextTab | Action::PreviousTab => {
+                let mode = match self.mode {
+                    Mode::Users => Mode::Posts,
+                    Mode::Posts => Mode::Users,
+                };
+                self.switch(mode, agent);
+                return AppEvent::None;
+            }
+            _ if self.mode == Mode::Posts => {
+                return self.handle_post_action(action, agent).await;
+            }
+
+            Action::Next => {
+                let Some(feed) = self.feed.as_mut() else {
                     return AppEvent::None;
+                };
+                if feed.state.selected.is_none() {
+                    feed.state.selected = Some(0);
+                } else {
+                    if feed.state.selected.unwrap() < feed.view.len() - 1 {
+                        feed.state.next();
+                    }
                 }
-                KeyCode::Backspace => {
-                    return AppEvent::ColumnPopLayer;
-                }
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                let Some(feed) = self.feed.as_mut() else {
+                    return AppEvent::None;
+                };
+                feed.state.previous();
+                return AppEvent::None;
+            }
 
-                KeyCode::Char('j') => {
-                    let Some(feed) = self.feed.as_mut() else {
-                        return AppEvent::None;
-                    };
-                    if feed.state.selected == None {
-                        feed.state.selected = Some(0);
-                    } else {
-                        if feed.state.selected.unwrap() < feed.view.len() - 1 {
-                            feed.state.next();
-                        }
-                    }
+            Action::Select => {
+                let Some(feed) = &self.feed else {
                     return AppEvent::None;
-                }
-                KeyCode::Char('k') => {
-                    let Some(feed) = self.feed.as_mut() else {
-                        return AppEvent::None;
-                    };
-                    feed.state.previous();
+                };
+                let Some(i) = feed.state.selected else {
                     return AppEvent::None;
-                }
+                };
+                let did = feed.view[i].did.clone();
+                return ProfilePage::open(did, agent).await;
+            }
+
+            _ => return AppEvent::None,
+        }
+    }
+}
 
-                KeyCode::Enter => {
-                    let Some(feed) = &self.feed else {
+impl SearchView {
+    async fn handle_post_action(
+        &mut self,
+        action: Action,
+        agent: Client,
+    ) -> AppEvent {
+        match action {
+            Action::Next => {
+                {
+                    let mut results = self.posts.lock().unwrap();
+                    let Some(results) = &mut *results else {
                         return AppEvent::None;
                     };
-                    let Some(i) = feed.state.selected else {
+                    let feed = &mut results.feed;
+                    if feed.state.selected.map(|i| i + 1)
+                        < Some(feed.posts.len())
+                    {
+                        feed.state.next();
                         return AppEvent::None;
-                    };
-                    let did = feed.view[i].did.clone();
-                    let me = &agent.get_session().await.unwrap().did;
-                    let profile = ProfilePage::from_did(did, me, agent);
-                    return AppEvent::ColumnNewLayer(Column::ProfilePage(
-                        profile,
-                    ));
+                    }
                 }
+                self.fetch_next_page(agent);
+                return AppEvent::None;
+            }
+            Action::Previous => {
+                if let Some(results) = &mut *self.posts.lock().unwrap() {
+                    results.feed.state.previous();
+                }
+                return AppEvent::None;
+            }
 
-                _ => return AppEvent::None,
-            },
+            Action::OpenThread | Action::Select => {
+                let Some(uri) = self.selected_post() else {
+                    return AppEvent::None;
+                };
+                let view = match ThreadView::from_uri(uri, agent).await {
+                    Ok(view) => view,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return AppEvent::None;
+                    }
+                };
+                return AppEvent::ColumnNewLayer(Column::Thread(view));
+            }
+
+            action => {
+                let Some(post) = self
+                    .selected_post()
+                    .and_then(|uri| post_manager!().at(&uri))
+                else {
+                    return AppEvent::None;
+                };
+                return post.handle_action(action, agent).await;
+            }
+        }
+    }
+
+    async fn handle_post_click(
+        &mut self,
+        position: Position,
+        agent: Client,
+    ) -> AppEvent {
+        let facet = {
+            let mut results = self.posts.lock().unwrap();
+            let Some(results) = &mut *results else {
+                return AppEvent::None;
+            };
+            let feed = &mut results.feed;
+            let Some((i, at)) = feed.state.item_at(position) else {
+                return AppEvent::None;
+            };
+            feed.state.select_visible(i);
+            self.focus = Focus::Results;
+            let post = &feed.posts[i];
+            FeedPostWidget::new(post)
+                .facet_at(feed.state.area().width, at.x, at.y)
+                .and_then(|f| {
+                    let post = post_manager!().at(&post.post_uri)?;
+                    FacetModalItem::from_facet(&f, &post.text)
+                })
+        };
+        match facet {
+            Some(facet) => return facet.open(agent).await,
+            None => return AppEvent::None,
         }
     }
 }
 
+impl ClickReceiver for &mut SearchView {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        if self.modes_area.contains(position) {
+            let mut right = self.modes_area.x;
+            for mode in MODES {
+                right += mode.name().len() as u16 + 1;
+                if position.x < right {
+                    self.switch(mode, agent);
+                    break;
+                }
+            }
+            return AppEvent::None;
+        }
+        if self.mode == Mode::Posts {
+            return self.handle_post_click(position, agent).await;
+        }
+        let Some(feed) = self.feed.as_mut() else {
+            return AppEvent::None;
+        };
+        if let Some((i, _)) = feed.state.item_at(position) {
+            feed.state.select_visible(i);
+            self.focus = Focus::Results;
+        }
+        return AppEvent::None;
+    }
+}
+
 impl Widget for &mut SearchView {
     fn render(
         self,
@@ -305,7 +613,39 @@ impl Widget for &mut SearchView {
         self.searchbar.textarea.focused(matches!(self.focus, Focus::SearchBar));
         self.searchbar.textarea.render(searchbar_area, buf);
 
-        Separation::default().padding(1).render(separation_area, buf);
+        let modes = MODES
+            .iter()
+            .map(|mode| {
+                let name = format!("{} ", mode.name());
+                if *mode == self.mode {
+                    Span::styled(name, Color::Green)
+                } else {
+                    Span::styled(name, Color::DarkGray)
+                }
+            })
+            .collect::<Vec<_>>();
+        Separation::default()
+            .text(Line::from(modes))
+            .padding(1)
+            .render(separation_area, buf);
+        self.modes_area =
+            Rect { y: separation_area.y + 1, height: 1, ..separation_area };
+
+        if self.mode == Mode::Posts {
+            let posts = Arc::clone(&self.posts);
+            let mut results = posts.lock().unwrap();
+            let Some(results) = &mut *results else {
+                return;
+            };
+            if results.feed.posts.is_empty() {
+                Line::styled("No posts found", Color::DarkGray)
+                    .centered()
+                    .render(results_area, buf);
+                return;
+            }
+            results.feed.render(results_area, buf);
+            return;
+        }
 
         if let Some(feed) = self.feed.as_mut() {
             let items = feed.view.clone();
@@ -328,3 +668,40 @@ impl Widget for &mut SearchView {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::fake::FakeApi;
+    use atrium_api::types::string::Did;
+
+    #[tokio::test]
+    async fn search_posts_with_operators() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let me = Did::new("did:plc:tskyme".to_string()).unwrap();
+        let search = |q: &str| {
+            let params = search_query::parse(q, &me).unwrap();
+            let agent = agent.clone();
+            async move {
+                let (posts, _) = fetch_posts(agent, params).await.unwrap();
+                return posts
+                    .into_iter()
+                    .map(|p| p.post_uri)
+                    .collect::<Vec<_>>();
+            }
+        };
+
+        assert_eq!(
+            search("post from:alice.tsky.test").await,
+            vec!["at://did:plc:alice/app.bsky.feed.post/6"]
+        );
+        assert_eq!(
+            search("post since:2024-12-01 sort:latest").await,
+            vec![
+                "at://did:plc:tskyme/app.bsky.feed.post/4",
+                "at://did:plc:bob/app.bsky.feed.post/3",
+            ]
+        );
+        assert!(search("post until:2024-11-01").await.is_empty());
+    }
+}
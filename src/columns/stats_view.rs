@@ -0,0 +1,58 @@
+use ratatui::{
+    style::Color,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Widget},
+};
+
+use crate::{
+    app::{ActionReceiver, AppEvent},
+    client::Client,
+    keymap::Action,
+    post_manager,
+};
+
+// Memory kept by the post manager, read again on every frame
+pub struct StatsView;
+
+impl ActionReceiver for &mut StatsView {
+    async fn handle_action(self, action: Action, _: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
+            _ => return AppEvent::None,
+        }
+    }
+}
+
+impl Widget for &mut StatsView {
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) where
+        Self: Sized,
+    {
+        let stats = post_manager!().stats();
+        let block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Color::DarkGray)
+            .title(Span::styled("Stats", Color::Gray));
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let lines = [
+            format!("Posts: {}", stats.posts),
+            format!("Referenced by columns: {}", stats.referenced),
+            format!("Kept when unreferenced: {}", stats.capacity),
+            format!("Evicted: {}", stats.evicted),
+            format!("Text: {} KiB", stats.text_bytes / 1024),
+        ];
+        for (i, line) in lines.into_iter().enumerate() {
+            if i as u16 >= inner.height {
+                break;
+            }
+            let mut line_area = inner;
+            line_area.y += i as u16;
+            line_area.height = 1;
+            Line::from(line).render(line_area, buf);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use atrium_api::{
     app::bsky::feed::{
         defs::{
@@ -8,26 +10,28 @@ use atrium_api::{
     },
     types::Union,
 };
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    layout::Position,
     style::Color,
     text::Line,
     widgets::{BorderType, StatefulWidget, Widget},
 };
 
 use crate::{
-    app::EventReceiver,
+    app::{ActionReceiver, ClickReceiver},
+    client::Client,
     columns::{
-        facet_modal::{FacetModal, FacetModalItem, Link, Mention},
+        facet_modal::{FacetModal, FacetModalItem},
+        updating_feed::FeedSource,
         Column,
     },
     components::{
         embed::{Embed, Record},
         list::{List, ListState},
-        post::{post_widget::PostWidget, FacetType, Post},
+        post::{post_widget::PostWidget, Post},
         separation::Separation,
     },
+    keymap::Action,
     post_manager, AppEvent,
 };
 
@@ -48,7 +52,14 @@ fn parent_posts_rev(
         return posts;
     };
     let ThreadViewPostData { parent, post, .. } = parent.data;
-    let post = Post::from(&post);
+    // parents above an unreadable one are not shown
+    let post = match Post::try_from(&post) {
+        Ok(post) => post,
+        Err(e) => {
+            log::error!("{}", e);
+            return posts;
+        }
+    };
     let post_uri = post.uri.clone();
     post_manager!().insert(post);
 
@@ -59,13 +70,9 @@ fn parent_posts_rev(
 impl ThreadView {
     pub async fn from_uri(
         uri: String,
-        agent: BskyAgent,
+        agent: Client,
     ) -> Result<ThreadView, String> {
         let out = agent
-            .api
-            .app
-            .bsky
-            .feed
             .get_post_thread(
                 atrium_api::app::bsky::feed::get_post_thread::ParametersData {
                     depth: Some(1.try_into().unwrap()),
@@ -82,7 +89,7 @@ impl ThreadView {
 
         match thread {
             GetPostThreadOutput::AppBskyFeedDefsThreadViewPost(thread) => {
-                return Ok(ThreadView::new(thread.data));
+                return ThreadView::new(thread.data);
             }
             GetPostThreadOutput::AppBskyFeedDefsBlockedPost(_) => {
                 return Err("Blocked thread".to_string());
@@ -93,8 +100,8 @@ impl ThreadView {
         }
     }
 
-    fn new(thread: ThreadViewPostData) -> ThreadView {
-        let post = Post::from(&thread.post);
+    fn new(thread: ThreadViewPostData) -> Result<ThreadView, String> {
+        let post = Post::try_from(&thread.post)?;
         let post_uri = post.uri.clone();
         post_manager!().insert(post);
 
@@ -110,34 +117,47 @@ impl ThreadView {
                 }
                 _ => None,
             })
-            .map(|reply| {
-                let post = Post::from(&reply.post);
+            .filter_map(|reply| {
+                let post = Post::try_from(&reply.post)
+                    .inspect_err(|e| log::error!("{}", e))
+                    .ok()?;
                 let post_uri = post.uri.clone();
                 post_manager!().insert(post);
-                return post_uri;
+                return Some(post_uri);
             })
             .collect();
 
         let l = parent.len();
-        ThreadView { post_uri, parent, replies, state: ListState::new(Some(l)) }
+        Ok(ThreadView {
+            post_uri,
+            parent,
+            replies,
+            state: ListState::new(Some(l)),
+        })
     }
 
     pub fn selected(&self) -> Option<&String> {
-        if let Some(i) = self.state.selected {
-            if i < self.parent.len() {
-                return Some(&self.parent[i]);
-            }
-            if i == self.parent.len() {
-                return Some(&self.post_uri);
-            }
-            if i == self.parent.len() + 1 {
-                return None;
-            }
-            if i > self.parent.len() + 1 {
-                return Some(&self.replies[i - self.parent.len() - 2]);
-            }
+        return self.state.selected.and_then(|i| self.post_at(i));
+    }
+
+    // Uri of the post at a list index, None for the replies bar
+    fn post_at(&self, i: usize) -> Option<&String> {
+        if i < self.parent.len() {
+            return Some(&self.parent[i]);
+        }
+        if i == self.parent.len() {
+            return Some(&self.post_uri);
         }
-        return None;
+        if i == self.parent.len() + 1 {
+            return None;
+        }
+        return self.replies.get(i - self.parent.len() - 2);
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        uris.insert(self.post_uri.clone());
+        uris.extend(self.parent.iter().cloned());
+        uris.extend(self.replies.iter().cloned());
     }
 
     pub fn is_selecting_main_post(&self) -> bool {
@@ -149,25 +169,17 @@ impl ThreadView {
     }
 }
 
-impl EventReceiver for &mut ThreadView {
-    async fn handle_events(
-        self,
-        event: event::Event,
-        agent: BskyAgent,
-    ) -> AppEvent {
-        let Event::Key(key) = event else {
-            return AppEvent::None;
-        };
-
-        match key.code {
-            KeyCode::Backspace => return AppEvent::ColumnPopLayer,
+impl ActionReceiver for &mut ThreadView {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Back => return AppEvent::ColumnPopLayer,
 
-            KeyCode::Char('q') => {
+            Action::Quit => {
                 return AppEvent::Quit;
             }
 
-            KeyCode::Char('j') => {
-                if let None = self.state.selected {
+            Action::Next => {
+                if self.state.selected.is_none() {
                     self.state.selected = Some(0);
                 } else {
                     if self.state.selected.unwrap() == self.parent.len() {
@@ -182,7 +194,7 @@ impl EventReceiver for &mut ThreadView {
                 return AppEvent::None;
             }
 
-            KeyCode::Char('k') => {
+            Action::Previous => {
                 if matches!(self.state.selected, Some(i) if i == self.parent.len() + 2)
                 {
                     self.state.previous();
@@ -193,30 +205,12 @@ impl EventReceiver for &mut ThreadView {
                 return AppEvent::None;
             }
 
-            KeyCode::Char('f') => {
+            Action::Facets => {
                 let post = post_manager!().at(&self.post_uri).unwrap();
                 let facets = post
                     .facets
                     .iter()
-                    .filter_map(|facet| match &facet.r#type {
-                        FacetType::Link(url) => {
-                            let text =
-                                post.text[facet.range.clone()].to_string();
-                            Some(FacetModalItem::Link(Link {
-                                text,
-                                url: url.clone(),
-                            }))
-                        }
-                        FacetType::Mention(m) => {
-                            let text =
-                                post.text[facet.range.clone()].to_string();
-                            Some(FacetModalItem::Mention(Mention {
-                                text,
-                                did: m.clone(),
-                            }))
-                        }
-                        _ => None,
-                    })
+                    .filter_map(|f| FacetModalItem::from_facet(f, &post.text))
                     .collect::<Vec<_>>();
                 return AppEvent::ColumnNewLayer(Column::FacetModal(
                     FacetModal {
@@ -226,17 +220,30 @@ impl EventReceiver for &mut ThreadView {
                 ));
             }
 
-            KeyCode::Enter => {
+            Action::OpenThread => {
                 let Some(selected) = self.selected() else {
                     return AppEvent::None;
                 };
                 let uri = if self.is_selecting_main_post() {
                     let post = post_manager!().at(&self.post_uri).unwrap();
-                    let Some(Embed::Record(Record::Post(post))) = post.embed
-                    else {
-                        return AppEvent::None;
-                    };
-                    post.uri
+                    match &post.embed {
+                        Some(Embed::Record(Record::Post(post))) => {
+                            post.uri.clone()
+                        }
+                        Some(Embed::Record(Record::List(list))) => {
+                            return AppEvent::ColumnNewLayer(Column::feed(
+                                FeedSource::List(list.uri.clone()),
+                                agent,
+                            ));
+                        }
+                        Some(Embed::Record(Record::Generator(generator))) => {
+                            return AppEvent::ColumnNewLayer(Column::feed(
+                                FeedSource::Generator(generator.uri.clone()),
+                                agent,
+                            ));
+                        }
+                        _ => return AppEvent::None,
+                    }
                 } else {
                     selected.clone()
                 };
@@ -251,17 +258,39 @@ impl EventReceiver for &mut ThreadView {
                 return AppEvent::ColumnNewLayer(Column::Thread(view));
             }
 
-            _ => {
+            action => {
                 let Some(selected) = self.selected() else {
                     return AppEvent::None;
                 };
                 let post = post_manager!().at(selected).unwrap();
-                return post.handle_events(event, agent).await;
+                return post.handle_action(action, agent).await;
             }
         }
     }
 }
 
+impl ClickReceiver for &mut ThreadView {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        let Some((i, at)) = self.state.item_at(position) else {
+            return AppEvent::None;
+        };
+        let Some(uri) = self.post_at(i) else {
+            return AppEvent::None;
+        };
+        let post = post_manager!().at(uri).unwrap();
+        self.state.select_visible(i);
+
+        let facet = PostWidget::new(post.clone())
+            .has_border(true)
+            .facet_at(self.state.area().width, at.x, at.y)
+            .and_then(|f| FacetModalItem::from_facet(&f, &post.text));
+        match facet {
+            Some(facet) => return facet.open(agent).await,
+            None => return AppEvent::None,
+        }
+    }
+}
+
 impl Widget for &mut ThreadView {
     fn render(
         self,
@@ -271,20 +300,25 @@ impl Widget for &mut ThreadView {
         Self: Sized,
     {
         let parent_items =
-            self.parent.clone().into_iter().map(|p| ThreadViewItem::Post(p));
+            self.parent.clone().into_iter().map(ThreadViewItem::Post);
         let reply_items =
-            self.replies.clone().into_iter().map(|p| ThreadViewItem::Post(p));
+            self.replies.clone().into_iter().map(ThreadViewItem::Post);
         let items = parent_items
             .chain(std::iter::once(ThreadViewItem::Post(self.post_uri.clone())))
             .chain(std::iter::once(ThreadViewItem::Bar))
             .chain(reply_items)
             .collect::<Vec<_>>();
+        #[allow(
+            clippy::single_range_in_vec_init,
+            reason = "one connected range, not a vec of the indices in it"
+        )]
+        let connected = vec![0..self.parent.len()];
 
         List::new(items.len(), move |context| match &items[context.index] {
             ThreadViewItem::Post(uri) => {
-                let post = post_manager!().at(&uri).unwrap();
+                let post = post_manager!().at(uri).unwrap();
                 let item = PostWidget::new(post)
-                    .is_selected(context.is_selected)
+                    .selected(context.is_selected)
                     .has_border(true);
                 let height = item.line_count(area.width);
                 return (ThreadViewItemWidget::Post(item), height);
@@ -297,7 +331,7 @@ impl Widget for &mut ThreadView {
                 return (ThreadViewItemWidget::Bar(item), 3);
             }
         })
-        .connecting(vec![0..self.parent.len()])
+        .connecting(connected)
         .render(area, buf, &mut self.state);
     }
 }
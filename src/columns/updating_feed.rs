@@ -1,115 +1,243 @@
-use atrium_api::app::bsky::feed::get_timeline;
-use bsky_sdk::BskyAgent;
-use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    widgets::Widget,
+use atrium_api::app::bsky::feed::{
+    defs::FeedViewPost, get_feed, get_list_feed, get_timeline,
 };
-use std::sync::{
-    mpsc::{Receiver, Sender},
-    Arc, Mutex,
+use ratatui::{layout::Position, widgets::Widget};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    Notify,
 };
 
 use crate::{
-    app::{AppEvent, EventReceiver},
+    app::{ActionReceiver, AppEvent, ClickReceiver},
+    client::Client,
     columns::{
-        Column, ComposerView, Notifications, ProfilePage, SearchView,
-        ThreadView,
+        facet_modal::FacetModalItem, raw_view::RawView, Column, ColumnKind,
+        ComposerView, ProfilePage, SavedFeeds, ThreadView,
     },
     components::{
         composer,
-        feed::{FeedPost, PostFeed, Reason, Reply},
+        feed::{FeedPost, FeedPostWidget, PostFeed, Reason},
+        feed_filter::FeedFilter,
         list::ListState,
     },
-    post_manager,
+    config,
+    keymap::Action,
+    post_manager, redraw, workers,
 };
 
 pub enum RequestMsg {
     OldPost,
-    Close,
+}
+
+// Where the posts of a feed column come from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeedSource {
+    // timeline of followed accounts
+    Following,
+    // at:// uri of an app.bsky.feed.generator record
+    Generator(String),
+    // at:// uri of an app.bsky.graph.list record
+    List(String),
+}
+
+impl FeedSource {
+    // A page of posts, the newest one without a cursor
+    async fn fetch(
+        &self,
+        agent: &Client,
+        cursor: Option<String>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), String> {
+        match self {
+            FeedSource::Following => {
+                let output = agent
+                    .get_timeline(
+                        get_timeline::ParametersData {
+                            algorithm: None,
+                            cursor,
+                            limit: None,
+                        }
+                        .into(),
+                    )
+                    .await?;
+                return Ok((output.data.feed, output.data.cursor));
+            }
+            FeedSource::Generator(uri) => {
+                let output = agent
+                    .get_feed(
+                        get_feed::ParametersData {
+                            cursor,
+                            feed: uri.clone(),
+                            limit: None,
+                        }
+                        .into(),
+                    )
+                    .await?;
+                return Ok((output.data.feed, output.data.cursor));
+            }
+            FeedSource::List(uri) => {
+                let output = agent
+                    .get_list_feed(
+                        get_list_feed::ParametersData {
+                            cursor,
+                            limit: None,
+                            list: uri.clone(),
+                        }
+                        .into(),
+                    )
+                    .await?;
+                return Ok((output.data.feed, output.data.cursor));
+            }
+        }
+    }
 }
 
 pub struct UpdatingFeed {
+    pub source: FeedSource,
     pub feed: Arc<Mutex<PostFeed>>,
-    pub request_worker_tx: Sender<RequestMsg>,
+    // read by the workers for every page
+    pub filter: Arc<Mutex<FeedFilter>>,
+    // restored by `:filter reset`
+    configured: FeedFilter,
+    // notified once a fetched page is in the feed
+    pub fetched: Arc<Notify>,
+    // the worker stops once this is dropped
+    pub request_worker_tx: UnboundedSender<RequestMsg>,
 }
 
 impl UpdatingFeed {
-    pub fn new(tx: Sender<RequestMsg>) -> UpdatingFeed {
+    // Cloned, so no lock is held while handling the post
+    fn selected(&self) -> Option<FeedPost> {
+        let feed = self.feed.lock().unwrap();
+        return feed.posts.get(feed.state.selected?).cloned();
+    }
+
+    pub fn new(
+        source: FeedSource,
+        filter: &FeedFilter,
+        tx: UnboundedSender<RequestMsg>,
+    ) -> UpdatingFeed {
+        let filter = filter.for_source(&source);
         UpdatingFeed {
+            source,
             feed: Arc::new(Mutex::new(PostFeed::default())),
+            filter: Arc::new(Mutex::new(filter.clone())),
+            configured: filter,
+            fetched: Arc::new(Notify::new()),
             request_worker_tx: tx,
         }
     }
 
-    pub fn spawn_feed_autoupdate(&self, agent: BskyAgent) {
-        let feed = Arc::clone(&self.feed);
-        tokio::spawn(async move {
-            let me = &agent.get_session().await.unwrap().did;
+    // Starts over with the new rules, the next fetch fills the column again
+    pub fn set_filter(&self, filter: FeedFilter) {
+        log::info!("Feed filter: {}", filter.describe());
+        *self.filter.lock().unwrap() = filter;
+        *self.feed.lock().unwrap() = PostFeed::default();
+        redraw!();
+    }
+
+    pub fn reset_filter(&self) {
+        self.set_filter(self.configured.clone());
+    }
+
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        self.feed.lock().unwrap().post_uris(uris);
+    }
+
+    // Stops once the column is dropped
+    pub fn spawn_feed_autoupdate(&self, agent: Client) {
+        let feed = Arc::downgrade(&self.feed);
+        let filter = Arc::clone(&self.filter);
+        let fetched = Arc::clone(&self.fetched);
+        let source = self.source.clone();
+        workers!().spawn(async move {
+            let me = &match agent.did().await {
+                Ok(me) => me,
+                Err(e) => {
+                    log::error!("Cannot update feed: {}", e);
+                    return;
+                }
+            };
+            // drawn right away, then replaced by the first fetch
+            let mut cached = match source {
+                FeedSource::Following => agent
+                    .cached_timeline()
+                    .await
+                    .map(|output| (output.data.feed, output.data.cursor)),
+                _ => None,
+            };
             loop {
-                let new_posts = agent
-                    .api
-                    .app
-                    .bsky
-                    .feed
-                    .get_timeline(
-                        get_timeline::ParametersData {
-                            algorithm: None,
-                            cursor: None,
-                            limit: None,
-                        }
-                        .into(),
-                    )
-                    .await;
+                let from_cache = cached.is_some();
+                let new_posts = match cached.take() {
+                    Some(page) => Ok(page),
+                    None => source.fetch(&agent, None).await,
+                };
+                let refresh_rate = config!().feed.refresh_rate;
                 let Result::Ok(new_posts) = new_posts else {
                     log::error!("Cannot fetch new posts");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1))
-                        .await;
+                    tokio::time::sleep(tokio::time::Duration::from_secs(
+                        refresh_rate,
+                    ))
+                    .await;
                     continue;
                 };
 
-                let get_timeline::OutputData {
-                    feed: posts,
-                    cursor: new_cursor,
-                } = new_posts.data;
-                let new_posts = posts.iter().map(FeedPost::from).filter(|p| {
-                    p.reply_to
-                        .as_ref()
-                        .map(|r| match r {
-                            Reply::Reply(r) => r.following || r.did == *me,
-                            _ => false,
-                        })
-                        .unwrap_or(true)
-                });
+                let (posts, new_cursor) = new_posts;
+                let filter = filter.lock().unwrap().clone();
+                let new_posts = posts
+                    .iter()
+                    .filter_map(|p| {
+                        FeedPost::try_from(p)
+                            .inspect_err(|e| log::error!("{}", e))
+                            .ok()
+                    })
+                    .filter(|p| filter.keeps(p, me));
 
                 {
+                    let Some(feed) = feed.upgrade() else {
+                        return;
+                    };
                     let mut feed = feed.lock().unwrap();
                     if feed.insert_new_posts(new_posts) {
                         feed.cursor = new_cursor;
                     }
                 }
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                fetched.notify_one();
+                redraw!();
+                if from_cache {
+                    continue;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    refresh_rate,
+                ))
+                .await;
             }
         });
     }
 
     pub fn spawn_request_worker(
         &self,
-        agent: BskyAgent,
-        rx: Receiver<RequestMsg>,
+        agent: Client,
+        mut rx: UnboundedReceiver<RequestMsg>,
     ) {
         let feed = Arc::clone(&self.feed);
-        tokio::spawn(async move {
-            loop {
-                let Ok(msg) = rx.recv() else {
-                    log::error!("Error receiving request message in worker");
-                    return;
-                };
-
+        let filter = Arc::clone(&self.filter);
+        let source = self.source.clone();
+        workers!().spawn(async move {
+            while let Some(msg) = rx.recv().await {
                 match msg {
-                    RequestMsg::Close => return,
-
                     RequestMsg::OldPost => {
-                        get_old_posts(&agent, Arc::clone(&feed)).await;
+                        let filter = filter.lock().unwrap().clone();
+                        get_old_posts(
+                            &agent,
+                            &source,
+                            &filter,
+                            Arc::clone(&feed),
+                        )
+                        .await;
                     }
                 }
             }
@@ -117,27 +245,17 @@ impl UpdatingFeed {
     }
 }
 
-impl EventReceiver for &mut UpdatingFeed {
-    async fn handle_events(
-        self,
-        event: event::Event,
-        agent: BskyAgent,
-    ) -> AppEvent {
-        let Event::Key(key) = event.clone() else {
-            return AppEvent::None;
-        };
-
-        let feed = Arc::clone(&self.feed);
-        let mut feed = feed.lock().unwrap();
-
-        match key.code {
-            KeyCode::Char('q') => {
+impl ActionReceiver for &mut UpdatingFeed {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::Quit => {
                 return AppEvent::Quit;
             }
 
             // Cursor move down
-            KeyCode::Char('j') => {
-                if feed.posts.len() > 0
+            Action::Next => {
+                let mut feed = self.feed.lock().unwrap();
+                if !feed.posts.is_empty()
                     && feed.state.selected == Some(feed.posts.len() - 1)
                 {
                     self.request_worker_tx.send(RequestMsg::OldPost)
@@ -151,28 +269,21 @@ impl EventReceiver for &mut UpdatingFeed {
             }
 
             // Cursor move up
-            KeyCode::Char('k') => {
-                feed.state.previous();
+            Action::Previous => {
+                self.feed.lock().unwrap().state.previous();
                 return AppEvent::None;
             }
 
-            KeyCode::Char('g') => {
-                let Event::Key(event::KeyEvent {
-                    code: KeyCode::Char('g'),
-                    kind: event::KeyEventKind::Press,
-                    ..
-                }) = event::read().expect("Cannot read event")
-                else {
-                    return AppEvent::None;
-                };
-
+            Action::Top => {
+                let mut feed = self.feed.lock().unwrap();
                 feed.state = ListState::default();
                 feed.state.selected = Some(0);
                 return AppEvent::None;
             }
 
-            KeyCode::Char('G') => {
-                if feed.posts.len() > 0 {
+            Action::Bottom => {
+                let mut feed = self.feed.lock().unwrap();
+                if !feed.posts.is_empty() {
                     feed.state = ListState::default();
                     feed.state.selected = Some(feed.posts.len() - 1);
                     self.request_worker_tx.send(RequestMsg::OldPost)
@@ -183,13 +294,11 @@ impl EventReceiver for &mut UpdatingFeed {
                 return AppEvent::None;
             }
 
-            KeyCode::Enter => {
-                let Some(selected) = feed.state.selected else {
+            Action::OpenThread => {
+                let Some(selected) = self.selected() else {
                     return AppEvent::None;
                 };
-
-                let uri = feed.posts[selected].post_uri.clone();
-                drop(feed);
+                let uri = selected.post_uri;
 
                 let view = match ThreadView::from_uri(uri, agent).await {
                     Ok(view) => view,
@@ -201,57 +310,82 @@ impl EventReceiver for &mut UpdatingFeed {
                 return AppEvent::ColumnNewLayer(Column::Thread(view));
             }
 
-            KeyCode::Char('n') => {
-                return AppEvent::ColumnNewLayer(Column::Composer(
+            Action::NewPost => {
+                return AppEvent::ColumnNewLayer(Column::Composer(Box::new(
                     ComposerView::new(None, composer::embed::Embed::None),
-                ));
+                )));
             }
 
-            KeyCode::Char('b') => {
-                let notifications = Notifications::new(agent.clone()).await;
-                notifications.spawn_worker(agent);
-                return AppEvent::ColumnNewLayer(Column::Notifications(
-                    notifications,
+            Action::Notifications => {
+                return Column::open_layer(ColumnKind::Notifications, agent)
+                    .await;
+            }
+
+            Action::SavedFeeds => {
+                return AppEvent::ColumnNewLayer(Column::SavedFeeds(
+                    SavedFeeds::new(agent),
                 ));
             }
 
-            KeyCode::Char('B') => {
-                let me = &agent.get_session().await.unwrap().did;
-                let profile = ProfilePage::from_did(me.clone(), me, agent);
-                return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+            Action::MyProfile => {
+                return Column::open_layer(ColumnKind::Profile, agent).await;
             }
 
-            KeyCode::Char('A') => {
-                let Some(selected) = feed.state.selected else {
+            Action::ReposterProfile => {
+                let Some(selected) = self.selected() else {
                     return AppEvent::None;
                 };
-                let Some(Reason::Repost(by)) = &feed.posts[selected].reason
-                else {
+                let Some(Reason::Repost(by)) = selected.reason else {
                     return AppEvent::None;
                 };
-                let me = &agent.get_session().await.unwrap().did;
-                let profile = ProfilePage::from_did(by.did.clone(), me, agent);
-                return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+                return ProfilePage::open(by.did, agent).await;
             }
 
-            KeyCode::Char('/') => {
-                return AppEvent::ColumnNewLayer(Column::SearchView(
-                    SearchView::new(agent),
-                ));
+            Action::Search => {
+                return Column::open_layer(ColumnKind::Search, agent).await;
+            }
+
+            Action::ViewRaw => {
+                let Some(selected) = self.selected() else {
+                    return AppEvent::None;
+                };
+                return RawView::open(&selected.unsupported());
             }
 
-            _ => {
-                let Some(selected) = feed.state.selected else {
+            action => {
+                let Some(selected) = self.selected() else {
                     return AppEvent::None;
                 };
-                let uri = &feed.posts[selected].post_uri;
-                let post = post_manager!().at(uri).unwrap();
-                return post.handle_events(event, agent).await;
+                let post = post_manager!().at(&selected.post_uri).unwrap();
+                return post.handle_action(action, agent).await;
             }
         };
     }
 }
 
+impl ClickReceiver for &mut UpdatingFeed {
+    async fn handle_click(self, position: Position, agent: Client) -> AppEvent {
+        let facet = {
+            let mut feed = self.feed.lock().unwrap();
+            let Some((i, at)) = feed.state.item_at(position) else {
+                return AppEvent::None;
+            };
+            feed.state.select_visible(i);
+            let post = &feed.posts[i];
+            FeedPostWidget::new(post)
+                .facet_at(feed.state.area().width, at.x, at.y)
+                .and_then(|f| {
+                    let post = post_manager!().at(&post.post_uri)?;
+                    FacetModalItem::from_facet(&f, &post.text)
+                })
+        };
+        match facet {
+            Some(facet) => return facet.open(agent).await,
+            None => return AppEvent::None,
+        }
+    }
+}
+
 impl Widget for &mut UpdatingFeed {
     fn render(
         self,
@@ -266,44 +400,147 @@ impl Widget for &mut UpdatingFeed {
     }
 }
 
-async fn get_old_posts(agent: &BskyAgent, feed: Arc<Mutex<PostFeed>>) {
+async fn get_old_posts(
+    agent: &Client,
+    source: &FeedSource,
+    filter: &FeedFilter,
+    feed: Arc<Mutex<PostFeed>>,
+) {
+    let me = &match agent.did().await {
+        Ok(me) => me,
+        Err(e) => {
+            log::error!("Cannot fetch old posts: {}", e);
+            return;
+        }
+    };
     let cursor = {
         let feed = Arc::clone(&feed);
         let feed = feed.lock().unwrap();
         feed.cursor.clone()
     };
-    let new_posts = agent
-        .api
-        .app
-        .bsky
-        .feed
-        .get_timeline(
-            get_timeline::ParametersData {
-                algorithm: None,
-                cursor,
-                limit: None,
-            }
-            .into(),
-        )
-        .await;
-    let Result::Ok(new_posts) = new_posts else {
+    let new_posts = source.fetch(agent, cursor).await;
+    let Result::Ok((posts, new_cursor)) = new_posts else {
         log::error!("Cannot fetch old posts");
         return;
     };
 
-    let get_timeline::OutputData { feed: posts, cursor: new_cursor } =
-        new_posts.data;
-
     let mut feed = feed.lock().unwrap();
-    let posts = posts.iter().map(FeedPost::from).filter(|p| {
-        p.reply_to
-            .as_ref()
-            .map(|r| match r {
-                Reply::Reply(r) => r.following,
-                _ => false,
-            })
-            .unwrap_or(true)
-    });
+    let posts = posts
+        .iter()
+        .filter_map(|p| {
+            FeedPost::try_from(p).inspect_err(|e| log::error!("{}", e)).ok()
+        })
+        .filter(|p| filter.keeps(p, me));
     feed.append_old_posts(posts);
     feed.cursor = new_cursor;
+    redraw!();
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use atrium_api::{
+        app::bsky::graph::get_lists, types::string::AtIdentifier,
+    };
+
+    use super::*;
+    use crate::client::{fake::FakeApi, Api};
+
+    // Posts of the first page the column shows for the source
+    async fn first_page(
+        source: FeedSource,
+        client: Arc<FakeApi>,
+    ) -> Vec<FeedPost> {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let feed = UpdatingFeed::new(source, &FeedFilter::default(), tx);
+        feed.spawn_feed_autoupdate(client);
+        tokio::time::timeout(Duration::from_secs(5), feed.fetched.notified())
+            .await
+            .expect("No page was fetched");
+        let posts = feed.feed.lock().unwrap().posts.clone();
+        return posts;
+    }
+
+    fn uris(feed: &[FeedViewPost]) -> Vec<String> {
+        return feed.iter().map(|p| p.post.uri.clone()).collect();
+    }
+
+    fn post_uris(posts: &[FeedPost]) -> Vec<String> {
+        return posts.iter().map(|p| p.post_uri.clone()).collect();
+    }
+
+    #[tokio::test]
+    async fn updating_feed_offline() {
+        let posts =
+            first_page(FeedSource::Following, Arc::new(FakeApi::new())).await;
+        assert_eq!(posts.len(), 4);
+        assert!(matches!(posts[3].reason, Some(Reason::Repost(_))));
+        assert!(post_manager!().at(&posts[0].post_uri).is_some());
+    }
+
+    #[tokio::test]
+    async fn generator_feed_column() {
+        let uri = "at://did:plc:gen/app.bsky.feed.generator/team".to_string();
+        let served = FakeApi::new()
+            .get_feed(
+                get_feed::ParametersData {
+                    cursor: None,
+                    feed: uri.clone(),
+                    limit: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert!(!served.feed.is_empty());
+
+        let client = Arc::new(FakeApi::new());
+        let posts =
+            first_page(FeedSource::Generator(uri.clone()), client.clone())
+                .await;
+        assert_eq!(post_uris(&posts), uris(&served.feed));
+        assert!(posts.iter().all(|p| p.reason.is_none()));
+        assert_eq!(client.requested(), vec![uri]);
+    }
+
+    #[tokio::test]
+    async fn list_feed_column() {
+        let client = Arc::new(FakeApi::new());
+        let me = client.did().await.unwrap();
+        let lists = client
+            .get_lists(
+                get_lists::ParametersData {
+                    actor: AtIdentifier::Did(me.clone()),
+                    cursor: None,
+                    limit: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        let uri = lists.lists[0].uri.clone();
+        assert!(uri.contains("/app.bsky.graph.list/"));
+        let served = FakeApi::new()
+            .get_list_feed(
+                get_list_feed::ParametersData {
+                    cursor: None,
+                    limit: None,
+                    list: uri.clone(),
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert!(!served.feed.is_empty());
+
+        let posts =
+            first_page(FeedSource::List(uri.clone()), client.clone()).await;
+        assert_eq!(post_uris(&posts), uris(&served.feed));
+        for post in &posts {
+            let post = post_manager!().at(&post.post_uri).unwrap();
+            assert_ne!(post.author.did, me);
+        }
+        assert_eq!(client.requested(), vec![uri]);
+    }
 }
@@ -0,0 +1,303 @@
+use atrium_api::{
+    com::atproto::identity::resolve_handle,
+    types::string::{Did, Handle},
+};
+
+use crate::{client::Client, components::feed_filter::Replies};
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    // handle or did
+    Profile(String),
+    // at:// uri of a post, a feed generator, a list or an actor
+    Open(String),
+    Search(String),
+    // post search query, operators like from: and since: included
+    SearchPosts(String),
+    Feed(Option<String>),
+    // pinned and saved feeds of the user
+    Feeds,
+    // at:// uri of a list
+    List(String),
+    Like,
+    // memory used by tsky, for debugging
+    Stats,
+    // writes not sent yet
+    Outbox,
+    Quit,
+    Set(Setting),
+    // changes the filter of the focused feed column
+    Filter(FilterRule),
+    // muted word or phrase, None lists the muted words
+    Mute(Option<String>),
+    Unmute(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FilterRule {
+    // logs the rules in effect
+    Show,
+    // toggles hiding all reposts, or the reposts by a handle or did
+    Reposts(Option<String>),
+    Replies(Replies),
+    Quotes,
+    // empty to show every language
+    Langs(Vec<String>),
+    // toggles hiding a handle or did
+    Hide(String),
+    // back to the configured rules
+    Reset,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Setting {
+    // seconds
+    FeedRefreshRate(u64),
+    NotificationsRefreshRate(u64),
+}
+
+// Parses the text typed after `:`
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, arg) = match line.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (line, ""),
+    };
+
+    let require_arg = |usage: &str| {
+        if arg.is_empty() {
+            Err(format!("Usage: :{} {}", name, usage))
+        } else {
+            Ok(arg.to_string())
+        }
+    };
+    let no_arg = |command: Command| {
+        if arg.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!(":{} takes no argument", name))
+        }
+    };
+
+    match name {
+        "profile" => {
+            let actor = require_arg("@handle")?;
+            Ok(Command::Profile(
+                actor.strip_prefix('@').unwrap_or(&actor).to_string(),
+            ))
+        }
+        "open" => {
+            let uri = require_arg("at://...")?;
+            if !uri.starts_with("at://") {
+                return Err(format!("Not an at:// uri: {}", uri));
+            }
+            Ok(Command::Open(uri))
+        }
+        "search" => Ok(Command::Search(require_arg("term")?)),
+        "posts" => Ok(Command::SearchPosts(require_arg("query")?)),
+        "feeds" => no_arg(Command::Feeds),
+        "feed" if arg.is_empty() => Ok(Command::Feed(None)),
+        "feed" => Ok(Command::Feed(Some(arg.to_string()))),
+        "list" => {
+            let uri = require_arg("at://...")?;
+            if !uri.starts_with("at://") {
+                return Err(format!("Not an at:// uri: {}", uri));
+            }
+            Ok(Command::List(uri))
+        }
+        "like" => no_arg(Command::Like),
+        "stats" => no_arg(Command::Stats),
+        "outbox" => no_arg(Command::Outbox),
+        "q" | "quit" => no_arg(Command::Quit),
+        "set" => {
+            let (key, value) = require_arg("name=value")?
+                .split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .ok_or("Usage: :set name=value".to_string())?;
+            let seconds = || match value.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("{} must be a positive number", key)),
+            };
+            match key.as_str() {
+                "refresh" => {
+                    Ok(Command::Set(Setting::FeedRefreshRate(seconds()?)))
+                }
+                "notifications_refresh" => Ok(Command::Set(
+                    Setting::NotificationsRefreshRate(seconds()?),
+                )),
+                _ => Err(format!("Unknown setting {}", key)),
+            }
+        }
+        "filter" => Ok(Command::Filter(parse_filter(arg)?)),
+        "mute" if arg.is_empty() => Ok(Command::Mute(None)),
+        "mute" => Ok(Command::Mute(Some(arg.to_string()))),
+        "unmute" => Ok(Command::Unmute(require_arg("word")?)),
+        "" => Err("Empty command".to_string()),
+        _ => Err(format!("Unknown command {}", name)),
+    }
+}
+
+fn parse_filter(arg: &str) -> Result<FilterRule, String> {
+    let (rule, arg) = match arg.split_once(char::is_whitespace) {
+        Some((rule, arg)) => (rule, arg.trim()),
+        None => (arg, ""),
+    };
+    let actor = |arg: &str| arg.strip_prefix('@').unwrap_or(arg).to_string();
+    match (rule, arg) {
+        ("", _) => Ok(FilterRule::Show),
+        ("reposts", "") => Ok(FilterRule::Reposts(None)),
+        ("reposts", arg) => Ok(FilterRule::Reposts(Some(actor(arg)))),
+        ("replies", "all") => Ok(FilterRule::Replies(Replies::All)),
+        ("replies", "following") => Ok(FilterRule::Replies(Replies::Following)),
+        ("replies", "none") => Ok(FilterRule::Replies(Replies::None)),
+        ("replies", _) => {
+            Err("Usage: :filter replies all|following|none".to_string())
+        }
+        ("quotes", "") => Ok(FilterRule::Quotes),
+        ("langs", arg) => Ok(FilterRule::Langs(
+            arg.split([',', ' '])
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect(),
+        )),
+        ("hide", "") => Err("Usage: :filter hide @handle".to_string()),
+        ("hide", arg) => Ok(FilterRule::Hide(actor(arg))),
+        ("reset", "") => Ok(FilterRule::Reset),
+        _ => Err(format!("Unknown filter {}", rule)),
+    }
+}
+
+// Did of a handle or did
+pub async fn resolve_actor(actor: &str, agent: &Client) -> Result<Did, String> {
+    if actor.starts_with("did:") {
+        return Did::new(actor.to_string()).map_err(|e| e.to_string());
+    }
+    let handle = Handle::new(actor.to_string())
+        .map_err(|e| format!("Invalid handle {}: {}", actor, e))?;
+    let output = agent
+        .resolve_handle(resolve_handle::ParametersData { handle }.into())
+        .await
+        .map_err(|e| format!("Cannot resolve handle {}: {}", actor, e))?;
+    Ok(output.data.did)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            parse("profile @alice.bsky.social"),
+            Ok(Command::Profile("alice.bsky.social".to_string()))
+        );
+        assert_eq!(
+            parse("  profile did:plc:abc "),
+            Ok(Command::Profile("did:plc:abc".to_string()))
+        );
+        assert_eq!(
+            parse("open at://did:plc:abc/app.bsky.feed.post/123"),
+            Ok(Command::Open(
+                "at://did:plc:abc/app.bsky.feed.post/123".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("search rust  tui"),
+            Ok(Command::Search("rust  tui".to_string()))
+        );
+        assert_eq!(
+            parse("posts cats from:alice.bsky.social lang:ja"),
+            Ok(Command::SearchPosts(
+                "cats from:alice.bsky.social lang:ja".to_string()
+            ))
+        );
+        assert!(parse("posts").is_err());
+        assert_eq!(parse("feed"), Ok(Command::Feed(None)));
+        assert_eq!(
+            parse("feed at://x"),
+            Ok(Command::Feed(Some("at://x".to_string())))
+        );
+        assert_eq!(
+            parse("list at://did:plc:abc/app.bsky.graph.list/123"),
+            Ok(Command::List(
+                "at://did:plc:abc/app.bsky.graph.list/123".to_string()
+            ))
+        );
+        assert_eq!(parse("feeds"), Ok(Command::Feeds));
+        assert_eq!(parse("like"), Ok(Command::Like));
+        assert_eq!(parse("stats"), Ok(Command::Stats));
+        assert_eq!(parse("outbox"), Ok(Command::Outbox));
+        assert_eq!(parse("q"), Ok(Command::Quit));
+        assert_eq!(parse("mute"), Ok(Command::Mute(None)));
+        assert_eq!(
+            parse("mute  two words "),
+            Ok(Command::Mute(Some("two words".to_string())))
+        );
+        assert_eq!(
+            parse("unmute #tag"),
+            Ok(Command::Unmute("#tag".to_string()))
+        );
+        assert_eq!(parse("filter"), Ok(Command::Filter(FilterRule::Show)));
+        assert_eq!(
+            parse("filter reposts"),
+            Ok(Command::Filter(FilterRule::Reposts(None)))
+        );
+        assert_eq!(
+            parse("filter reposts @bob.test"),
+            Ok(Command::Filter(FilterRule::Reposts(Some(
+                "bob.test".to_string()
+            ))))
+        );
+        assert_eq!(
+            parse("filter replies following"),
+            Ok(Command::Filter(FilterRule::Replies(Replies::Following)))
+        );
+        assert_eq!(
+            parse("filter langs en, ja"),
+            Ok(Command::Filter(FilterRule::Langs(vec![
+                "en".to_string(),
+                "ja".to_string()
+            ])))
+        );
+        assert_eq!(
+            parse("filter langs"),
+            Ok(Command::Filter(FilterRule::Langs(vec![])))
+        );
+        assert_eq!(
+            parse("filter hide did:plc:abc"),
+            Ok(Command::Filter(FilterRule::Hide("did:plc:abc".to_string())))
+        );
+        assert_eq!(
+            parse("filter reset"),
+            Ok(Command::Filter(FilterRule::Reset))
+        );
+        assert_eq!(
+            parse("set refresh=5"),
+            Ok(Command::Set(Setting::FeedRefreshRate(5)))
+        );
+        assert_eq!(
+            parse("set notifications_refresh = 30"),
+            Ok(Command::Set(Setting::NotificationsRefreshRate(30)))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("profile").is_err());
+        assert!(parse("open https://bsky.app").is_err());
+        assert!(parse("list").is_err());
+        assert!(parse("list https://bsky.app").is_err());
+        assert!(parse("like now").is_err());
+        assert!(parse("set refresh").is_err());
+        assert!(parse("set refresh=0").is_err());
+        assert!(parse("set refresh=fast").is_err());
+        assert!(parse("set speed=1").is_err());
+        assert!(parse("unmute").is_err());
+        assert!(parse("filter replies some").is_err());
+        assert!(parse("filter hide").is_err());
+        assert!(parse("filter quotes now").is_err());
+        assert!(parse("filter boosts").is_err());
+        assert!(parse("frobnicate").is_err());
+    }
+}
@@ -1,10 +1,12 @@
-use std::process::{Command, Stdio};
+use std::{
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
 
 use atrium_api::{
     app::bsky::actor::defs::ProfileViewBasicData, types::string::Did,
 };
 use ratatui::{
-    crossterm::event::{Event, KeyCode},
     layout::{Constraint, Layout},
     style::{Color, Style},
     text::{Line, Span},
@@ -12,8 +14,14 @@ use ratatui::{
 };
 
 use crate::{
-    app::{AppEvent, EventReceiver},
+    app::{ActionReceiver, AppEvent},
+    client::Client,
     components::paragraph::Paragraph,
+    config,
+    keymap::{key_hint, Action, Context},
+    outbox,
+    outbox::Op,
+    redraw, workers,
 };
 
 #[derive(Clone)]
@@ -22,6 +30,8 @@ pub struct ActorBasic {
     pub name: String,
     pub handle: String,
     pub labels: Vec<String>,
+    // by the user
+    pub following: bool,
 }
 
 impl ActorBasic {
@@ -37,6 +47,10 @@ impl ActorBasic {
                 .map(|label| label.val.clone())
                 .collect(),
             did: author.did.clone(),
+            following: author
+                .viewer
+                .as_ref()
+                .is_some_and(|v| v.following.is_some()),
         }
     }
 }
@@ -146,10 +160,8 @@ impl<'a> ActorWidget<'a> {
     pub fn line_count(&self, width: u16) -> u16 {
         let b = self.block.is_some() as u16 * 2;
         1 + b
-            + Paragraph::new(
-                self.actor.description.clone().unwrap_or(String::new()),
-            )
-            .line_count(width - b as u16)
+            + Paragraph::new(self.actor.description.clone().unwrap_or_default())
+                .line_count(width - b)
     }
 }
 
@@ -178,7 +190,7 @@ impl<'a> Widget for ActorWidget<'a> {
             Layout::vertical([Constraint::Length(1), Constraint::Fill(1)])
                 .areas(area);
         ActorBasicWidget::new(&self.actor.basic).render(basic_area, buf);
-        Paragraph::new(self.actor.description.clone().unwrap_or(String::new()))
+        Paragraph::new(self.actor.description.clone().unwrap_or_default())
             .render(description_area, buf);
     }
 }
@@ -195,6 +207,9 @@ pub struct ActorDetailed {
     blocking: bool,
     blocked_by: bool,
     following: Option<String>,
+    // toggled by the user but not settled yet
+    follow_wanted: Option<bool>,
+    follow_in_flight: bool,
     followed_by: bool,
     muted: bool,
 }
@@ -252,7 +267,9 @@ impl ActorDetailed {
                 .as_ref()
                 .map(|v| v.blocked_by.unwrap_or(false))
                 .unwrap_or(false),
-            following: viewer.as_ref().map(|v| v.following.clone()).flatten(),
+            following: viewer.as_ref().and_then(|v| v.following.clone()),
+            follow_wanted: None,
+            follow_in_flight: false,
             followed_by: viewer
                 .as_ref()
                 .map(|v| v.followed_by.is_some())
@@ -265,57 +282,115 @@ impl ActorDetailed {
     }
 }
 
-impl EventReceiver for &mut ActorDetailed {
-    async fn handle_events(
-        self,
-        event: ratatui::crossterm::event::Event,
-        agent: bsky_sdk::BskyAgent,
-    ) -> crate::app::AppEvent {
-        let Event::Key(key) = event else {
-            return AppEvent::None;
+impl ActorDetailed {
+    fn is_following(&self) -> bool {
+        return self.follow_wanted.unwrap_or(self.following.is_some());
+    }
+
+    // count including the pending follow
+    fn shown_follower_count(&self) -> u64 {
+        match (self.is_following(), self.following.is_some()) {
+            (true, false) => return self.follower_count + 1,
+            (false, true) => return self.follower_count.saturating_sub(1),
+            _ => return self.follower_count,
+        }
+    }
+}
+
+// Flips the follow shown right away. A worker creates or deletes the record,
+// toggles made meanwhile are settled together and a failure rolls back.
+pub fn toggle_follow(actor: &Arc<Mutex<Option<ActorDetailed>>>) {
+    {
+        let mut actor = actor.lock().unwrap();
+        let Some(actor) = &mut *actor else {
+            return;
         };
-        match key.code {
-            KeyCode::Enter => {
-                if self.is_me {
-                    return AppEvent::None;
+        if actor.is_me {
+            return;
+        }
+        actor.follow_wanted = Some(!actor.is_following());
+        if actor.follow_in_flight {
+            return;
+        }
+        actor.follow_in_flight = true;
+    }
+
+    let actor = Arc::clone(actor);
+    workers!().spawn(async move {
+        loop {
+            let (record, did) = {
+                let mut actor = actor.lock().unwrap();
+                let Some(actor) = &mut *actor else {
+                    return;
+                };
+                match actor.follow_wanted {
+                    Some(wanted) if wanted != actor.following.is_some() => {
+                        (actor.following.clone(), actor.actor.basic.did.clone())
+                    }
+                    _ => {
+                        actor.follow_wanted = None;
+                        actor.follow_in_flight = false;
+                        return;
+                    }
                 }
-                match &self.following {
-                    Some(uri) => {
-                        let out = agent.delete_record(uri).await;
-                        if let Err(e) = out {
-                            log::error!("Could not unfollow user: {}", e);
-                            return AppEvent::None;
-                        }
-                        self.following = None;
+            };
+
+            let op = match &record {
+                Some(uri) => Op::Delete(uri.clone()),
+                None => Op::Create(
+                    atrium_api::app::bsky::graph::follow::RecordData {
+                        created_at: atrium_api::types::string::Datetime::now(),
+                        subject: did,
                     }
-                    None => {
-                        let out = agent
-                            .create_record(
-                                atrium_api::app::bsky::graph::follow::RecordData {
-                                    created_at:
-                                        atrium_api::types::string::Datetime::now(),
-                                    subject: self.actor.basic.did.clone(),
-                                },
-                            )
-                            .await;
-                        match out {
-                            Ok(out) => self.following = Some(out.uri.clone()),
-                            Err(e) => {
-                                log::error!("Could not follow user: {}", e)
-                            }
+                    .into(),
+                ),
+            };
+            let result = outbox!()
+                .submit(op)
+                .await
+                .unwrap_or(Err("Outbox closed".to_string()));
+
+            let mut actor = actor.lock().unwrap();
+            let Some(actor) = &mut *actor else {
+                return;
+            };
+            match result {
+                Ok(Some(uri)) => {
+                    actor.following = Some(uri);
+                    actor.follower_count += 1;
+                }
+                Ok(None) => {
+                    actor.following = None;
+                    actor.follower_count =
+                        actor.follower_count.saturating_sub(1);
+                }
+                Err(e) => {
+                    match record {
+                        Some(_) => {
+                            log::error!("Could not unfollow user: {}", e)
                         }
+                        None => log::error!("Could not follow user: {}", e),
                     }
+                    actor.follow_wanted = None;
+                    actor.follow_in_flight = false;
+                    redraw!();
+                    return;
                 }
             }
-            KeyCode::Char('m') => {
+            redraw!();
+        }
+    });
+}
+
+impl ActionReceiver for &mut ActorDetailed {
+    async fn handle_action(self, action: Action, _: Client) -> AppEvent {
+        match action {
+            Action::OpenMedia => {
                 if self.avatar.is_none() && self.banner.is_none() {
                     log::info!("Avatar and banner not set");
                     return AppEvent::None;
                 }
-                if let Err(e) = Command::new("feh")
-                    .args(["--output-dir", "/tmp?"])
-                    .args(["--zoom", "50%"])
-                    .arg("--")
+                if let Err(e) = config::command(&config!().media.image_viewer)
                     .args(
                         [&self.avatar, &self.banner]
                             .into_iter()
@@ -328,12 +403,12 @@ impl EventReceiver for &mut ActorDetailed {
                     log::error!("{:?}", e);
                 };
             }
-            KeyCode::Char('p') => {
+            Action::OpenInBrowser => {
                 let url = format!(
                     "https://bsky.app/profile/{}",
                     self.actor.basic.handle
                 );
-                if let Err(e) = Command::new("xdg-open")
+                if let Err(e) = config::command(&config!().media.browser)
                     .arg(url)
                     .stdout(Stdio::null())
                     .stderr(Stdio::null())
@@ -373,11 +448,7 @@ impl<'a> ActorDetailedWidget<'a> {
     pub fn line_count(&self, width: u16) -> u16 {
         4 + !self.detailed.actor.basic.labels.is_empty() as u16
             + Paragraph::new(
-                self.detailed
-                    .actor
-                    .description
-                    .clone()
-                    .unwrap_or(String::new()),
+                self.detailed.actor.description.clone().unwrap_or_default(),
             )
             .line_count(width)
             + 2 * self.block.is_some() as u16
@@ -401,7 +472,7 @@ impl<'a> Widget for ActorDetailedWidget<'a> {
         };
 
         let description = Paragraph::new(
-            self.detailed.actor.description.clone().unwrap_or(String::new()),
+            self.detailed.actor.description.clone().unwrap_or_default(),
         );
         let [name_ff_area, handle_area, stat_area, label_area, _, description_area] =
             Layout::vertical([
@@ -412,18 +483,26 @@ impl<'a> Widget for ActorDetailedWidget<'a> {
                     !self.detailed.actor.basic.labels.is_empty() as u16,
                 ),
                 Constraint::Length(1),
-                Constraint::Length(description.line_count(area.width) as u16),
+                Constraint::Length(description.line_count(area.width)),
             ])
             .areas(area);
 
         let name = Span::styled(&self.detailed.actor.basic.name, Color::Cyan);
-        let key_hint = Span::styled(
-            if self.focused { " 🖼️(m) 🦋(p)" } else { " " },
+        let hints = [("🖼️", Action::OpenMedia), ("🦋", Action::OpenInBrowser)]
+            .into_iter()
+            .filter(|_| self.focused)
+            .filter_map(|(icon, action)| {
+                let keys = key_hint(Context::Actor, action)?;
+                return Some(format!(" {}{}", icon, keys));
+            })
+            .collect::<String>();
+        let hints = Span::styled(
+            if hints.is_empty() { " ".to_string() } else { hints },
             Color::DarkGray,
         );
         let ff = match (
             self.detailed.followed_by,
-            self.detailed.following.is_some(),
+            self.detailed.is_following(),
             self.detailed.is_me,
         ) {
             (_, _, true) => "",
@@ -432,10 +511,18 @@ impl<'a> Widget for ActorDetailedWidget<'a> {
             (false, true, _) => "[Following]",
             (false, false, _) => "[+ Follow]",
         };
-        let ff = Span::from(ff);
+        let ff = if self.detailed.follow_wanted.is_some() {
+            Span::styled(ff, Color::Yellow)
+        } else {
+            Span::from(ff)
+        };
         let ff = ff
             + Span::styled(
-                if self.focused && !self.detailed.is_me { "(↵)" } else { "" },
+                match self.focused && !self.detailed.is_me {
+                    true => key_hint(Context::Actor, Action::Follow)
+                        .unwrap_or_default(),
+                    false => String::new(),
+                },
                 Color::DarkGray,
             );
         let [name_area, _, ff_area] = Layout::horizontal([
@@ -444,7 +531,7 @@ impl<'a> Widget for ActorDetailedWidget<'a> {
             Constraint::Length(ff.to_string().len() as u16),
         ])
         .areas(name_ff_area);
-        (name + key_hint).render(name_area, buf);
+        (name + hints).render(name_area, buf);
         ff.render(ff_area, buf);
 
         (Span::styled(
@@ -455,21 +542,18 @@ impl<'a> Widget for ActorDetailedWidget<'a> {
             ),
             Color::DarkGray,
         ) + Span::styled(
-            format!(
-                "{}",
-                match (self.detailed.blocking, self.detailed.blocked_by) {
-                    (true, true) => "[Mutual Blocking]",
-                    (true, false) => "[Blocking]",
-                    (false, true) => "[Blocked by]",
-                    (false, false) => "",
-                }
-            ),
+            match (self.detailed.blocking, self.detailed.blocked_by) {
+                (true, true) => "[Mutual Blocking]",
+                (true, false) => "[Blocking]",
+                (false, true) => "[Blocked by]",
+                (false, false) => "",
+            },
             Color::LightRed,
         ))
         .render(handle_area, buf);
 
         [
-            Span::from(self.detailed.follower_count.to_string()),
+            Span::from(self.detailed.shown_follower_count().to_string()),
             Span::styled(" followers ", Color::DarkGray),
             Span::from(self.detailed.following_count.to_string()),
             Span::styled(" following ", Color::DarkGray),
@@ -1,23 +1,21 @@
-use std::{io::Read, process::Stdio};
-
-use bsky_sdk::BskyAgent;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    crossterm::event::{Event, KeyCode},
     layout::{Constraint, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Widget},
 };
+use std::{io::Read, process::Stdio};
 use tokio::{fs::File, io::AsyncReadExt, process::Command};
 use wl_clipboard_rs::paste::{self, ClipboardType, MimeType, Seat};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
+    app::{ActionReceiver, AppEvent, EventReceiver},
+    client::Client,
     columns::{thread_view::ThreadView, Column},
-    components::{
-        composer::textarea::{Input, Key},
-        post::{post_widget::PostWidget, PostRef},
-    },
+    components::post::{post_widget::PostWidget, PostRef},
+    keymap,
+    keymap::{Context, KeyPress, Resolution},
     post_manager,
 };
 
@@ -102,11 +100,13 @@ impl<'a> Widget for MediaWidget<'a> {
 pub struct EmbedState {
     pub embed: Embed,
     pub state: usize,
+    // d was pressed, a second d removes the selected embed
+    pending_delete: bool,
 }
 
 impl EmbedState {
     pub fn new(embed: Embed) -> Self {
-        EmbedState { embed, state: 0 }
+        EmbedState { embed, state: 0, pending_delete: false }
     }
 
     pub fn paste_image(&mut self) {
@@ -176,11 +176,68 @@ impl EmbedState {
     }
 }
 
+impl EmbedState {
+    fn remove_selected(&mut self) {
+        let mut embed = Embed::None;
+        std::mem::swap(&mut embed, &mut self.embed);
+        self.embed = match embed {
+            Embed::None => Embed::None,
+            Embed::Media(Media::Images(mut images)) => {
+                self.state = self.state.saturating_sub(1);
+                images.remove(self.state);
+                if images.is_empty() {
+                    Embed::None
+                } else {
+                    Embed::Media(Media::Images(images))
+                }
+            }
+            Embed::Media(Media::External(_)) => Embed::None,
+            Embed::Record(post) => {
+                if self.state == 0 {
+                    Embed::Record(post)
+                } else {
+                    self.state = 0;
+                    Embed::None
+                }
+            }
+            Embed::RecordWithMedia(post, Media::Images(mut images)) => {
+                if self.state == images.len() {
+                    self.state = self.state.saturating_sub(1);
+                    Embed::Media(Media::Images(images))
+                } else {
+                    images.remove(self.state);
+                    self.state = self.state.saturating_sub(1);
+                    if images.is_empty() {
+                        Embed::Record(post)
+                    } else {
+                        Embed::RecordWithMedia(post, Media::Images(images))
+                    }
+                }
+            }
+            Embed::RecordWithMedia(post, Media::External(uri)) => {
+                if self.state == 1 {
+                    self.state = 0;
+                    Embed::Media(Media::External(uri))
+                } else {
+                    self.state = 0;
+                    Embed::Record(post)
+                }
+            }
+        };
+    }
+}
+
 impl EventReceiver for &mut EmbedState {
-    async fn handle_events(self, event: Event, agent: BskyAgent) -> AppEvent {
+    async fn handle_events(self, event: Event, agent: Client) -> AppEvent {
         let Event::Key(key) = event else {
             return AppEvent::None;
         };
+        if std::mem::take(&mut self.pending_delete) {
+            if key.code == KeyCode::Char('d') {
+                self.remove_selected();
+            }
+            return AppEvent::None;
+        }
         match key.code {
             KeyCode::Backspace => {
                 return AppEvent::ColumnPopLayer;
@@ -261,59 +318,7 @@ impl EventReceiver for &mut EmbedState {
             }
 
             KeyCode::Char('d') => {
-                let e = event::read().expect("Cannot read event").into();
-                let Input { key: Key::Char('d'), .. } = e else {
-                    return AppEvent::None;
-                };
-                let mut embed = Embed::None;
-                std::mem::swap(&mut embed, &mut self.embed);
-                self.embed = match embed {
-                    Embed::None => Embed::None,
-                    Embed::Media(Media::Images(mut images)) => {
-                        self.state = self.state.saturating_sub(1);
-                        images.remove(self.state);
-                        if images.is_empty() {
-                            Embed::None
-                        } else {
-                            Embed::Media(Media::Images(images))
-                        }
-                    }
-                    Embed::Media(Media::External(_)) => Embed::None,
-                    Embed::Record(post) => {
-                        if self.state == 0 {
-                            Embed::Record(post)
-                        } else {
-                            self.state = 0;
-                            Embed::None
-                        }
-                    }
-                    Embed::RecordWithMedia(post, Media::Images(mut images)) => {
-                        if self.state == images.len() {
-                            self.state = self.state.saturating_sub(1);
-                            Embed::Media(Media::Images(images))
-                        } else {
-                            images.remove(self.state);
-                            self.state = self.state.saturating_sub(1);
-                            if images.is_empty() {
-                                Embed::Record(post)
-                            } else {
-                                Embed::RecordWithMedia(
-                                    post,
-                                    Media::Images(images),
-                                )
-                            }
-                        }
-                    }
-                    Embed::RecordWithMedia(post, Media::External(uri)) => {
-                        if self.state == 1 {
-                            self.state = 0;
-                            Embed::Media(Media::External(uri))
-                        } else {
-                            self.state = 0;
-                            Embed::Record(post)
-                        }
-                    }
-                };
+                self.pending_delete = true;
             }
 
             _ => {
@@ -324,12 +329,12 @@ impl EventReceiver for &mut EmbedState {
                     }
                     _ => None,
                 };
-                if let Some(post) = post {
-                    return post_manager!()
-                        .at(&post.uri)
-                        .unwrap()
-                        .handle_events(event, agent)
-                        .await;
+                let action = keymap!()
+                    .resolve(&[Context::Post], &[KeyPress::from(&key)]);
+                if let (Some(post), Resolution::Action(action)) = (post, action)
+                {
+                    let post = post_manager!().at(&post.uri).unwrap();
+                    return post.handle_action(action, agent).await;
                 }
             }
         }
@@ -420,7 +425,7 @@ impl<'a> Widget for EmbedWidget<'a> {
     {
         let (media_height, post_height) = self.media_record_height(area.width);
         let [media_area, _, quote_area] = Layout::vertical([
-            Constraint::Length(2 + media_height as u16),
+            Constraint::Length(2 + media_height),
             Constraint::Length(1),
             Constraint::Length(post_height),
         ])
@@ -440,7 +445,7 @@ impl<'a> Widget for EmbedWidget<'a> {
             .title(Span::styled(title, Color::Gray));
 
         if let Some(media) = media {
-            let mut mw = MediaWidget::new(&media).block(media_block);
+            let mut mw = MediaWidget::new(media).block(media_block);
             if self.focused {
                 mw = mw.focused(self.embed.state);
             }
@@ -470,7 +475,7 @@ impl<'a> Widget for EmbedWidget<'a> {
                 }
             };
             PostWidget::new(post_manager!().at(&quote.uri).unwrap())
-                .is_selected(is_selected)
+                .selected(is_selected)
                 .has_border(true)
                 .render(quote_area, buf);
         }
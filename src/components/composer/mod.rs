@@ -1,3 +1,17 @@
 pub mod embed;
+#[allow(
+    clippy::bool_comparison,
+    clippy::clone_on_copy,
+    clippy::derivable_impls,
+    clippy::if_same_then_else,
+    clippy::into_iter_on_ref,
+    clippy::len_zero,
+    clippy::map_flatten,
+    clippy::redundant_pattern_matching,
+    clippy::skip_while_next,
+    clippy::wrong_self_convention,
+    reason = "the text area is kept as it was written, to be cleaned up on \
+              its own"
+)]
 pub mod textarea;
 pub mod vim;
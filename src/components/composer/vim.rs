@@ -1,7 +1,9 @@
-use bsky_sdk::BskyAgent;
 use ratatui::crossterm::event;
 
-use crate::app::{AppEvent, EventReceiver};
+use crate::{
+    app::{AppEvent, EventReceiver},
+    client::Client,
+};
 
 use super::textarea::{CursorMove, Input, Key, TextArea};
 
@@ -15,6 +17,8 @@ pub struct Vim {
     pub textarea: TextArea,
     pub mode: InputMode,
     pub allowed_input: fn(Input) -> bool,
+    // first key of a two key command like dd or gg, finished by the next key
+    pending: Option<char>,
 }
 
 impl Vim {
@@ -23,16 +27,48 @@ impl Vim {
             textarea: TextArea::from(String::new()),
             mode: InputMode::Insert,
             allowed_input: f,
+            pending: None,
+        }
+    }
+
+    fn finish_pending(&mut self, first: char, second: Input) {
+        let Key::Char(second) = second.key else {
+            return;
+        };
+        match (first, second) {
+            ('>', '>') => self.textarea.indent_right(),
+            ('<', '<') => self.textarea.indent_left(),
+            ('g', 'g') => self.textarea.move_cursor(CursorMove::Top),
+            ('d', 'd') => self.textarea.delete_line(),
+            ('d', 'w') => {
+                self.textarea.start_selection();
+                self.textarea.move_cursor(CursorMove::WordForward);
+                self.textarea.move_cursor(CursorMove::Back);
+                self.textarea.cut();
+            }
+            ('d', 'e') => {
+                self.textarea.start_selection();
+                self.textarea.move_cursor(CursorMove::WordEnd);
+                self.textarea.cut();
+            }
+            ('d', 'b') => {
+                self.textarea.start_selection();
+                self.textarea.move_cursor(CursorMove::WordBack);
+                self.textarea.cut();
+            }
+            _ => {}
         }
     }
 }
 
 impl EventReceiver for &mut Vim {
-    async fn handle_events(
-        self,
-        event: event::Event,
-        _: BskyAgent,
-    ) -> AppEvent {
+    async fn handle_events(self, event: event::Event, _: Client) -> AppEvent {
+        if let event::Event::Key(_) = event {
+            if let Some(first) = self.pending.take() {
+                self.finish_pending(first, event.into());
+                return AppEvent::None;
+            }
+        }
         match event.into() {
             Input { key: Key::Esc, .. } => match self.mode {
                 InputMode::Insert => {
@@ -50,7 +86,7 @@ impl EventReceiver for &mut Vim {
             },
 
             i if matches!(self.mode, InputMode::Insert) => {
-                self.textarea.input(i, &self.allowed_input);
+                self.textarea.input(i, self.allowed_input);
             }
 
             // normal mode
@@ -116,24 +152,9 @@ impl EventReceiver for &mut Vim {
                     self.textarea.delete_char();
                 }
             }
-            Input { key: Key::Char('>'), .. } => {
-                if matches!(self.mode, InputMode::Normal)
-                    && matches!(
-                        event::read().unwrap().into(),
-                        Input { key: Key::Char('>'), .. }
-                    )
-                {
-                    self.textarea.indent_right();
-                }
-            }
-            Input { key: Key::Char('<'), .. } => {
-                if matches!(self.mode, InputMode::Normal)
-                    && matches!(
-                        event::read().unwrap().into(),
-                        Input { key: Key::Char('<'), .. }
-                    )
-                {
-                    self.textarea.indent_left();
+            Input { key: Key::Char(c @ ('>' | '<')), .. } => {
+                if matches!(self.mode, InputMode::Normal) {
+                    self.pending = Some(c);
                 }
             }
 
@@ -167,12 +188,7 @@ impl EventReceiver for &mut Vim {
                 self.textarea.snap_cursor();
             }
             Input { key: Key::Char('g'), .. } => {
-                if matches!(
-                    event::read().unwrap().into(),
-                    Input { key: Key::Char('g'), .. }
-                ) {
-                    self.textarea.move_cursor(CursorMove::Top);
-                }
+                self.pending = Some('g');
             }
             Input { key: Key::Char('G'), .. } => {
                 self.textarea.move_cursor(CursorMove::Bottom);
@@ -180,29 +196,7 @@ impl EventReceiver for &mut Vim {
 
             Input { key: Key::Char('d'), .. } => match self.mode {
                 InputMode::Normal => {
-                    let e = event::read().unwrap().into();
-                    match e {
-                        Input { key: Key::Char('d'), .. } => {
-                            self.textarea.delete_line();
-                        }
-                        Input { key: Key::Char('w'), .. } => {
-                            self.textarea.start_selection();
-                            self.textarea.move_cursor(CursorMove::WordForward);
-                            self.textarea.move_cursor(CursorMove::Back);
-                            self.textarea.cut();
-                        }
-                        Input { key: Key::Char('e'), .. } => {
-                            self.textarea.start_selection();
-                            self.textarea.move_cursor(CursorMove::WordEnd);
-                            self.textarea.cut();
-                        }
-                        Input { key: Key::Char('b'), .. } => {
-                            self.textarea.start_selection();
-                            self.textarea.move_cursor(CursorMove::WordBack);
-                            self.textarea.cut();
-                        }
-                        _ => {}
-                    }
+                    self.pending = Some('d');
                 }
                 InputMode::Visual => {
                     self.textarea.cut();
@@ -222,3 +216,37 @@ impl EventReceiver for &mut Vim {
         return AppEvent::None;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    #[tokio::test]
+    async fn two_key_commands() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let mut vim = Vim::new(|_| true);
+        vim.textarea = TextArea::from("one\ntwo\nthree".to_string());
+        vim.mode = InputMode::Normal;
+        let press =
+            |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+        // the second key comes with the next event
+        vim.handle_events(press('j'), agent.clone()).await;
+        vim.handle_events(press('d'), agent.clone()).await;
+        assert_eq!(vim.textarea.lines(), ["one", "two", "three"]);
+        vim.handle_events(press('d'), agent.clone()).await;
+        assert_eq!(vim.textarea.lines(), ["one", "three"]);
+
+        // a key that finishes no command is dropped
+        vim.handle_events(press('d'), agent.clone()).await;
+        vim.handle_events(press('x'), agent.clone()).await;
+        assert_eq!(vim.textarea.lines(), ["one", "three"]);
+        vim.handle_events(press('x'), agent).await;
+        assert_eq!(vim.textarea.lines(), ["one", "hree"]);
+    }
+}
@@ -31,13 +31,13 @@ impl EmbedWidget {
 
     pub fn line_count(&self, width: u16) -> u16 {
         if let Embed::Record(record) = &self.embed {
-            RecordWidget::new(record, false).line_count(width) as u16
+            RecordWidget::new(record, false).line_count(width)
         } else {
-            self.non_record_paragraph().line_count(width - 2) as u16 + 2
+            self.non_record_paragraph().line_count(width - 2) + 2
         }
     }
 
-    fn non_record_paragraph(&self) -> Paragraph {
+    fn non_record_paragraph(&self) -> Paragraph<'_> {
         match &self.embed {
             Embed::Images(images) => Paragraph::new(
                 images
@@ -60,6 +60,8 @@ impl EmbedWidget {
                     .style(Style::default().add_modifier(Modifier::UNDERLINED)),
             ]),
 
+            Embed::Unsupported(u) => Paragraph::new(u.placeholder()),
+
             Embed::Record(_) => panic!("Shouldn't happen"),
         }
     }
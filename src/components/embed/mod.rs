@@ -1,17 +1,21 @@
 pub mod embed_widget;
 mod record_widget;
 
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 use atrium_api::{
     app::bsky::{
         embed::{record::ViewRecordRefs, record_with_media::ViewMediaRefs},
-        feed::defs::PostViewEmbedRefs,
+        feed::defs::{GeneratorView, PostViewEmbedRefs},
+        graph::defs::ListView,
     },
     types::{Object, Union},
 };
 
-use crate::components::actor::ActorBasic;
+use crate::{
+    components::{actor::ActorBasic, unsupported::Unsupported},
+    config,
+};
 
 #[derive(Clone)]
 pub enum Embed {
@@ -19,13 +23,18 @@ pub enum Embed {
     Video(Video),
     External(External),
     Record(Record),
+    Unsupported(Unsupported),
 }
 
 impl Embed {
     pub fn from(e: &Union<PostViewEmbedRefs>) -> Embed {
         let e = match e {
             Union::Refs(e) => e,
-            Union::Unknown(u) => panic!("Unknown embed type: {}", u.r#type),
+            Union::Unknown(u) => {
+                return Embed::Unsupported(Unsupported::new(
+                    "embed", &u.r#type, u,
+                ))
+            }
         };
         match e {
             PostViewEmbedRefs::AppBskyEmbedImagesView(view) => {
@@ -38,7 +47,7 @@ impl Embed {
                 Embed::External(External::from(view))
             }
             PostViewEmbedRefs::AppBskyEmbedRecordView(view) => {
-                Embed::Record(Record::from(&*view, None))
+                Embed::Record(Record::from(view, None))
             }
             PostViewEmbedRefs::AppBskyEmbedRecordWithMediaView(view) => {
                 let media = Some(EmbededPostMedia::from(&view.media));
@@ -50,32 +59,31 @@ impl Embed {
     pub fn open_media(&self) {
         match self {
             Self::Images(images) => {
-                let Result::Err(e) = Command::new("feh")
-                    .args(["--output-dir", "/tmp"])
-                    .args(["--zoom", "50%"])
-                    .arg("--")
-                    .args(images.iter().map(|i| i.url.clone()))
-                    .stderr(Stdio::null())
-                    .stdout(Stdio::null())
-                    .spawn()
+                let Result::Err(e) =
+                    config::command(&config!().media.image_viewer)
+                        .args(images.iter().map(|i| i.url.clone()))
+                        .stderr(Stdio::null())
+                        .stdout(Stdio::null())
+                        .spawn()
                 else {
                     return;
                 };
                 log::error!("{:?}", e);
             }
             Self::Video(video) => {
-                let Result::Err(e) = Command::new("vlc")
-                    .arg(video.m3u8.clone())
-                    .stderr(Stdio::null())
-                    .stdout(Stdio::null())
-                    .spawn()
+                let Result::Err(e) =
+                    config::command(&config!().media.video_player)
+                        .arg(video.m3u8.clone())
+                        .stderr(Stdio::null())
+                        .stdout(Stdio::null())
+                        .spawn()
                 else {
                     return;
                 };
                 log::error!("{:?}", e);
             }
             Self::External(external) => {
-                let Result::Err(e) = Command::new("xdg-open")
+                let Result::Err(e) = config::command(&config!().media.browser)
                     .arg(external.url.clone())
                     .stderr(Stdio::null())
                     .stdout(Stdio::null())
@@ -102,8 +110,23 @@ impl Embed {
                     EmbededPostMedia::External(external) => {
                         Embed::External(external.clone()).open_media();
                     }
+                    EmbededPostMedia::Unsupported(_) => {}
                 }
             }
+            Self::Unsupported(_) => {}
+        }
+    }
+
+    // Parts of the embed that could not be read, outermost first
+    pub fn unsupported(&self) -> Vec<&Unsupported> {
+        match self {
+            Self::Unsupported(u) => return vec![u],
+            Self::Record(Record::Unsupported(u)) => return vec![u],
+            Self::Record(Record::Post(EmbededPost {
+                media: Some(EmbededPostMedia::Unsupported(u)),
+                ..
+            })) => return vec![u],
+            _ => return vec![],
         }
     }
 }
@@ -114,11 +137,12 @@ pub enum Record {
     Blocked,
     NotFound,
     Detached,
-    // List(EmbededList),
-    // Generator(EmbededGenerator),
+    List(EmbededList),
+    Generator(EmbededGenerator),
     // Labler(EmbededLabler),
     // StarterPack(EmbededStarterPack),
     NotImplemented,
+    Unsupported(Unsupported),
 }
 
 impl Record {
@@ -129,18 +153,25 @@ impl Record {
         let record = match &view.record {
             Union::Refs(e) => e,
             Union::Unknown(u) => {
-                panic!("Unknown embed record type: {}", u.r#type)
+                return Record::Unsupported(Unsupported::new(
+                    "record", &u.r#type, u,
+                ))
             }
         };
         match record {
             ViewRecordRefs::ViewRecord(post) => {
-                let atrium_api::types::Unknown::Object(record) = &post.value
-                else {
-                    panic!("Unknown embeded post value type");
+                let text = match &post.value {
+                    atrium_api::types::Unknown::Object(record) => {
+                        record.get("text").map(|t| &**t)
+                    }
+                    _ => None,
                 };
-                let ipld_core::ipld::Ipld::String(text) = &*record["text"]
-                else {
-                    panic!("embeded text is not a string");
+                let Some(ipld_core::ipld::Ipld::String(text)) = text else {
+                    return Record::Unsupported(Unsupported::new(
+                        "record",
+                        "app.bsky.embed.record#viewRecord",
+                        &post.value,
+                    ));
                 };
                 let text = text.clone();
                 // let text = text.replace("\t", "    ");
@@ -153,7 +184,7 @@ impl Record {
                     has_embed: post
                         .embeds
                         .as_ref()
-                        .map(|v| v.len() > 0)
+                        .map(|v| !v.is_empty())
                         .unwrap_or(false),
                     media,
                     text,
@@ -163,6 +194,12 @@ impl Record {
             ViewRecordRefs::ViewBlocked(_) => Record::Blocked,
             ViewRecordRefs::ViewNotFound(_) => Record::NotFound,
             ViewRecordRefs::ViewDetached(_) => Record::Detached,
+            ViewRecordRefs::AppBskyGraphDefsListView(list) => {
+                Record::List(EmbededList::from(list))
+            }
+            ViewRecordRefs::AppBskyFeedDefsGeneratorView(generator) => {
+                Record::Generator(EmbededGenerator::from(generator))
+            }
             _ => Record::NotImplemented,
         }
     }
@@ -183,6 +220,7 @@ pub enum EmbededPostMedia {
     Images(Vec<Image>),
     Video(Video),
     External(External),
+    Unsupported(Unsupported),
 }
 
 impl EmbededPostMedia {
@@ -194,7 +232,9 @@ impl EmbededPostMedia {
         let media = match media {
             Union::Refs(e) => e,
             Union::Unknown(u) => {
-                panic!("Unknown embed media type: {}", u.r#type)
+                return EmbededPostMedia::Unsupported(Unsupported::new(
+                    "embed", &u.r#type, u,
+                ))
             }
         };
         match media {
@@ -226,12 +266,13 @@ impl EmbededPostMedia {
     }
 }
 
-impl Into<Embed> for EmbededPostMedia {
-    fn into(self) -> Embed {
-        match self {
+impl From<EmbededPostMedia> for Embed {
+    fn from(media: EmbededPostMedia) -> Self {
+        match media {
             EmbededPostMedia::Images(images) => Embed::Images(images),
             EmbededPostMedia::Video(video) => Embed::Video(video),
             EmbededPostMedia::External(external) => Embed::External(external),
+            EmbededPostMedia::Unsupported(u) => Embed::Unsupported(u),
         }
     }
 }
@@ -286,25 +327,49 @@ impl External {
     }
 }
 
-// #[derive(Clone)]
-// struct EmbededList {
-//     uri: String,
-//     name: String,
-//     description: String,
-//     author: String,
-//     handle: String,
-// }
-//
-// #[derive(Clone)]
-// struct EmbededGenerator {
-//     uri: String,
-//     name: String,
-//     description: String,
-//     author: String,
-//     handle: String,
-//     // label
-// }
-//
+#[derive(Clone)]
+pub struct EmbededList {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    pub handle: String,
+}
+
+impl EmbededList {
+    pub fn from(list: &ListView) -> EmbededList {
+        EmbededList {
+            uri: list.uri.clone(),
+            name: list.name.clone(),
+            description: list.description.clone().unwrap_or_default(),
+            author: list.creator.display_name.clone().unwrap_or_default(),
+            handle: list.creator.handle.to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EmbededGenerator {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    pub handle: String,
+    // label
+}
+
+impl EmbededGenerator {
+    pub fn from(generator: &GeneratorView) -> EmbededGenerator {
+        EmbededGenerator {
+            uri: generator.uri.clone(),
+            name: generator.display_name.clone(),
+            description: generator.description.clone().unwrap_or_default(),
+            author: generator.creator.display_name.clone().unwrap_or_default(),
+            handle: generator.creator.handle.to_string(),
+        }
+    }
+}
+
 // #[derive(Clone)]
 // struct EmbededLabler {
 //     // No name?
@@ -40,7 +40,7 @@ impl<'a> RecordWidget<'a> {
                         .collect::<Vec<Line>>(),
                 )
                 .wrap(true)
-                .line_count(width - 2) as u16;
+                .line_count(width - 2);
 
                 let media_lines = post
                     .media
@@ -52,6 +52,13 @@ impl<'a> RecordWidget<'a> {
 
                 media_lines + (1 + text_lines) + post.has_embed as u16 + 2
             }
+            Record::List(list) => {
+                1 + description(&list.description).line_count(width - 2) + 2
+            }
+            Record::Generator(generator) => {
+                1 + description(&generator.description).line_count(width - 2)
+                    + 2
+            }
             _ => 1 + 2,
         }
     }
@@ -88,7 +95,7 @@ impl<'a> Widget for RecordWidget<'a> {
                             .unwrap_or(0),
                     ),
                     Constraint::Length(
-                        text.line_count(area.width - 2) as u16
+                        text.line_count(area.width - 2)
                             + 1
                             + post.has_embed as u16
                             + 2,
@@ -96,7 +103,9 @@ impl<'a> Widget for RecordWidget<'a> {
                 ])
                 .areas(area);
 
-                media.map(|e| e.render(media_area, buf));
+                if let Some(e) = media {
+                    e.render(media_area, buf)
+                }
 
                 let quote_border = Block::bordered()
                     .style(self.style)
@@ -108,9 +117,7 @@ impl<'a> Widget for RecordWidget<'a> {
                 let [author_area, text_area, quote_embed_area] =
                     Layout::vertical([
                         Constraint::Length(1),
-                        Constraint::Length(
-                            text.line_count(area.width - 2) as u16
-                        ),
+                        Constraint::Length(text.line_count(area.width - 2)),
                         Constraint::Length(post.has_embed as u16),
                     ])
                     .areas(quote_inner_area);
@@ -135,6 +142,60 @@ impl<'a> Widget for RecordWidget<'a> {
                 }
             }
 
+            Record::List(list) => {
+                let border = Block::bordered()
+                    .style(self.style)
+                    .border_set(symbols::border::ROUNDED)
+                    .border_style(Color::DarkGray);
+                let inner_area = border.inner(area);
+                border.render(area, buf);
+
+                let description = description(&list.description);
+                let [name_area, description_area] = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Length(
+                        description.line_count(inner_area.width),
+                    ),
+                ])
+                .areas(inner_area);
+
+                (Span::styled(format!("[list] {}", list.name), Color::Cyan)
+                    + Span::styled(
+                        format!(" by {} @{}", list.author, list.handle),
+                        Color::Gray,
+                    ))
+                .render(name_area, buf);
+                description.render(description_area, buf);
+            }
+
+            Record::Generator(generator) => {
+                let border = Block::bordered()
+                    .style(self.style)
+                    .border_set(symbols::border::ROUNDED)
+                    .border_style(Color::DarkGray);
+                let inner_area = border.inner(area);
+                border.render(area, buf);
+
+                let description = description(&generator.description);
+                let [name_area, description_area] = Layout::vertical([
+                    Constraint::Length(1),
+                    Constraint::Length(
+                        description.line_count(inner_area.width),
+                    ),
+                ])
+                .areas(inner_area);
+
+                (Span::styled(
+                    format!("[feed] {}", generator.name),
+                    Color::Cyan,
+                ) + Span::styled(
+                    format!(" by {} @{}", generator.author, generator.handle),
+                    Color::Gray,
+                ))
+                .render(name_area, buf);
+                description.render(description_area, buf);
+            }
+
             Record::Blocked => {
                 Line::from("[blocked]").render(area, buf);
             }
@@ -147,6 +208,20 @@ impl<'a> Widget for RecordWidget<'a> {
             Record::NotImplemented => {
                 Line::from("[Not implemented]").render(area, buf);
             }
+            Record::Unsupported(u) => {
+                Line::from(u.placeholder()).render(area, buf);
+            }
         }
     }
 }
+
+fn description(text: &str) -> Paragraph<'_> {
+    let lines = if text.is_empty() {
+        vec![]
+    } else {
+        text.split('\n')
+            .map(|line| Line::from(line).style(Color::White))
+            .collect::<Vec<Line>>()
+    };
+    Paragraph::new(lines).wrap(true)
+}
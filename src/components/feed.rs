@@ -1,6 +1,8 @@
+use std::collections::HashSet;
+
 use atrium_api::{
     app::bsky::feed::defs::{
-        FeedViewPost, FeedViewPostReasonRefs, ReplyRefParentRefs,
+        FeedViewPost, FeedViewPostReasonRefs, PostView, ReplyRefParentRefs,
     },
     types::{string::Did, Union},
 };
@@ -16,7 +18,8 @@ use ratatui::{
 use crate::{
     components::{
         list::{List, ListState},
-        post::{post_widget::PostWidget, Post},
+        post::{post_widget::PostWidget, Facet, Post},
+        unsupported::Unsupported,
     },
     post_manager,
 };
@@ -29,16 +32,20 @@ pub struct PostFeed {
 }
 
 impl PostFeed {
+    pub fn post_uris(&self, uris: &mut HashSet<String>) {
+        uris.extend(self.posts.iter().map(|p| p.post_uri.clone()));
+    }
+
     pub fn insert_new_posts<T>(&mut self, new_posts: T) -> bool
     where
         T: Iterator<Item = FeedPost>,
     {
         let new_posts = new_posts.collect::<Vec<_>>();
-        if new_posts.len() == 0 {
+        if new_posts.is_empty() {
             return true;
         }
 
-        if self.posts.len() == 0 {
+        if self.posts.is_empty() {
             self.posts = new_posts;
             self.state.selected = Some(0);
             self.remove_duplicate();
@@ -93,7 +100,7 @@ impl PostFeed {
     where
         T: Iterator<Item = FeedPost> + Clone,
     {
-        if self.posts.len() == 0 {
+        if self.posts.is_empty() {
             return;
         }
 
@@ -125,7 +132,7 @@ impl PostFeed {
             }
             let mut j = i + 1;
             while j < self.posts.len() {
-                let selected_post = &self.posts[j as usize];
+                let selected_post = &self.posts[j];
                 let position = new_view.iter().position(|p| p == selected_post);
                 if position.is_some() {
                     self.state.selected = position;
@@ -155,9 +162,8 @@ impl Widget for &mut PostFeed {
 
         List::new(self.posts.len(), |context| {
             let post = &posts[context.index];
-            let item =
-                FeedPostWidget::new(post).is_selected(context.is_selected);
-            let height = item.line_count(width) as u16;
+            let item = FeedPostWidget::new(post).selected(context.is_selected);
+            let height = item.line_count(width);
             return (item, height);
         })
         .render(area, buf, &mut self.state);
@@ -168,6 +174,7 @@ impl Widget for &mut PostFeed {
 pub enum Reason {
     Repost(RepostBy),
     Pin,
+    Unsupported(Unsupported),
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -187,9 +194,10 @@ pub struct ReplyData {
 
 #[derive(Clone)]
 pub enum Reply {
-    Reply(ReplyData),
+    Post(ReplyData),
     DeletedPost,
     BlockedUser,
+    Unsupported(Unsupported),
 }
 
 #[derive(Clone)]
@@ -200,8 +208,16 @@ pub struct FeedPost {
 }
 
 impl FeedPost {
-    pub fn from(view: &FeedViewPost) -> FeedPost {
-        let post = Post::from(&view.post);
+    // A post shown on its own, e.g. a quote of another post
+    pub fn from_post(view: &PostView) -> Result<FeedPost, String> {
+        let post = Post::try_from(view)?;
+        let uri = post.uri.clone();
+        post_manager!().insert(post);
+        return Ok(FeedPost { post_uri: uri, reason: None, reply_to: None });
+    }
+
+    pub fn try_from(view: &FeedViewPost) -> Result<FeedPost, String> {
+        let post = Post::try_from(&view.post)?;
         let uri = post.uri.clone();
         post_manager!().insert(post);
 
@@ -216,9 +232,9 @@ impl FeedPost {
             Some(Union::Refs(FeedViewPostReasonRefs::ReasonPin(_))) => {
                 Some(Reason::Pin)
             }
-            Some(Union::Unknown(u)) => {
-                panic!("Unknown reason type: {}", u.r#type)
-            }
+            Some(Union::Unknown(u)) => Some(Reason::Unsupported(
+                Unsupported::new("reason", &u.r#type, u),
+            )),
             _ => None,
         };
 
@@ -226,7 +242,9 @@ impl FeedPost {
             let parent = match &r.parent {
                 Union::Refs(e) => e,
                 Union::Unknown(u) => {
-                    panic!("Unknown parent type: {}", u.r#type)
+                    return Reply::Unsupported(Unsupported::new(
+                        "parent", &u.r#type, u,
+                    ))
                 }
             };
             match parent {
@@ -241,14 +259,31 @@ impl FeedPost {
                     let following = view.author.viewer.is_some()
                         && view .author.viewer.as_ref().unwrap().following.is_some();
                     let did = view.author.did.clone();
-                    Reply::Reply(ReplyData { author, handle, following, did })
+                    Reply::Post(ReplyData { author, handle, following, did })
                 }
                 ReplyRefParentRefs::NotFoundPost(_) => Reply::DeletedPost,
                 ReplyRefParentRefs::BlockedPost(_) => Reply::BlockedUser,
             }
         });
 
-        return FeedPost { post_uri: uri, reason, reply_to };
+        return Ok(FeedPost { post_uri: uri, reason, reply_to });
+    }
+
+    // Parts of the feed item and its post that could not be read
+    pub fn unsupported(&self) -> Vec<Unsupported> {
+        let reason = match &self.reason {
+            Some(Reason::Unsupported(u)) => Some(u.clone()),
+            _ => None,
+        };
+        let reply_to = match &self.reply_to {
+            Some(Reply::Unsupported(u)) => Some(u.clone()),
+            _ => None,
+        };
+        let post = post_manager!()
+            .at(&self.post_uri)
+            .map(|p| p.unsupported().into_iter().cloned().collect())
+            .unwrap_or(vec![]);
+        return reason.into_iter().chain(reply_to).chain(post).collect();
     }
 }
 
@@ -276,7 +311,7 @@ impl<'a> FeedPostWidget<'a> {
         }
     }
 
-    pub fn is_selected(mut self, is_selected: bool) -> Self {
+    pub fn selected(mut self, is_selected: bool) -> Self {
         self.is_selected = is_selected;
         self.style = if is_selected {
             Style::default().bg(Color::Rgb(45, 50, 55))
@@ -288,11 +323,23 @@ impl<'a> FeedPostWidget<'a> {
 
     pub fn line_count(&self, width: u16) -> u16 {
         let post = post_manager!().at(&self.feed_post.post_uri).unwrap();
-        PostWidget::new(post).line_count(width)
+        PostWidget::new(post).line_count(width - 2)
             + self.feed_post.reply_to.is_some() as u16
             + self.feed_post.reason.is_some() as u16
             + 2 // borders
     }
+
+    // Facet rendered at (x, y) relative to the widget
+    pub fn facet_at(&self, width: u16, x: u16, y: u16) -> Option<Facet> {
+        let post = post_manager!().at(&self.feed_post.post_uri).unwrap();
+        let top = self.feed_post.reason.is_some() as u16
+            + self.feed_post.reply_to.is_some() as u16;
+        return PostWidget::new(post).facet_at(
+            width.checked_sub(2)?,
+            x.checked_sub(1)?,
+            y.checked_sub(1 + top)?,
+        );
+    }
 }
 
 impl<'a> Widget for FeedPostWidget<'a> {
@@ -311,7 +358,7 @@ impl<'a> Widget for FeedPostWidget<'a> {
         borders.render(area, buf);
 
         let post = post_manager!().at(&self.feed_post.post_uri).unwrap();
-        let post_widget = PostWidget::new(post).is_selected(self.is_selected);
+        let post_widget = PostWidget::new(post).selected(self.is_selected);
 
         let [top_area, post_area] = Layout::vertical([
             Constraint::Length(
@@ -342,14 +389,19 @@ impl<'a> Widget for FeedPostWidget<'a> {
             Some(Reason::Pin) => {
                 Line::styled("📌Pinned", Color::Green).render(reason_area, buf);
             }
+            Some(Reason::Unsupported(u)) => {
+                Line::styled(u.placeholder(), Color::DarkGray)
+                    .render(reason_area, buf);
+            }
             None => {}
         }
 
         if let Some(reply_to) = &self.feed_post.reply_to {
             let reply_to = match reply_to {
-                Reply::Reply(a) => &a.author,
-                Reply::DeletedPost => "[deleted post]",
-                Reply::BlockedUser => "[blocked user]",
+                Reply::Post(a) => a.author.clone(),
+                Reply::DeletedPost => "[deleted post]".to_string(),
+                Reply::BlockedUser => "[blocked user]".to_string(),
+                Reply::Unsupported(u) => u.placeholder(),
             };
             Line::from(Span::styled(
                 format!("⮡ Reply to {}", reply_to),
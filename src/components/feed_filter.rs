@@ -0,0 +1,208 @@
+use atrium_api::types::string::Did;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+    columns::updating_feed::FeedSource,
+    components::{
+        embed::{Embed, Record},
+        feed::{FeedPost, Reason, Reply},
+    },
+    post_manager,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Replies {
+    All,
+    // replies to followed accounts and to the user
+    Following,
+    None,
+}
+
+// Rules deciding which posts a feed column shows, applied to every page
+// fetched for the column
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedFilter {
+    pub hide_reposts: bool,
+    // reposts by these accounts are hidden, their own posts are kept
+    pub hide_reposts_by: Vec<Did>,
+    // None picks by source: the timeline has replies to anyone and only keeps
+    // the ones to followed accounts, feed generators and lists pick their
+    // replies themselves
+    pub replies: Option<Replies>,
+    pub hide_quotes: bool,
+    // keeps posts in any of these languages, e.g. "en" also keeps "en-US".
+    // Posts without languages are kept.
+    pub langs: Vec<String>,
+    // posts and reposts by these accounts are hidden
+    pub hide_accounts: Vec<Did>,
+}
+
+impl FeedFilter {
+    pub fn for_source(&self, source: &FeedSource) -> FeedFilter {
+        let mut filter = self.clone();
+        filter.replies.get_or_insert(match source {
+            FeedSource::Following => Replies::Following,
+            _ => Replies::All,
+        });
+        return filter;
+    }
+
+    pub fn keeps(&self, post: &FeedPost, me: &Did) -> bool {
+        if let Some(Reason::Repost(by)) = &post.reason {
+            if self.hide_reposts
+                || self.hide_reposts_by.contains(&by.did)
+                || self.hide_accounts.contains(&by.did)
+            {
+                return false;
+            }
+        }
+
+        let keeps_reply = match (self.replies, &post.reply_to) {
+            (_, None) | (Some(Replies::All) | None, _) => true,
+            (Some(Replies::None), Some(_)) => false,
+            (Some(Replies::Following), Some(Reply::Post(r))) => {
+                r.following || r.did == *me
+            }
+            (Some(Replies::Following), Some(_)) => false,
+        };
+        if !keeps_reply {
+            return false;
+        }
+
+        let Some(post) = post_manager!().at(&post.post_uri) else {
+            return true;
+        };
+        if self.hide_accounts.contains(&post.author.did) {
+            return false;
+        }
+        if self.hide_quotes
+            && matches!(
+                post.embed,
+                Some(Embed::Record(
+                    Record::Post(_)
+                        | Record::Blocked
+                        | Record::NotFound
+                        | Record::Detached
+                ))
+            )
+        {
+            return false;
+        }
+        if !self.langs.is_empty() && !post.langs.is_empty() {
+            return post.langs.iter().any(|lang| {
+                self.langs.iter().any(|l| {
+                    let lang = lang.to_lowercase();
+                    let l = l.to_lowercase();
+                    lang == l || lang.starts_with(&format!("{}-", l))
+                })
+            });
+        }
+        return true;
+    }
+
+    // Rules in effect, for the log
+    pub fn describe(&self) -> String {
+        let mut rules = Vec::new();
+        if self.hide_reposts {
+            rules.push("no reposts".to_string());
+        }
+        if !self.hide_reposts_by.is_empty() {
+            rules.push(format!(
+                "no reposts by {}",
+                self.hide_reposts_by.iter().map(Did::as_str).join(", ")
+            ));
+        }
+        match self.replies {
+            Some(Replies::Following) => {
+                rules.push("replies to followed accounts".to_string())
+            }
+            Some(Replies::None) => rules.push("no replies".to_string()),
+            Some(Replies::All) | None => {}
+        }
+        if self.hide_quotes {
+            rules.push("no quotes".to_string());
+        }
+        if !self.langs.is_empty() {
+            rules.push(format!("langs {}", self.langs.join(", ")));
+        }
+        if !self.hide_accounts.is_empty() {
+            rules.push(format!(
+                "hiding {}",
+                self.hide_accounts.iter().map(Did::as_str).join(", ")
+            ));
+        }
+        if rules.is_empty() {
+            return "showing everything".to_string();
+        }
+        return rules.join("; ");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use atrium_api::app::bsky::feed::get_timeline;
+
+    use super::*;
+    use crate::client::{fake::FakeApi, Client};
+
+    async fn timeline() -> (Vec<FeedPost>, Did) {
+        let agent: Client = Arc::new(FakeApi::new());
+        let output = agent
+            .get_timeline(
+                get_timeline::ParametersData {
+                    algorithm: None,
+                    cursor: None,
+                    limit: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        let posts = output
+            .data
+            .feed
+            .iter()
+            .map(|p| FeedPost::try_from(p).unwrap())
+            .collect();
+        return (posts, agent.did().await.unwrap());
+    }
+
+    fn kept(filter: &FeedFilter, posts: &[FeedPost], me: &Did) -> usize {
+        return posts.iter().filter(|p| filter.keeps(p, me)).count();
+    }
+
+    #[tokio::test]
+    async fn rules() {
+        let (posts, me) = timeline().await;
+        let all = FeedFilter::default().for_source(&FeedSource::List(
+            "at://did:plc:bob/app.bsky.graph.list/people".to_string(),
+        ));
+        assert_eq!(all.replies, Some(Replies::All));
+        assert_eq!(kept(&all, &posts, &me), 4);
+
+        let timeline = FeedFilter::default().for_source(&FeedSource::Following);
+        assert_eq!(timeline.replies, Some(Replies::Following));
+        assert_eq!(kept(&timeline, &posts, &me), 4);
+
+        let bob = Did::new("did:plc:bob".to_string()).unwrap();
+        let alice = Did::new("did:plc:alice".to_string()).unwrap();
+        let cases = [
+            (FeedFilter { hide_reposts: true, ..all.clone() }, 3),
+            (FeedFilter { hide_reposts_by: vec![alice], ..all.clone() }, 3),
+            (FeedFilter { replies: Some(Replies::None), ..all.clone() }, 3),
+            (FeedFilter { hide_quotes: true, ..all.clone() }, 3),
+            (FeedFilter { langs: vec!["ja".to_string()], ..all.clone() }, 0),
+            (FeedFilter { langs: vec!["EN".to_string()], ..all.clone() }, 4),
+            // bob's reply and bob's post reposted by alice
+            (FeedFilter { hide_accounts: vec![bob], ..all.clone() }, 2),
+        ];
+        for (filter, count) in cases {
+            assert_eq!(kept(&filter, &posts, &me), count, "{:?}", filter);
+        }
+    }
+}
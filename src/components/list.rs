@@ -1,21 +1,29 @@
 use std::ops::Range;
 
 use ratatui::{
-    layout::Position,
+    layout::{Position, Rect},
     text::Text,
     widgets::{StatefulWidget, Widget},
 };
 
+// Items fetched so far and the cursor of the next page, None until the first
+// page is fetched
+pub type Pages<T> = Option<(Vec<T>, Option<String>)>;
+
 #[derive(Clone, Debug, Default)]
 pub struct ListState {
     pub selected: Option<usize>,
     selected_y: i32,
     delta_index: i64,
+    // Where the list and its visible items were last rendered, for mouse
+    // hit-testing. Items are (index, y relative to area, height).
+    area: Rect,
+    items: Vec<(usize, i32, u16)>,
 }
 
 impl ListState {
     pub fn new(selected: Option<usize>) -> Self {
-        ListState { selected, selected_y: 0, delta_index: 0 }
+        ListState { selected, ..Default::default() }
     }
 }
 
@@ -35,6 +43,37 @@ impl ListState {
             }
         }
     }
+
+    pub fn area(&self) -> Rect {
+        return self.area;
+    }
+
+    // Index of the item drawn at a screen position and the position
+    // relative to the item's top left corner
+    pub fn item_at(&self, position: Position) -> Option<(usize, Position)> {
+        if !self.area.contains(position) {
+            return None;
+        }
+        let y = (position.y - self.area.y) as i32;
+        let &(index, top, _) = self
+            .items
+            .iter()
+            .find(|(_, top, height)| y >= *top && y < top + *height as i32)?;
+        let relative =
+            Position::new(position.x - self.area.x, (y - top) as u16);
+        return Some((index, relative));
+    }
+
+    // Selects a visible item without scrolling
+    pub fn select_visible(&mut self, index: usize) {
+        let Some(&(_, top, _)) = self.items.iter().find(|(i, ..)| *i == index)
+        else {
+            return;
+        };
+        self.selected = Some(index);
+        self.selected_y = top;
+        self.delta_index = 0;
+    }
 }
 
 pub struct ListContext {
@@ -79,6 +118,8 @@ where
         buf: &mut ratatui::prelude::Buffer,
         state: &mut Self::State,
     ) {
+        state.area = area;
+        state.items.clear();
         if state.selected.is_some() {
             if self.len == 0 {
                 state.selected = None;
@@ -144,6 +185,7 @@ where
                     .unwrap_or(false),
             });
             y -= height as i32;
+            state.items.push((i as usize, y, height));
             render_truncated(
                 item,
                 SignedRect {
@@ -155,7 +197,7 @@ where
                 area,
                 buf,
             );
-            if bottom_y == None {
+            if bottom_y.is_none() {
                 bottom_y = Some(y + height as i32);
             }
 
@@ -200,7 +242,8 @@ where
         let mut y = bottom_y.unwrap_or(0);
         while i < self.len && y < area.height as i32 {
             let (item, height) =
-                (self.f)(ListContext { index: i as usize, is_selected: false });
+                (self.f)(ListContext { index: i, is_selected: false });
+            state.items.push((i, y, height));
             render_truncated(
                 item,
                 SignedRect {
@@ -217,7 +260,7 @@ where
                 .iter()
                 .find(|r| i >= r.start && i < r.end)
                 .is_some()
-                && i != self.len as usize - 1
+                && i != self.len - 1
             {
                 Text::from("┬").render(
                     ratatui::layout::Rect {
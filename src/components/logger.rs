@@ -1,9 +1,14 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use chrono::{DateTime, Local};
 use lazy_static::lazy_static;
 use tokio::sync::Mutex;
 
+use crate::redraw;
+
+// How long the last log stays on the bottom line
+pub const LOG_SECONDS: u64 = 5;
+
 lazy_static! {
     pub static ref LOGSTORE: LogStore = LogStore::new();
 }
@@ -22,8 +27,11 @@ impl log::Log for Logger {
             let time = chrono::Local::now();
             let msg = format!("[{}] {}", record.level(), record.args());
             tokio::spawn(async move {
-                let mut logs = logs.lock().await;
-                logs.push((time, msg));
+                logs.lock().await.push((time, msg));
+                redraw!();
+                // hide it again
+                tokio::time::sleep(Duration::from_secs(LOG_SECONDS)).await;
+                redraw!();
             });
         }
     }
@@ -31,8 +39,11 @@ impl log::Log for Logger {
     fn flush(&self) {}
 }
 
+// Time of each message and the message
+pub type Logs = Vec<(DateTime<Local>, String)>;
+
 pub struct LogStore {
-    pub logs: Arc<Mutex<Vec<(DateTime<Local>, String)>>>,
+    pub logs: Arc<Mutex<Logs>>,
 }
 
 impl LogStore {
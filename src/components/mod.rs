@@ -2,6 +2,7 @@ pub mod actor;
 pub mod composer;
 pub mod embed;
 pub mod feed;
+pub mod feed_filter;
 pub mod list;
 pub mod logger;
 pub mod notification;
@@ -10,3 +11,4 @@ pub mod post;
 pub mod post_manager;
 pub mod paragraph;
 pub mod separation;
+pub mod unsupported;
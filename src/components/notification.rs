@@ -1,8 +1,4 @@
-use atrium_api::{
-    app::bsky::notification::list_notifications::NotificationData,
-    types::TryFromUnknown,
-};
-use chrono::{DateTime, FixedOffset, Local};
+use atrium_api::app::bsky::notification::list_notifications::NotificationData;
 use ratatui::{
     layout::{Constraint, Layout},
     style::Color,
@@ -11,7 +7,12 @@ use ratatui::{
 };
 
 use crate::{
-    components::{actor::Actor, post::post_widget::PostWidget},
+    components::{
+        actor::Actor,
+        post::post_widget::PostWidget,
+        unsupported::{from_unknown, Unsupported},
+    },
+    keymap::{key_hint, Action, Context},
     post_manager,
 };
 
@@ -28,6 +29,7 @@ pub enum Record {
     Mention(PostUri),
     Quote(PostUri),
     Follow,
+    Unsupported(Unsupported),
 }
 
 impl Record {
@@ -40,20 +42,24 @@ impl Record {
 
         match reason.as_str() {
             "like" => {
-                let r = like::RecordData::try_from_unknown(record)
-                    .map_err(|e| e.to_string())?;
+                let r = from_unknown::<like::RecordData>(&record)?;
                 return Ok(Record::Like(r.subject.uri.clone()));
             }
             "repost" => {
-                let r = repost::RecordData::try_from_unknown(record)
-                    .map_err(|e| e.to_string())?;
+                let r = from_unknown::<repost::RecordData>(&record)?;
                 return Ok(Record::Repost(r.subject.uri.clone()));
             }
             "reply" => return Ok(Record::Reply(uri)),
             "mention" => return Ok(Record::Mention(uri)),
             "quote" => return Ok(Record::Quote(uri)),
             "follow" => return Ok(Record::Follow),
-            _ => return Err("Unknown notification reason".to_string()),
+            _ => {
+                return Ok(Record::Unsupported(Unsupported::new(
+                    "notification",
+                    &reason,
+                    &record,
+                )))
+            }
         }
     }
 }
@@ -64,32 +70,16 @@ pub struct Notification {
     pub author: Actor,
     pub record: Record,
     pub is_read: bool,
-    pub indexed_at: DateTime<FixedOffset>,
 }
 
 impl Notification {
     pub fn new(data: NotificationData) -> Result<Self, String> {
-        let NotificationData {
-            uri,
-            author,
-            indexed_at,
-            is_read,
-            reason,
-            record,
-            ..
-        } = data;
+        let NotificationData { uri, author, is_read, reason, record, .. } =
+            data;
 
         let author = Actor::new(author.data);
         let record = Record::new(uri.clone(), reason, record)?;
-        let indexed_at = {
-            let indexed_at = indexed_at.as_str();
-            let dt = Local::now();
-            let indexed_at_utc =
-                DateTime::parse_from_rfc3339(indexed_at).unwrap().naive_local();
-            DateTime::from_naive_utc_and_offset(indexed_at_utc, *dt.offset())
-        };
-
-        Ok(Notification { uri, author, record, is_read, indexed_at })
+        Ok(Notification { uri, author, record, is_read })
     }
 }
 
@@ -128,32 +118,33 @@ impl<'a> NotificationWidget<'a> {
         let width = width - bh;
         match &self.notif.record {
             Record::Like(subject) => {
-                let post = post_manager!().at(&subject).unwrap();
+                let post = post_manager!().at(subject).unwrap();
                 PostWidget::new(post).show_author(false).line_count(width)
                     + bh
                     + 2
             }
             Record::Repost(subject) => {
-                let post = post_manager!().at(&subject).unwrap();
+                let post = post_manager!().at(subject).unwrap();
                 PostWidget::new(post).show_author(false).line_count(width)
                     + bh
                     + 2
             }
             Record::Reply(post_uri) => {
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).show_author(false).line_count(width)
                     + bh
                     + 2
             }
             Record::Mention(post_uri) => {
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).line_count(width) + bh
             }
             Record::Quote(post_uri) => {
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).line_count(width) + bh
             }
             Record::Follow => 1 + bh,
+            Record::Unsupported(_) => 1 + bh,
         }
     }
 }
@@ -178,6 +169,14 @@ impl<'a> Widget for NotificationWidget<'a> {
         } else {
             area
         };
+        let author_hint = match self.focused {
+            true => {
+                key_hint(Context::Notifications, Action::NotificationAuthor)
+                    .map(|keys| format!(" {}", keys))
+                    .unwrap_or_default()
+            }
+            false => String::new(),
+        };
         match &self.notif.record {
             Record::Like(subject) => {
                 let [reason_area, separation_area, post_area] =
@@ -190,10 +189,7 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Line::from(vec![
                     Span::styled(&self.notif.author.basic.name, Color::Cyan),
-                    Span::styled(
-                        if self.focused { " (A)" } else { "" },
-                        Color::DarkGray,
-                    ),
+                    Span::styled(author_hint.clone(), Color::DarkGray),
                     Span::styled(" ♡ liked", Color::Green),
                     Span::styled(" your post", Color::Gray),
                 ])
@@ -201,7 +197,7 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Separation::default().render(separation_area, buf);
 
-                let post = post_manager!().at(&subject).unwrap();
+                let post = post_manager!().at(subject).unwrap();
                 PostWidget::new(post).show_author(false).render(post_area, buf);
             }
             Record::Repost(subject) => {
@@ -215,10 +211,7 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Line::from(vec![
                     Span::styled(&self.notif.author.basic.name, Color::Cyan),
-                    Span::styled(
-                        if self.focused { " (A)" } else { "" },
-                        Color::DarkGray,
-                    ),
+                    Span::styled(author_hint.clone(), Color::DarkGray),
                     Span::styled(" ⭮ reposted", Color::Green),
                     Span::styled(" your post", Color::Gray),
                 ])
@@ -226,7 +219,7 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Separation::default().render(separation_area, buf);
 
-                let post = post_manager!().at(&subject).unwrap();
+                let post = post_manager!().at(subject).unwrap();
                 PostWidget::new(post).show_author(false).render(post_area, buf);
             }
             Record::Reply(post_uri) => {
@@ -240,10 +233,7 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Line::from(vec![
                     Span::styled(&self.notif.author.basic.name, Color::Cyan),
-                    Span::styled(
-                        if self.focused { " (A)" } else { "" },
-                        Color::DarkGray,
-                    ),
+                    Span::styled(author_hint.clone(), Color::DarkGray),
                     Span::styled(" 💬replied", Color::Green),
                     Span::styled(" to your post", Color::Gray),
                 ])
@@ -251,29 +241,34 @@ impl<'a> Widget for NotificationWidget<'a> {
 
                 Separation::default().render(separation_area, buf);
 
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).show_author(false).render(post_area, buf);
             }
             Record::Mention(post_uri) => {
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).render(area, buf);
             }
             Record::Quote(post_uri) => {
-                let post = post_manager!().at(&post_uri).unwrap();
+                let post = post_manager!().at(post_uri).unwrap();
                 PostWidget::new(post).render(area, buf);
             }
             Record::Follow => {
                 Line::from(vec![
                     Span::styled(&self.notif.author.basic.name, Color::Cyan),
-                    Span::styled(
-                        if self.focused { "(A)" } else { "" },
-                        Color::DarkGray,
-                    ),
+                    Span::styled(author_hint.clone(), Color::DarkGray),
                     Span::styled(" followed", Color::Green),
                     Span::styled(" you", Color::Gray),
                 ])
                 .render(area, buf);
             }
+            Record::Unsupported(u) => {
+                Line::from(vec![
+                    Span::styled(&self.notif.author.basic.name, Color::Cyan),
+                    Span::styled(author_hint.clone(), Color::DarkGray),
+                    Span::styled(format!(" {}", u.placeholder()), Color::Gray),
+                ])
+                .render(area, buf);
+            }
         }
     }
 }
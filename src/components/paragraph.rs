@@ -52,6 +52,50 @@ impl<'a> Paragraph<'a> {
 
         return break_lines(&self.text.lines, width).len() as u16;
     }
+
+    // Line index and byte offset in that line of the character rendered at
+    // (x, y), without considering block or scroll
+    pub fn offset_at(
+        &self,
+        width: u16,
+        x: u16,
+        y: u16,
+    ) -> Option<(usize, usize)> {
+        let mut row = 0;
+        for (i, line) in self.text.lines.iter().enumerate() {
+            let line = vec![line.clone()];
+            let rows = if self.wrap {
+                break_lines(&line, width)
+            } else {
+                line.clone()
+            };
+            if (y as usize) >= row + rows.len() {
+                row += rows.len();
+                continue;
+            }
+
+            let skipped = rows[..y as usize - row]
+                .iter()
+                .map(|l| line_to_string(l).len())
+                .sum::<usize>();
+            let mut offset = 0;
+            let mut left = 0;
+            let graphemes = rows[y as usize - row]
+                .spans
+                .iter()
+                .flat_map(|s| s.styled_graphemes(Style::default()));
+            for g in graphemes {
+                let right = left + g.symbol.width_cjk();
+                if (x as usize) < right {
+                    return Some((i, skipped + offset));
+                }
+                left = right;
+                offset += g.symbol.len();
+            }
+            return None;
+        }
+        return None;
+    }
 }
 
 impl<'a> Widget for Paragraph<'a> {
@@ -156,6 +200,21 @@ fn break_lines<'a>(lines: &'a Vec<Line<'a>>, width: u16) -> Vec<Line<'a>> {
 mod test {
     use super::*;
 
+    #[test]
+    fn offset_at() {
+        let paragraph = Paragraph::new(vec![
+            Line::from("abc def"),
+            Line::from(vec![Span::raw("日本"), Span::raw("語")]),
+        ]);
+        assert_eq!(paragraph.offset_at(4, 0, 0), Some((0, 0)));
+        assert_eq!(paragraph.offset_at(4, 1, 1), Some((0, 5)));
+        assert_eq!(paragraph.offset_at(4, 3, 1), None);
+        assert_eq!(paragraph.offset_at(4, 1, 2), Some((1, 0)));
+        assert_eq!(paragraph.offset_at(4, 2, 2), Some((1, 3)));
+        assert_eq!(paragraph.offset_at(4, 0, 3), Some((1, 6)));
+        assert_eq!(paragraph.offset_at(4, 0, 4), None);
+    }
+
     #[test]
     fn no_breaking_line() {
         let lines = vec![Line::from("abc")];
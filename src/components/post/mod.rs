@@ -1,11 +1,15 @@
+#[allow(
+    clippy::regex_creation_in_loops,
+    reason = "the regexes are compiled once, in OnceLock::get_or_init"
+)]
+#[allow(
+    clippy::unnecessary_map_or,
+    reason = "the facet parser is kept as it was written, to be cleaned up on \
+              its own"
+)]
 pub mod facets;
 pub mod post_widget;
 
-use std::{
-    ops::Range,
-    process::{Command, Stdio},
-};
-
 use atrium_api::{
     app::bsky::{
         feed::{defs::PostView, post},
@@ -13,38 +17,65 @@ use atrium_api::{
     },
     types::{
         string::{Cid, Did},
-        TryFromUnknown, Union,
+        Union,
     },
 };
-use bsky_sdk::BskyAgent;
 use chrono::{DateTime, Local};
-use ratatui::crossterm::event::{self, Event, KeyCode};
+use std::{ops::Range, process::Stdio};
 
 use crate::{
-    app::{AppEvent, EventReceiver},
+    app::{ActionReceiver, AppEvent},
+    client::Client,
     columns::{
         composer_view::ComposerView,
-        facet_modal::{FacetModal, FacetModalItem, Link, Mention},
-        post_likes::PostLikes,
+        facet_modal::{FacetModal, FacetModalItem},
+        interactions::{Interactions, Tab},
         profile_page::ProfilePage,
+        raw_view::RawView,
         Column,
     },
     components::{
-        actor::ActorBasic, composer, embed::Embed, list::ListState,
-        post_manager,
+        actor::ActorBasic,
+        composer,
+        embed::Embed,
+        list::ListState,
+        post_manager::Toggle,
+        unsupported::{from_unknown, Unsupported},
     },
-    post_manager_tx,
+    config,
+    keymap::Action,
+    moderation, post_manager,
 };
 
 #[derive(Clone)]
 pub struct LikeRepostView {
     pub count: u32,
+    // record of the user, as known by the AppView
     pub uri: Option<String>,
+    // toggled by the user but not settled yet
+    pub wanted: Option<bool>,
 }
 
 impl LikeRepostView {
     fn new(count: Option<i64>, uri: Option<String>) -> LikeRepostView {
-        LikeRepostView { count: count.unwrap_or(0) as u32, uri }
+        LikeRepostView { count: count.unwrap_or(0) as u32, uri, wanted: None }
+    }
+
+    pub fn is_on(&self) -> bool {
+        return self.wanted.unwrap_or(self.uri.is_some());
+    }
+
+    pub fn is_pending(&self) -> bool {
+        return self.wanted.is_some();
+    }
+
+    // count including the pending change
+    pub fn shown_count(&self) -> u32 {
+        match (self.is_on(), self.uri.is_some()) {
+            (true, false) => return self.count + 1,
+            (false, true) => return self.count.saturating_sub(1),
+            _ => return self.count,
+        }
     }
 }
 
@@ -88,21 +119,29 @@ pub struct Post {
     pub embed: Option<Embed>,
     pub labels: Vec<String>,
     pub facets: Vec<Facet>,
+    pub langs: Vec<String>,
+    // hashtags in the text and tags of the record, without #
+    pub tags: Vec<String>,
+    // no longer returned by the AppView
+    pub deleted: bool,
 }
 
 impl Post {
-    pub fn from(view: &PostView) -> Post {
+    pub fn try_from(view: &PostView) -> Result<Post, String> {
         let author = &view.author;
 
-        let Ok(record) =
-            post::RecordData::try_from_unknown(view.record.clone())
-        else {
-            panic!("Invalid record type");
-        };
+        let record =
+            from_unknown::<post::RecordData>(&view.record).map_err(|e| {
+                format!("Invalid record of post {}: {}", view.uri, e)
+            })?;
 
         let created_at = {
             let created_at = record.created_at.as_str();
-            DateTime::parse_from_rfc3339(created_at).unwrap().into()
+            DateTime::parse_from_rfc3339(created_at)
+                .map_err(|e| {
+                    format!("Invalid date of post {}: {}", view.uri, e)
+                })?
+                .into()
         };
 
         // let text = record.text.replace("\t", "    ");
@@ -136,6 +175,13 @@ impl Post {
 
         let embed = view.embed.as_ref().map(Embed::from);
 
+        let langs = record
+            .langs
+            .iter()
+            .flatten()
+            .map(|lang| lang.as_ref().as_str().to_string())
+            .collect();
+
         let labels = view
             .labels
             .as_ref()
@@ -144,6 +190,20 @@ impl Post {
             .map(|label| label.val.clone())
             .collect();
 
+        let tags = record
+            .facets
+            .iter()
+            .flatten()
+            .flat_map(|facet| &facet.features)
+            .filter_map(|feature| match feature {
+                Union::Refs(MainFeaturesItem::Tag(tag)) => {
+                    Some(tag.tag.clone())
+                }
+                _ => None,
+            })
+            .chain(record.tags.iter().flatten().cloned())
+            .collect();
+
         let mut facets = record
             .facets
             .unwrap_or_default()
@@ -171,9 +231,9 @@ impl Post {
             })
             .collect::<Vec<_>>();
 
-        facets.sort_by(|l, r| l.range.start.cmp(&r.range.start));
+        facets.sort_by_key(|l| l.range.start);
 
-        return Post {
+        return Ok(Post {
             uri: view.uri.clone(),
             cid: view.cid.clone(),
             author,
@@ -187,88 +247,37 @@ impl Post {
             embed,
             labels,
             facets,
-        };
+            langs,
+            tags,
+            deleted: false,
+        });
+    }
+
+    // Parts of the post that could not be read
+    pub fn unsupported(&self) -> Vec<&Unsupported> {
+        return self.embed.iter().flat_map(Embed::unsupported).collect();
     }
 }
 
-impl EventReceiver for &Post {
-    async fn handle_events(
-        self,
-        event: event::Event,
-        agent: BskyAgent,
-    ) -> AppEvent {
-        let Event::Key(key) = event else {
-            return AppEvent::None;
-        };
+impl ActionReceiver for &Post {
+    async fn handle_action(self, action: Action, agent: Client) -> AppEvent {
+        match action {
+            Action::LikeToggle => {
+                post_manager!().toggle(Toggle::Like, &self.uri);
+                return AppEvent::None;
+            }
 
-        match key.code {
-            KeyCode::Char(' ') => {
-                if self.like_view.uri.is_some() {
-                    post_manager_tx!()
-                        .send(post_manager::RequestMsg::UnlikePost(
-                            post_manager::DeleteRecordData {
-                                post_uri: self.uri.clone(),
-                                record_uri: self.like_view.uri.clone().unwrap(),
-                            },
-                        ))
-                        .unwrap_or_else(|_| {
-                            log::error!(
-                                "Cannot send message to worker unliking post"
-                            );
-                        });
-                } else {
-                    post_manager_tx!()
-                        .send(post_manager::RequestMsg::LikePost(
-                            post_manager::CreateRecordData {
-                                post_uri: self.uri.clone(),
-                                post_cid: self.cid.clone(),
-                            },
-                        ))
-                        .unwrap_or_else(|_| {
-                            log::error!(
-                                "Cannot send message to worker unliking post"
-                            );
-                        });
-                }
+            Action::Repost => {
+                post_manager!().toggle(Toggle::Repost, &self.uri);
                 return AppEvent::None;
             }
 
-            KeyCode::Char('o') => {
-                if self.repost_view.uri.is_some() {
-                    post_manager_tx!()
-                        .send(post_manager::RequestMsg::UnrepostPost(
-                            post_manager::DeleteRecordData {
-                                post_uri: self.uri.clone(),
-                                record_uri: self
-                                    .repost_view
-                                    .uri
-                                    .clone()
-                                    .unwrap(),
-                            },
-                        ))
-                        .unwrap_or_else(|_| {
-                            log::error!(
-                                "Cannot send message to worker repost post"
-                            );
-                        });
-                } else {
-                    post_manager_tx!()
-                        .send(post_manager::RequestMsg::RepostPost(
-                            post_manager::CreateRecordData {
-                                post_uri: self.uri.clone(),
-                                post_cid: self.cid.clone(),
-                            },
-                        ))
-                        .unwrap_or_else(|_| {
-                            log::error!(
-                                "Cannot send message to worker unrepost post"
-                            );
-                        });
-                }
+            Action::Reveal => {
+                moderation!().reveal(&self.uri);
                 return AppEvent::None;
             }
 
-            KeyCode::Char('u') => {
+            Action::Reply => {
                 let root = self.reply_to.clone().map_or(
                     PostRef { uri: self.uri.clone(), cid: self.cid.clone() },
                     |reply| reply.root.clone(),
@@ -280,26 +289,26 @@ impl EventReceiver for &Post {
                         cid: self.cid.clone(),
                     },
                 };
-                return AppEvent::ColumnNewLayer(Column::Composer(
+                return AppEvent::ColumnNewLayer(Column::Composer(Box::new(
                     ComposerView::new(
                         Some(reply_to),
                         composer::embed::Embed::None,
                     ),
-                ));
+                )));
             }
 
-            KeyCode::Char('i') => {
+            Action::Quote => {
                 let post_ref =
                     PostRef { uri: self.uri.clone(), cid: self.cid.clone() };
-                return AppEvent::ColumnNewLayer(Column::Composer(
+                return AppEvent::ColumnNewLayer(Column::Composer(Box::new(
                     ComposerView::new(
                         None,
                         composer::embed::Embed::Record(post_ref),
                     ),
-                ));
+                )));
             }
 
-            KeyCode::Char('p') => {
+            Action::OpenInBrowser => {
                 let post_uri = self.uri.split('/').collect::<Vec<_>>();
                 let author = post_uri[2];
                 let post_id = post_uri[4];
@@ -307,7 +316,7 @@ impl EventReceiver for &Post {
                     "https://bsky.app/profile/{}/post/{}",
                     author, post_id
                 );
-                if let Err(e) = Command::new("xdg-open")
+                if let Err(e) = config::command(&config!().media.browser)
                     .arg(url)
                     .stdout(Stdio::null())
                     .stderr(Stdio::null())
@@ -318,52 +327,45 @@ impl EventReceiver for &Post {
                 return AppEvent::None;
             }
 
-            KeyCode::Char('a') => {
-                let me = &agent.get_session().await.unwrap().did;
-                let profile =
-                    ProfilePage::from_did(self.author.did.clone(), me, agent);
-                return AppEvent::ColumnNewLayer(Column::ProfilePage(profile));
+            Action::AuthorProfile => {
+                return ProfilePage::open(self.author.did.clone(), agent).await;
             }
 
-            KeyCode::Char('m') => {
-                if self.embed.is_some() {
-                    self.embed.as_ref().unwrap().open_media();
+            Action::OpenMedia => {
+                if let Some(embed) = &self.embed {
+                    embed.open_media();
                 }
                 return AppEvent::None;
             }
 
-            KeyCode::Char('f') => {
+            Action::Facets => {
                 let links = self
                     .facets
                     .iter()
-                    .filter_map(|facet| match &facet.r#type {
-                        FacetType::Link(url) => {
-                            let text =
-                                self.text[facet.range.clone()].to_string();
-                            Some(FacetModalItem::Link(Link {
-                                text,
-                                url: url.clone(),
-                            }))
-                        }
-                        FacetType::Mention(m) => {
-                            let text =
-                                self.text[facet.range.clone()].to_string();
-                            Some(FacetModalItem::Mention(Mention {
-                                text,
-                                did: m.clone(),
-                            }))
-                        }
-                        _ => None,
-                    })
+                    .filter_map(|f| FacetModalItem::from_facet(f, &self.text))
                     .collect::<Vec<_>>();
                 return AppEvent::ColumnNewLayer(Column::FacetModal(
                     FacetModal { links, state: ListState::new(Some(0)) },
                 ));
             }
 
-            KeyCode::Char('F') => {
-                let post_likes = PostLikes::new(agent, self.uri.clone());
-                return AppEvent::ColumnNewLayer(Column::PostLikes(post_likes));
+            Action::Likes | Action::Reposts | Action::Quotes => {
+                let tab = match action {
+                    Action::Likes => Tab::Likes,
+                    Action::Reposts => Tab::Reposts,
+                    _ => Tab::Quotes,
+                };
+                let interactions =
+                    Interactions::new(agent, self.uri.clone(), tab);
+                return AppEvent::ColumnNewLayer(Column::Interactions(
+                    interactions,
+                ));
+            }
+
+            Action::ViewRaw => {
+                let unsupported =
+                    self.unsupported().into_iter().cloned().collect::<Vec<_>>();
+                return RawView::open(&unsupported);
             }
 
             _ => return AppEvent::None,
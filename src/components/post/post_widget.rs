@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
@@ -6,24 +8,32 @@ use ratatui::{
     widgets::{Block, Widget},
 };
 
-use crate::components::{
-    actor::ActorBasicWidget,
-    embed::embed_widget::EmbedWidget,
-    paragraph::Paragraph,
-    post::{FacetType, Post},
+use crate::{
+    components::{
+        actor::ActorBasicWidget,
+        embed::embed_widget::EmbedWidget,
+        paragraph::Paragraph,
+        post::{Facet, FacetType, LikeRepostView, Post},
+    },
+    keymap,
+    keymap::{key_hint, Action, Context},
+    moderation, post_manager,
 };
 
 pub struct PostWidget {
-    post: Post,
+    post: Arc<Post>,
     style: Style,
     is_selected: bool,
     has_border: bool,
     show_author: bool,
+    // drawn collapsed with the reason instead of the text and embed
+    muted: Option<String>,
 }
 
 impl PostWidget {
-    pub fn new(post: Post) -> Self {
+    pub fn new(post: Arc<Post>) -> Self {
         PostWidget {
+            muted: moderation!().reason(&post),
             post,
             style: Style::default(),
             is_selected: false,
@@ -32,7 +42,7 @@ impl PostWidget {
         }
     }
 
-    pub fn is_selected(mut self, selected: bool) -> Self {
+    pub fn selected(mut self, selected: bool) -> Self {
         self.is_selected = selected;
         self.style = if self.is_selected {
             Style::default().bg(Color::Rgb(45, 50, 55))
@@ -54,16 +64,50 @@ impl PostWidget {
 
     pub fn line_count(&self, width: u16) -> u16 {
         let width = width - self.has_border as u16 * 2;
+        if self.muted.is_some() {
+            return self.show_author as u16
+                + 1 // date
+                + 1 // reason
+                + self.has_border as u16 * 2;
+        }
         self.show_author as u16
             + 1 // date
-            + self.body_paragraph().line_count(width) as u16
+            + self.body_paragraph().line_count(width)
             + self.post.labels.len() as u16
             + 1 // stats
-            + self.post.embed.as_ref().map(|e| EmbedWidget::new(e.clone(), false).line_count(width) as u16).unwrap_or(0)
+            + self.post.embed.as_ref().map(|e| EmbedWidget::new(e.clone(), false).line_count(width)).unwrap_or(0)
             + self.has_border as u16 * 2
     }
 
-    fn body_paragraph(&self) -> Paragraph {
+    // Facet rendered at (x, y) relative to the widget
+    pub fn facet_at(&self, width: u16, x: u16, y: u16) -> Option<Facet> {
+        if self.muted.is_some() {
+            return None;
+        }
+        let (width, x, y) = if self.has_border {
+            (width.checked_sub(2)?, x.checked_sub(1)?, y.checked_sub(1)?)
+        } else {
+            (width, x, y)
+        };
+        let y = y.checked_sub(self.show_author as u16 + 1)?;
+        let (line, offset) = self.body_paragraph().offset_at(width, x, y)?;
+        let offset = self
+            .post
+            .text
+            .split('\n')
+            .take(line)
+            .map(|l| l.len() + 1)
+            .sum::<usize>()
+            + offset;
+        return self
+            .post
+            .facets
+            .iter()
+            .find(|f| f.range.contains(&offset))
+            .cloned();
+    }
+
+    fn body_paragraph(&self) -> Paragraph<'_> {
         let mut last_segment = self.post.text.as_str();
         let mut last_offset = 0;
         let mut lines = vec![Line::from("")];
@@ -108,6 +152,27 @@ impl PostWidget {
     }
 }
 
+// Time since the post was made, e.g. "3h"
+fn relative_date(post: &Post) -> Line<'static> {
+    let delta_time = crate::clock::now() - post.created_at;
+    let weeks = delta_time.num_weeks();
+    let days = delta_time.num_days();
+    let hours = delta_time.num_hours();
+    let mins = delta_time.num_minutes();
+    let date = if weeks > 0 {
+        format!("{}wk", weeks)
+    } else if days > 0 {
+        format!("{}d", days)
+    } else if hours > 0 {
+        format!("{}h", hours)
+    } else if mins > 0 {
+        format!("{}m", mins)
+    } else {
+        "now".to_string()
+    };
+    return Line::from(date).style(Color::DarkGray);
+}
+
 impl Widget for PostWidget {
     fn render(
         self,
@@ -129,19 +194,41 @@ impl Widget for PostWidget {
         };
         let post = &self.post;
 
+        if let Some(reason) = &self.muted {
+            let [author_area, datetime_area, reason_area] = Layout::vertical([
+                Constraint::Length(self.show_author as u16),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(area);
+            if self.show_author {
+                ActorBasicWidget::new(&post.author).render(author_area, buf);
+            }
+            relative_date(post).render(datetime_area, buf);
+            post_manager!().mark_visible(&post.uri);
+            let reveal = match keymap!().hint(Context::Post, Action::Reveal) {
+                Some(keys) => format!("{} (press {} to reveal)", reason, keys),
+                None => reason.clone(),
+            };
+            Line::from(reveal)
+                .style(Style::default().fg(Color::DarkGray).italic())
+                .render(reason_area, buf);
+            return;
+        }
+
         let text = self.body_paragraph();
         let embed = self
             .post
             .embed
             .clone()
-            .map(|e| EmbedWidget::new(e.into(), self.is_selected));
+            .map(|e| EmbedWidget::new(e, self.is_selected));
         let labels = &self.post.labels;
 
         let [author_area, datetime_area, text_area, labels_area, embed_area, stats_area] =
             Layout::vertical([
                 Constraint::Length(self.show_author as u16),
                 Constraint::Length(1),
-                Constraint::Length(text.line_count(area.width) as u16),
+                Constraint::Length(text.line_count(area.width)),
                 Constraint::Length(labels.len() as u16),
                 Constraint::Length(
                     embed
@@ -157,30 +244,15 @@ impl Widget for PostWidget {
             ActorBasicWidget::new(&post.author).render(author_area, buf);
         }
 
-        let delta_time = chrono::Local::now() - post.created_at;
-        let weeks = delta_time.num_weeks();
-        let days = delta_time.num_days();
-        let hours = delta_time.num_hours();
-        let mins = delta_time.num_minutes();
-        if weeks > 0 {
-            Line::from(format!("{}wk", weeks,))
-                .style(Color::DarkGray)
-                .render(datetime_area, buf);
-        } else if days > 0 {
-            Line::from(format!("{}d", days,))
-                .style(Color::DarkGray)
-                .render(datetime_area, buf);
-        } else if hours > 0 {
-            Line::from(format!("{}h", hours))
-                .style(Color::DarkGray)
-                .render(datetime_area, buf);
-        } else if mins > 0 {
-            Line::from(format!("{}m", mins))
-                .style(Color::DarkGray)
+        relative_date(post).render(datetime_area, buf);
+
+        if post.deleted {
+            Line::from("deleted")
+                .style(Color::LightRed)
+                .right_aligned()
                 .render(datetime_area, buf);
-        } else {
-            Line::from("now").style(Color::DarkGray).render(datetime_area, buf);
         }
+        post_manager!().mark_visible(&post.uri);
 
         self.body_paragraph().render(text_area, buf);
 
@@ -206,58 +278,63 @@ impl Widget for PostWidget {
             .areas(stats_area);
 
         let stat_color = Color::Rgb(130, 130, 130);
+        // keys of the actions, shown on the selected post
+        let hint = |action| match self.is_selected {
+            true => key_hint(Context::Post, action)
+                .map(|h| format!(" {}", h))
+                .unwrap_or_default(),
+            false => String::new(),
+        };
 
-        Line::from(format!(
-            "💬{}{}",
-            post.reply,
-            if self.is_selected { " (u)" } else { "" }
-        ))
-        .style(stat_color)
-        .alignment(Alignment::Left)
-        .render(reply_area, buf);
+        Line::from(format!("💬{}{}", post.reply, hint(Action::Reply)))
+            .style(stat_color)
+            .alignment(Alignment::Left)
+            .render(reply_area, buf);
 
-        Line::from(format!(
-            "❝ {}{}",
-            post.quote,
-            if self.is_selected { " (i)" } else { "" }
-        ))
-        .style(stat_color)
-        .alignment(Alignment::Left)
-        .render(quote_area, buf);
+        Line::from(format!("❝ {}{}", post.quote, hint(Action::Quote)))
+            .style(stat_color)
+            .alignment(Alignment::Left)
+            .render(quote_area, buf);
+
+        // pending changes are shown before the AppView confirms them
+        let toggle_style = |view: &LikeRepostView| {
+            if view.is_pending() {
+                Color::Yellow
+            } else if view.is_on() {
+                Color::Green
+            } else {
+                stat_color
+            }
+        };
 
         Line::from(format!(
             "⭮ {}{}",
-            post.repost_view.count,
-            if self.is_selected { " (o)" } else { "" }
+            post.repost_view.shown_count(),
+            hint(Action::Repost)
         ))
-        .style(if post.repost_view.uri.is_some() {
-            Color::Green
-        } else {
-            stat_color
-        })
+        .style(toggle_style(&post.repost_view))
         .alignment(Alignment::Left)
         .render(repost_area, buf);
 
         Line::from(format!(
             "♡ {}{}",
-            post.like_view.count,
-            if self.is_selected { " (⎵)" } else { "" }
+            post.like_view.shown_count(),
+            hint(Action::LikeToggle)
         ))
-        .style(if post.like_view.uri.is_some() {
-            Color::Green
-        } else {
-            stat_color
-        })
+        .style(toggle_style(&post.like_view))
         .alignment(Alignment::Left)
         .render(like_area, buf);
 
-        if self.is_selected {
-            Line::from("🦋(p)")
+        let browser = key_hint(Context::Post, Action::OpenInBrowser);
+        if let (true, Some(browser)) = (self.is_selected, browser) {
+            Line::from(format!("🦋{}", browser))
                 .style(stat_color)
                 .alignment(Alignment::Left)
                 .render(bsky_area, buf);
         }
 
-        embed.map(|e| e.render(embed_area, buf));
+        if let Some(e) = embed {
+            e.render(embed_area, buf)
+        }
     }
 }
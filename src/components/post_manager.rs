@@ -1,61 +1,121 @@
 use std::{
-    collections::HashMap,
-    sync::{
-        mpsc::{self, Sender},
-        Arc,
-    },
+    collections::{HashMap, HashSet},
+    sync::Arc,
 };
 
-use atrium_api::types::string::Cid;
-use bsky_sdk::BskyAgent;
-
-use crate::components::post::Post;
+use atrium_api::{
+    app::bsky::feed::{get_posts, like, repost},
+    com::atproto::repo::strong_ref,
+    record::KnownRecord,
+    types::string::Datetime,
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
 
-pub struct DeleteRecordData {
-    pub post_uri: String,
-    pub record_uri: String,
-}
+use crate::{
+    client::Client,
+    components::post::{LikeRepostView, Post},
+    config, outbox,
+    outbox::{Op, Outbox},
+    redraw, workers,
+};
 
-pub struct CreateRecordData {
-    pub post_uri: String,
-    pub post_cid: Cid,
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Toggle {
+    Like,
+    Repost,
 }
 
+// Brings the record of a post in line with what the user last asked for
 pub enum RequestMsg {
-    LikePost(CreateRecordData),
-    UnlikePost(DeleteRecordData),
-    RepostPost(CreateRecordData),
-    UnrepostPost(DeleteRecordData),
-    Close,
+    Settle(Toggle, String),
 }
 
+// most uris accepted by getPosts
+const GET_POSTS_LIMIT: usize = 25;
+
+// Posts kept when none is referenced by open columns. Referenced posts are
+// never evicted, so the map can be larger.
+const CAPACITY: usize = 2000;
+
 pub struct PostManager {
-    posts: Arc<std::sync::Mutex<HashMap<String, Post>>>,
-    pub tx: Option<Sender<RequestMsg>>,
+    posts: Arc<std::sync::Mutex<Posts>>,
+    pub tx: Option<UnboundedSender<RequestMsg>>,
 }
 
-macro_rules! request_retry {
-    ($retry:expr, $request:expr) => {{
-        let mut count = 0;
-        loop {
-            let r = $request;
-            match r {
-                Ok(output) => break Some(output),
-                Err(_) => {
-                    count += 1;
-                    if count == $retry {
-                        break None;
-                    }
-                }
-            }
+#[derive(Default)]
+struct Posts {
+    map: HashMap<String, Entry>,
+    // bumped on every access, for least recently used eviction
+    clock: u64,
+    evicted: u64,
+    // posts referenced by open columns at the last eviction
+    referenced: usize,
+    // posts rendered since the last refresh
+    visible: HashSet<String>,
+    // records being created or deleted by the worker
+    in_flight: HashSet<(Toggle, String)>,
+}
+
+struct Entry {
+    post: Arc<Post>,
+    used: u64,
+}
+
+impl Posts {
+    fn insert(&mut self, mut post: Post) {
+        self.clock += 1;
+        if let Some(old) = self.map.get(&post.uri) {
+            keep_settling(&old.post, &mut post);
+        }
+        let entry = Entry { post: Arc::new(post), used: self.clock };
+        self.map.insert(entry.post.uri.clone(), entry);
+    }
+
+    // Copy on write, readers keep the post they got
+    fn get_mut(&mut self, key: &String) -> Option<&mut Post> {
+        return self.map.get_mut(key).map(|e| Arc::make_mut(&mut e.post));
+    }
+
+    fn view_mut(
+        &mut self,
+        toggle: Toggle,
+        key: &String,
+    ) -> Option<&mut LikeRepostView> {
+        let post = self.get_mut(key)?;
+        match toggle {
+            Toggle::Like => return Some(&mut post.like_view),
+            Toggle::Repost => return Some(&mut post.repost_view),
         }
-    }};
+    }
+
+    // Post liked or reposted by the record at `record`
+    fn find_record(&self, record: &String) -> Option<(Toggle, String)> {
+        return self.map.values().find_map(|e| {
+            if e.post.like_view.uri.as_ref() == Some(record) {
+                return Some((Toggle::Like, e.post.uri.clone()));
+            }
+            if e.post.repost_view.uri.as_ref() == Some(record) {
+                return Some((Toggle::Repost, e.post.uri.clone()));
+            }
+            return None;
+        });
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub posts: usize,
+    pub referenced: usize,
+    pub capacity: usize,
+    pub evicted: u64,
+    // bytes of post text, the bulk of a post
+    pub text_bytes: usize,
 }
 
 impl PostManager {
     pub fn new() -> PostManager {
         PostManager {
-            posts: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            posts: Arc::new(std::sync::Mutex::new(Posts::default())),
             tx: None,
         }
     }
@@ -63,144 +123,540 @@ impl PostManager {
     pub fn insert(&self, post: Post) {
         let posts = Arc::clone(&self.posts);
         let mut posts = posts.lock().unwrap();
-        posts.insert(post.uri.clone(), post);
+        posts.insert(post);
     }
 
     pub fn append(&self, new_posts: Vec<Post>) {
         let posts = Arc::clone(&self.posts);
         let mut posts = posts.lock().unwrap();
-        posts.extend(new_posts.into_iter().map(|p| (p.uri.clone(), p)));
+        new_posts.into_iter().for_each(|p| posts.insert(p));
     }
 
-    pub fn at(&self, key: &String) -> Option<Post> {
+    pub fn at(&self, key: &String) -> Option<Arc<Post>> {
         let posts = Arc::clone(&self.posts);
-        let posts = posts.lock().unwrap();
-        return posts.get(key).map(|p| p.to_owned());
+        let mut posts = posts.lock().unwrap();
+        posts.clock += 1;
+        let clock = posts.clock;
+        let entry = posts.map.get_mut(key)?;
+        entry.used = clock;
+        return Some(Arc::clone(&entry.post));
+    }
+
+    // Flips the like or repost shown right away. The worker creates or
+    // deletes the record, toggles made meanwhile are settled together.
+    pub fn toggle(&self, toggle: Toggle, uri: &String) {
+        let mut posts = self.posts.lock().unwrap();
+        let Some(view) = posts.view_mut(toggle, uri) else {
+            log::error!("Could not find post in post manager");
+            return;
+        };
+        let on = !view.is_on();
+        self.want(&mut posts, toggle, uri, on);
+    }
+
+    // Sends a like or repost that failed for good again. It was rolled back
+    // then, so the post shows it as wanted until the worker settles it.
+    // False if the write is not a like or repost of a post in the manager.
+    pub fn resend(&self, op: &Op) -> bool {
+        let mut posts = self.posts.lock().unwrap();
+        let (toggle, uri, on) = match op {
+            Op::Create(KnownRecord::AppBskyFeedLike(r)) => {
+                (Toggle::Like, r.subject.uri.clone(), true)
+            }
+            Op::Create(KnownRecord::AppBskyFeedRepost(r)) => {
+                (Toggle::Repost, r.subject.uri.clone(), true)
+            }
+            Op::Delete(record) => match posts.find_record(record) {
+                Some((toggle, uri)) => (toggle, uri, false),
+                None => return false,
+            },
+            Op::Create(_) => return false,
+        };
+        if posts.view_mut(toggle, &uri).is_none() {
+            return false;
+        }
+        self.want(&mut posts, toggle, &uri, on);
+        return true;
     }
 
-    pub fn spawn_worker(&mut self, agent: BskyAgent) {
+    fn want(&self, posts: &mut Posts, toggle: Toggle, uri: &String, on: bool) {
+        if let Some(view) = posts.view_mut(toggle, uri) {
+            view.wanted = Some(on);
+        }
+        if !posts.in_flight.insert((toggle, uri.clone())) {
+            return;
+        }
+        let sent = self
+            .tx
+            .as_ref()
+            .map(|tx| tx.send(RequestMsg::Settle(toggle, uri.clone())).is_ok());
+        if sent != Some(true) {
+            log::error!("Cannot send message to post manager worker");
+            posts.in_flight.remove(&(toggle, uri.clone()));
+            if let Some(view) = posts.view_mut(toggle, uri) {
+                view.wanted = None;
+            }
+        }
+    }
+
+    // Called when rendering, the post is refreshed with the next batch
+    pub fn mark_visible(&self, uri: &String) {
+        let mut posts = self.posts.lock().unwrap();
+        if !posts.visible.contains(uri) {
+            posts.visible.insert(uri.clone());
+        }
+    }
+
+    pub fn over_capacity(&self) -> bool {
+        return self.posts.lock().unwrap().map.len() > CAPACITY;
+    }
+
+    // Drops the least recently used posts over CAPACITY, keeping the ones in
+    // `referenced` and the ones still held outside the manager
+    pub fn evict(&self, referenced: &HashSet<String>) {
+        let mut posts = self.posts.lock().unwrap();
+        let mut unreferenced = posts
+            .map
+            .iter()
+            .filter(|(uri, e)| {
+                !referenced.contains(*uri) && Arc::strong_count(&e.post) == 1
+            })
+            .map(|(uri, e)| (e.used, uri.clone()))
+            .collect::<Vec<_>>();
+        let over = posts.map.len().saturating_sub(CAPACITY);
+        let over = over.min(unreferenced.len());
+        unreferenced.sort_unstable();
+        for (_, uri) in &unreferenced[..over] {
+            posts.map.remove(uri);
+        }
+        posts.evicted += over as u64;
+        posts.referenced = referenced.len();
+    }
+
+    pub fn stats(&self) -> Stats {
+        let posts = self.posts.lock().unwrap();
+        return Stats {
+            posts: posts.map.len(),
+            referenced: posts.referenced,
+            capacity: CAPACITY,
+            evicted: posts.evicted,
+            text_bytes: posts.map.values().map(|e| e.post.text.len()).sum(),
+        };
+    }
+
+    pub fn spawn_worker(&mut self, agent: Client) {
         let posts = Arc::clone(&self.posts);
-        let (tx, rx) = mpsc::channel();
-        self.tx = Some(tx);
-        tokio::spawn(async move {
+        workers!().spawn(async move {
             loop {
-                let Ok(msg) = rx.recv() else {
-                    continue;
-                };
-                match msg {
-                    RequestMsg::Close => return,
-
-                    RequestMsg::LikePost(data) => {
-                        let Some(output) = request_retry!(3, {
-                            agent.create_record(
-                                atrium_api::app::bsky::feed::like::RecordData {
-                                    created_at: atrium_api::types::string::Datetime::now(),
-                                    subject: atrium_api::com::atproto::repo::strong_ref::MainData {
-                                        cid: data.post_cid.clone(),
-                                        uri: data.post_uri.clone(),
-                                    }.into()
-                                }
-                            ).await
-                        }) else {
-                            log::error!(
-                                "Could not post create record liking post"
-                            );
-                            continue;
-                        };
-
-                        let mut posts = posts.lock().unwrap();
-                        let Some(post) = posts.get_mut(&data.post_uri) else {
-                            log::error!("Could not find post in post manager");
-                            continue;
-                        };
-                        post.like_view.uri = Some(output.uri.clone());
-                        post.like_view.count += 1;
-                        tokio::spawn(async {}); // black magic, removing this causes feed autoupdating to stop
-                    }
+                let refresh_rate = config!().feed.counts_refresh_rate;
+                tokio::time::sleep(tokio::time::Duration::from_secs(
+                    refresh_rate,
+                ))
+                .await;
+                refresh_visible(&posts, &agent).await;
+            }
+        });
 
-                    RequestMsg::UnlikePost(data) => {
-                        let Some(_) = request_retry!(3, {
-                            agent.delete_record(data.record_uri.clone()).await
-                        }) else {
-                            log::error!(
-                                "Could not post delete record unliking post"
-                            );
-                            continue;
-                        };
-
-                        let mut posts = posts.lock().unwrap();
-                        let Some(post) = posts.get_mut(&data.post_uri) else {
-                            log::error!("Could not find post in post manager");
-                            continue;
-                        };
-                        post.like_view.uri = None;
-                        post.like_view.count -= 1;
-                        tokio::spawn(async {});
+        let posts = Arc::clone(&self.posts);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        self.tx = Some(tx);
+        workers!().spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                // a write waiting in the outbox does not hold up others
+                let posts = Arc::clone(&posts);
+                workers!().spawn(async move {
+                    match msg {
+                        RequestMsg::Settle(toggle, uri) => {
+                            settle(&posts, &outbox!(), toggle, &uri).await;
+                            let mut posts = posts.lock().unwrap();
+                            posts.in_flight.remove(&(toggle, uri));
+                            redraw!();
+                        }
                     }
+                });
+            }
+        });
+    }
+}
+
+// Creates or deletes records through the outbox until the post matches what
+// was last wanted. A write that failed for good or was discarded rolls back
+// to the record the post has.
+async fn settle(
+    posts: &std::sync::Mutex<Posts>,
+    outbox: &Outbox,
+    toggle: Toggle,
+    uri: &String,
+) {
+    loop {
+        let (record, cid) = {
+            let mut posts = posts.lock().unwrap();
+            let Some(cid) = posts.map.get(uri).map(|e| e.post.cid.clone())
+            else {
+                log::error!("Could not find post in post manager");
+                return;
+            };
+            let view = posts.view_mut(toggle, uri).unwrap();
+            match view.wanted {
+                Some(wanted) if wanted != view.uri.is_some() => {
+                    (view.uri.clone(), cid)
+                }
+                _ => {
+                    view.wanted = None;
+                    return;
+                }
+            }
+        };
 
-                    RequestMsg::RepostPost(data) => {
-                        let Some(output) = request_retry!(3, {
-                            agent.create_record(
-                                atrium_api::app::bsky::feed::repost::RecordData {
-                                    created_at: atrium_api::types::string::Datetime::now(),
-                                    subject: atrium_api::com::atproto::repo::strong_ref::MainData {
-                                        cid: data.post_cid.clone(),
-                                        uri: data.post_uri.clone(),
-                                    }.into()
-                                }
-                            ).await
-                        }) else {
-                            log::error!(
-                                "Could not post create record reposting post"
-                            );
-                            continue;
-                        };
-
-                        let mut posts = posts.lock().unwrap();
-                        let Some(post) = posts.get_mut(&data.post_uri) else {
-                            log::error!("Could not find post in post manager");
-                            continue;
-                        };
-                        post.repost_view.uri = Some(output.uri.clone());
-                        post.repost_view.count += 1;
-                        tokio::spawn(async {});
+        let op = match &record {
+            Some(record) => Op::Delete(record.clone()),
+            None => {
+                let subject: strong_ref::Main =
+                    strong_ref::MainData { cid, uri: uri.clone() }.into();
+                let created_at = Datetime::now();
+                Op::Create(match toggle {
+                    Toggle::Like => {
+                        like::RecordData { created_at, subject }.into()
                     }
+                    Toggle::Repost => {
+                        repost::RecordData { created_at, subject }.into()
+                    }
+                })
+            }
+        };
+        let result = outbox.submit(op).await;
 
-                    RequestMsg::UnrepostPost(data) => {
-                        let Some(_) = request_retry!(3, {
-                            agent.delete_record(data.record_uri.clone()).await
-                        }) else {
-                            log::error!(
-                                "Could not post delete record unreposting post"
-                            );
-                            continue;
-                        };
-
-                        let mut posts = posts.lock().unwrap();
-                        let Some(post) = posts.get_mut(&data.post_uri) else {
-                            log::error!("Could not find post in post manager");
-                            continue;
-                        };
-                        post.repost_view.uri = None;
-                        post.repost_view.count -= 1;
-                        tokio::spawn(async {});
+        let mut posts = posts.lock().unwrap();
+        let Some(view) = posts.view_mut(toggle, uri) else {
+            return;
+        };
+        match result {
+            Ok(Ok(Some(record))) => {
+                view.uri = Some(record);
+                view.count += 1;
+            }
+            Ok(Ok(None)) => {
+                view.uri = None;
+                view.count = view.count.saturating_sub(1);
+            }
+            Ok(Err(_)) | Err(_) => {
+                log::error!(
+                    "Could not {} post, rolled back",
+                    match (toggle, record.is_some()) {
+                        (Toggle::Like, false) => "like",
+                        (Toggle::Like, true) => "unlike",
+                        (Toggle::Repost, false) => "repost",
+                        (Toggle::Repost, true) => "unrepost",
                     }
+                );
+                view.wanted = None;
+                return;
+            }
+        }
+        drop(posts);
+        redraw!();
+    }
+}
+
+// Fetches the posts rendered since the last call again, for counts and
+// viewer state changed by others. Posts the AppView does not return any more
+// are marked deleted.
+async fn refresh_visible(posts: &std::sync::Mutex<Posts>, agent: &Client) {
+    let uris = std::mem::take(&mut posts.lock().unwrap().visible)
+        .into_iter()
+        .collect::<Vec<_>>();
+    let mut updated = false;
+    for uris in uris.chunks(GET_POSTS_LIMIT) {
+        let output = agent
+            .get_posts(get_posts::ParametersData { uris: uris.to_vec() }.into())
+            .await;
+        let views = match output {
+            Ok(output) => output.data.posts,
+            // the other batches are still refreshed, these ones are the next
+            // time they are drawn
+            Err(e) => {
+                log::warn!("Cannot refresh posts: {}", e);
+                continue;
+            }
+        };
+
+        let mut posts = posts.lock().unwrap();
+        for view in &views {
+            let post = match Post::try_from(view) {
+                Ok(post) => post,
+                Err(e) => {
+                    log::error!("{}", e);
+                    continue;
                 }
+            };
+            if let Some(entry) = posts.map.get_mut(&post.uri) {
+                let mut post = post;
+                keep_settling(&entry.post, &mut post);
+                entry.post = Arc::new(post);
+                updated = true;
             }
-        });
+        }
+        for uri in uris {
+            if views.iter().any(|v| v.uri == *uri) {
+                continue;
+            }
+            if let Some(post) = posts.get_mut(uri) {
+                if !post.deleted {
+                    post.deleted = true;
+                    updated = true;
+                }
+            }
+        }
+    }
+    if updated {
+        redraw!();
+    }
+}
+
+// The worker owns records being settled, a fresh copy of the post keeps the
+// like or repost it is settling
+fn keep_settling(old: &Post, post: &mut Post) {
+    if old.like_view.wanted.is_some() {
+        post.like_view = old.like_view.clone();
+    }
+    if old.repost_view.wanted.is_some() {
+        post.repost_view = old.repost_view.clone();
     }
 }
 
 #[macro_export]
 macro_rules! post_manager {
     () => {
-        crate::POST_MANAGER.read().unwrap()
+        $crate::POST_MANAGER.read().unwrap()
     };
 }
 
-#[macro_export]
-macro_rules! post_manager_tx {
-    () => {
-        crate::POST_MANAGER.read().unwrap().tx.as_ref().unwrap()
-    };
+#[cfg(test)]
+mod test {
+    use atrium_api::app::bsky::feed::get_timeline;
+
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    async fn timeline_post(agent: &Client) -> Post {
+        let timeline = agent
+            .get_timeline(
+                get_timeline::ParametersData {
+                    algorithm: None,
+                    cursor: None,
+                    limit: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap();
+        return Post::try_from(&timeline.feed[0].post).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refreshes_visible_posts() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let count = post.like_view.count;
+        let mut stale = post.clone();
+        stale.like_view.count = count + 100;
+        let mut gone = post.clone();
+        gone.uri = "at://did:plc:test/app.bsky.feed.post/gone".to_string();
+
+        let manager = PostManager::new();
+        manager.append(vec![stale, gone.clone()]);
+        manager.mark_visible(&post.uri);
+        manager.mark_visible(&gone.uri);
+        refresh_visible(&manager.posts, &agent).await;
+
+        let refreshed = manager.at(&post.uri).unwrap();
+        assert_eq!(refreshed.like_view.count, count);
+        assert!(!refreshed.deleted);
+        assert!(manager.at(&gone.uri).unwrap().deleted);
+    }
+
+    #[tokio::test]
+    async fn toggles_settle_once() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let count = post.like_view.count;
+        let uri = post.uri.clone();
+
+        let mut manager = PostManager::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.tx = Some(tx);
+        manager.insert(post);
+
+        manager.toggle(Toggle::Like, &uri);
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(view.is_pending());
+        assert_eq!(view.shown_count(), count + 1);
+        // toggles made before the worker runs need no more requests
+        manager.toggle(Toggle::Like, &uri);
+        manager.toggle(Toggle::Like, &uri);
+        assert!(matches!(rx.try_recv(), Ok(RequestMsg::Settle(..))));
+        assert!(rx.try_recv().is_err());
+
+        let outbox = Outbox::new();
+        tokio::select! {
+            _ = settle(&manager.posts, &outbox, Toggle::Like, &uri) => {}
+            _ = outbox.run(agent) => {}
+        }
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(!view.is_pending());
+        assert!(view.uri.is_some());
+        assert_eq!(view.count, count + 1);
+    }
+
+    #[tokio::test]
+    async fn insert_keeps_pending_toggle() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let count = post.like_view.count;
+        let uri = post.uri.clone();
+
+        let mut manager = PostManager::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        manager.tx = Some(tx);
+        manager.insert(post.clone());
+        manager.toggle(Toggle::Like, &uri);
+
+        // fetched again by a feed poll before the like is created
+        manager.insert(post);
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(view.is_pending());
+        assert_eq!(view.shown_count(), count + 1);
+
+        let outbox = Outbox::new();
+        tokio::select! {
+            _ = settle(&manager.posts, &outbox, Toggle::Like, &uri) => {}
+            _ = outbox.run(agent) => {}
+        }
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(!view.is_pending());
+        assert!(view.uri.is_some());
+        assert_eq!(view.count, count + 1);
+    }
+
+    #[tokio::test]
+    async fn failed_toggle_rolls_back() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let count = post.repost_view.count;
+        let uri = post.uri.clone();
+
+        let mut manager = PostManager::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        manager.tx = Some(tx);
+        manager.insert(post);
+        manager.toggle(Toggle::Repost, &uri);
+
+        // given up on by the user
+        let outbox = Outbox::new();
+        let discard = async {
+            while outbox.items().is_empty() {
+                tokio::task::yield_now().await;
+            }
+            outbox.discard(outbox.items()[0].id);
+        };
+        tokio::join!(
+            settle(&manager.posts, &outbox, Toggle::Repost, &uri),
+            discard
+        );
+        let view = manager.at(&uri).unwrap().repost_view.clone();
+        assert!(!view.is_pending());
+        assert!(view.uri.is_none());
+        assert_eq!(view.shown_count(), count);
+    }
+
+    #[tokio::test]
+    async fn resends_failed_toggle() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let count = post.like_view.count;
+        let uri = post.uri.clone();
+
+        let mut manager = PostManager::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.tx = Some(tx);
+        manager.insert(post.clone());
+
+        // failed for good and rolled back
+        let like = Op::Create(
+            like::RecordData {
+                created_at: Datetime::now(),
+                subject: strong_ref::MainData {
+                    cid: post.cid.clone(),
+                    uri: uri.clone(),
+                }
+                .into(),
+            }
+            .into(),
+        );
+        assert!(manager.resend(&like));
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(view.is_pending());
+        assert_eq!(view.shown_count(), count + 1);
+        assert!(matches!(rx.try_recv(), Ok(RequestMsg::Settle(..))));
+
+        let outbox = Outbox::new();
+        tokio::select! {
+            _ = settle(&manager.posts, &outbox, Toggle::Like, &uri) => {}
+            _ = outbox.run(agent.clone()) => {}
+        }
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert!(!view.is_pending());
+        assert_eq!(view.count, count + 1);
+        manager.posts.lock().unwrap().in_flight.clear();
+
+        let record = view.uri.clone().unwrap();
+        assert!(manager.resend(&Op::Delete(record)));
+        let view = manager.at(&uri).unwrap().like_view.clone();
+        assert_eq!(view.wanted, Some(false));
+        assert!(matches!(rx.try_recv(), Ok(RequestMsg::Settle(..))));
+
+        // not shown by any post, sent again by the outbox
+        let unknown = "at://did:plc:tskyme/app.bsky.feed.like/unknown";
+        assert!(!manager.resend(&Op::Delete(unknown.to_string())));
+    }
+
+    #[tokio::test]
+    async fn evicts_unreferenced_posts() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let post = timeline_post(&agent).await;
+        let uri = |i: usize| format!("at://did:plc:test/post/{}", i);
+
+        let manager = PostManager::new();
+        manager.append(
+            (0..CAPACITY + 10)
+                .map(|i| {
+                    let mut post = post.clone();
+                    post.uri = uri(i);
+                    post
+                })
+                .collect(),
+        );
+        assert!(manager.over_capacity());
+        // used recently but not referenced
+        manager.at(&uri(1)).unwrap();
+        // held outside the manager
+        let held = manager.at(&uri(2)).unwrap();
+
+        let referenced = [uri(0), uri(3)].into_iter().collect();
+        manager.evict(&referenced);
+        assert!(!manager.over_capacity());
+        assert!(manager.at(&uri(0)).is_some());
+        assert!(manager.at(&uri(1)).is_some());
+        assert!(manager.at(&uri(2)).is_some());
+        assert!(manager.at(&uri(3)).is_some());
+        // oldest unreferenced
+        assert!(manager.at(&uri(4)).is_none());
+        assert!(manager.at(&uri(CAPACITY + 9)).is_some());
+        drop(held);
+
+        let stats = manager.stats();
+        assert_eq!(stats.posts, CAPACITY);
+        assert_eq!(stats.referenced, 2);
+        assert_eq!(stats.evicted, 10);
+    }
 }
@@ -0,0 +1,36 @@
+use atrium_api::types::Unknown;
+use serde::{de::DeserializeOwned, Serialize};
+
+// Lexicon object tsky does not know how to show. Shown as a placeholder,
+// the JSON is kept so that it can still be read in a raw view.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Unsupported {
+    // what the object is part of, e.g. "embed"
+    pub kind: &'static str,
+    pub r#type: String,
+    pub json: String,
+}
+
+impl Unsupported {
+    pub fn new<T: Serialize>(
+        kind: &'static str,
+        r#type: &str,
+        data: &T,
+    ) -> Unsupported {
+        let json = serde_json::to_string_pretty(data)
+            .unwrap_or_else(|e| format!("Cannot serialize: {}", e));
+        Unsupported { kind, r#type: r#type.to_string(), json }
+    }
+
+    pub fn placeholder(&self) -> String {
+        return format!("[unsupported {}: {}]", self.kind, self.r#type);
+    }
+}
+
+// Reads an open record as a known type. TryFromUnknown panics when the data
+// does not match the type.
+pub fn from_unknown<T: DeserializeOwned>(value: &Unknown) -> Result<T, String> {
+    return serde_json::to_value(value)
+        .and_then(serde_json::from_value)
+        .map_err(|e| e.to_string());
+}
@@ -0,0 +1,449 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+use atrium_api::types::string::Language;
+use serde::Deserialize;
+
+use crate::{
+    columns::ColumnKind,
+    components::feed_filter::FeedFilter,
+    keymap::{Keymap, KeysConfig},
+};
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub layout: LayoutConfig,
+    pub feed: FeedConfig,
+    pub notifications: NotificationsConfig,
+    pub media: MediaConfig,
+    pub composer: ComposerConfig,
+    pub cache: CacheConfig,
+    pub keys: KeysConfig,
+    pub workspaces: Vec<WorkspaceConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    // default width of a column
+    pub width: u16,
+    pub columns: Vec<ColumnConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnConfig {
+    pub kind: ColumnKind,
+    pub width: Option<u16>,
+    // replaces feed.filter, only for feed columns
+    pub filter: Option<FeedFilter>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedConfig {
+    // seconds
+    pub refresh_rate: u64,
+    // seconds between refreshing counts of posts on screen
+    pub counts_refresh_rate: u64,
+    // posts hidden from every feed column
+    pub filter: FeedFilter,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    // seconds
+    pub refresh_rate: u64,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MediaConfig {
+    pub image_viewer: Vec<String>,
+    pub video_player: Vec<String>,
+    pub browser: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComposerConfig {
+    pub langs: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    // keep the timeline, threads and profiles on disk for offline reading
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    pub name: String,
+    pub columns: Vec<ColumnConfig>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig { width: 80, columns: Vec::new() }
+    }
+}
+
+// Kind, width and feed filter of a column opened on start
+pub type ColumnLayout = (ColumnKind, u16, Option<FeedFilter>);
+
+impl LayoutConfig {
+    // Columns shown side by side on start, a single feed if none is set
+    pub fn columns(&self) -> Vec<ColumnLayout> {
+        if self.columns.is_empty() {
+            return vec![(ColumnKind::Feed, self.width, None)];
+        }
+        self.columns_of(&self.columns)
+    }
+
+    fn columns_of(&self, columns: &[ColumnConfig]) -> Vec<ColumnLayout> {
+        columns
+            .iter()
+            .map(|c| (c.kind, c.width.unwrap_or(self.width), c.filter.clone()))
+            .collect()
+    }
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        FeedConfig {
+            refresh_rate: 1,
+            counts_refresh_rate: 30,
+            filter: FeedFilter::default(),
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig { refresh_rate: 10 }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig { enabled: true }
+    }
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        MediaConfig {
+            image_viewer: [
+                "feh",
+                "--output-dir",
+                "/tmp",
+                "--zoom",
+                "50%",
+                "--",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            video_player: vec!["vlc".to_string()],
+            browser: vec!["xdg-open".to_string()],
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Result<PathBuf, String> {
+        let config_home = match env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => {
+                let home = env::var("HOME")
+                    .map_err(|_| "Cannot get $HOME".to_string())?;
+                PathBuf::from(home).join(".config")
+            }
+        };
+        Ok(config_home.join("tsky").join("config.toml"))
+    }
+
+    // Returns the default config if the file does not exist
+    pub fn load() -> Result<Config, String> {
+        let path = Config::path()?;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = fs::read_to_string(&path).map_err(|e| {
+            format!("Cannot read config file {}: {}", path.display(), e)
+        })?;
+        let config = Config::parse(&text).map_err(|e| {
+            format!("Invalid config file {}: {}", path.display(), e)
+        })?;
+        Ok(config)
+    }
+
+    // Workspaces opened on start, a single "home" workspace of
+    // layout.columns if none is set
+    pub fn workspaces(&self) -> Vec<(String, Vec<ColumnLayout>)> {
+        if self.workspaces.is_empty() {
+            return vec![("home".to_string(), self.layout.columns())];
+        }
+        self.workspaces
+            .iter()
+            .map(|w| (w.name.clone(), self.layout.columns_of(&w.columns)))
+            .collect()
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(text).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.layout.width == 0 {
+            return Err("layout.width must be greater than 0".to_string());
+        }
+        if self.layout.columns.iter().any(|c| c.width == Some(0)) {
+            return Err(
+                "layout.columns widths must be greater than 0".to_string()
+            );
+        }
+        for w in &self.workspaces {
+            if w.columns.is_empty() {
+                return Err(format!("workspace `{}` has no columns", w.name));
+            }
+            if w.columns.iter().any(|c| c.width == Some(0)) {
+                return Err(format!(
+                    "workspace `{}` column widths must be greater than 0",
+                    w.name
+                ));
+            }
+        }
+        if self.feed.refresh_rate == 0 {
+            return Err("feed.refresh_rate must be greater than 0".to_string());
+        }
+        if self.notifications.refresh_rate == 0 {
+            return Err(
+                "notifications.refresh_rate must be greater than 0".to_string()
+            );
+        }
+        for (name, command) in [
+            ("media.image_viewer", &self.media.image_viewer),
+            ("media.video_player", &self.media.video_player),
+            ("media.browser", &self.media.browser),
+        ] {
+            if command.first().is_none_or(|c| c.trim().is_empty()) {
+                return Err(format!("{} must name a program", name));
+            }
+        }
+        let invalid_langs = |langs: &[String]| {
+            langs
+                .iter()
+                .filter(|lang| Language::new(lang.to_string()).is_err())
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let invalid = invalid_langs(&self.composer.langs);
+        if !invalid.is_empty() {
+            return Err(format!("composer.langs {:?} are invalid", invalid));
+        }
+        let invalid = invalid_langs(&self.feed.filter.langs);
+        if !invalid.is_empty() {
+            return Err(format!("feed.filter.langs {:?} are invalid", invalid));
+        }
+        let columns = self
+            .layout
+            .columns
+            .iter()
+            .chain(self.workspaces.iter().flat_map(|w| &w.columns));
+        for c in columns {
+            let Some(filter) = &c.filter else {
+                continue;
+            };
+            if c.kind != ColumnKind::Feed {
+                return Err(
+                    "filter can only be set on feed columns".to_string()
+                );
+            }
+            let invalid = invalid_langs(&filter.langs);
+            if !invalid.is_empty() {
+                return Err(format!("filter.langs {:?} are invalid", invalid));
+            }
+        }
+        Keymap::new(&self.keys)?;
+        Ok(())
+    }
+}
+
+// Program and leading arguments of a configured command, ready to have more
+// arguments appended
+pub fn command(command: &[String]) -> Command {
+    let mut c = Command::new(&command[0]);
+    c.args(&command[1..]);
+    c
+}
+
+#[macro_export]
+macro_rules! config {
+    () => {
+        $crate::CONFIG.read().unwrap()
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::feed_filter::Replies;
+
+    #[test]
+    fn empty_config_is_default() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.layout.width, 80);
+        assert_eq!(config.feed.refresh_rate, 1);
+        assert_eq!(config.notifications.refresh_rate, 10);
+        assert_eq!(config.media.browser, vec!["xdg-open"]);
+        assert!(config.composer.langs.is_empty());
+    }
+
+    #[test]
+    fn partial_config() {
+        let config = Config::parse(
+            r#"
+            [layout]
+            width = 120
+
+            [media]
+            image_viewer = ["imv"]
+
+            [composer]
+            langs = ["en", "ja"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.layout.width, 120);
+        assert_eq!(config.feed.refresh_rate, 1);
+        assert_eq!(config.media.image_viewer, vec!["imv"]);
+        assert_eq!(config.media.video_player, vec!["vlc"]);
+        assert_eq!(config.composer.langs, vec!["en", "ja"]);
+    }
+
+    #[test]
+    fn layout_columns() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.layout.columns(), vec![(ColumnKind::Feed, 80, None)]);
+
+        let config = Config::parse(
+            r#"
+            [layout]
+            width = 70
+            columns = [
+                { kind = "feed" },
+                { kind = "notifications", width = 50 },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.layout.columns(),
+            vec![
+                (ColumnKind::Feed, 70, None),
+                (ColumnKind::Notifications, 50, None)
+            ]
+        );
+        assert!(
+            Config::parse("[layout]\ncolumns = [{ kind = \"x\" }]").is_err()
+        );
+    }
+
+    #[test]
+    fn workspaces() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(
+            config.workspaces(),
+            vec![("home".to_string(), vec![(ColumnKind::Feed, 80, None)])]
+        );
+
+        let config = Config::parse(
+            r#"
+            [[workspaces]]
+            name = "home"
+            columns = [{ kind = "feed" }, { kind = "notifications" }]
+
+            [[workspaces]]
+            name = "me"
+            columns = [{ kind = "profile", width = 100 }]
+            "#,
+        )
+        .unwrap();
+        let workspaces = config.workspaces();
+        assert_eq!(workspaces.len(), 2);
+        assert_eq!(workspaces[0].1.len(), 2);
+        assert_eq!(
+            workspaces[1],
+            ("me".to_string(), vec![(ColumnKind::Profile, 100, None)])
+        );
+
+        let e = Config::parse("[[workspaces]]\nname = \"x\"\ncolumns = []")
+            .err()
+            .unwrap();
+        assert!(e.contains("workspace `x`"));
+    }
+
+    #[test]
+    fn feed_filters() {
+        let config = Config::parse(
+            r#"
+            [feed.filter]
+            hide_quotes = true
+
+            [layout]
+            columns = [
+                { kind = "feed", filter = { hide_reposts = true, replies = "none" } },
+                { kind = "feed" },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(config.feed.filter.hide_quotes);
+        let columns = config.layout.columns();
+        let filter = columns[0].2.as_ref().unwrap();
+        assert!(filter.hide_reposts);
+        assert_eq!(filter.replies, Some(Replies::None));
+        assert!(columns[1].2.is_none());
+
+        let e = Config::parse(
+            "[layout]\ncolumns = [{ kind = \"search\", filter = {} }]",
+        )
+        .err()
+        .unwrap();
+        assert!(e.contains("feed columns"));
+        let e = Config::parse("[feed.filter]\nhide_accounts = [\"bob\"]")
+            .err()
+            .unwrap();
+        assert!(e.contains("hide_accounts"));
+        let e = Config::parse("[feed.filter]\nlangs = [\"not a lang\"]")
+            .err()
+            .unwrap();
+        assert!(e.contains("feed.filter.langs"));
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert!(Config::parse("[layout]\nwidht = 120").is_err());
+    }
+
+    #[test]
+    fn invalid_values() {
+        let e = Config::parse("[feed]\nrefresh_rate = 0").err().unwrap();
+        assert!(e.contains("feed.refresh_rate"));
+        let e = Config::parse("[media]\nbrowser = []").err().unwrap();
+        assert!(e.contains("media.browser"));
+        let e = Config::parse("[composer]\nlangs = [\"not a lang\"]")
+            .err()
+            .unwrap();
+        assert!(e.contains("composer.langs"));
+        let e =
+            Config::parse("[keys.post]\nlike_toggle = \"spce\"").err().unwrap();
+        assert!(e.contains("keys.post.like_toggle"));
+    }
+}
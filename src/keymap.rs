@@ -0,0 +1,700 @@
+use std::collections::HashMap;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // navigation
+    Quit,
+    Back,
+    Next,
+    Previous,
+    Top,
+    Bottom,
+    Select,
+    SwitchFocus,
+    FocusLeft,
+    FocusRight,
+    Workspace1,
+    Workspace2,
+    Workspace3,
+    Workspace4,
+    Workspace5,
+    Workspace6,
+    Workspace7,
+    Workspace8,
+    Workspace9,
+    CommandLine,
+    NextTab,
+    PreviousTab,
+
+    // opening columns
+    OpenThread,
+    NewPost,
+    Notifications,
+    MyProfile,
+    Search,
+    SavedFeeds,
+
+    // post
+    LikeToggle,
+    Repost,
+    Reply,
+    Quote,
+    OpenInBrowser,
+    AuthorProfile,
+    ReposterProfile,
+    OpenMedia,
+    Facets,
+    Likes,
+    Reposts,
+    Quotes,
+    ViewRaw,
+    Reveal,
+
+    // actor
+    Follow,
+    Lists,
+
+    // notification
+    NotificationAuthor,
+
+    // outbox
+    Retry,
+    Discard,
+
+    // saved feeds
+    PinToggle,
+    MoveUp,
+    MoveDown,
+}
+
+// Where a key binding applies. Columns resolve keys against a list of
+// contexts, the first context having a binding wins.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    Global,
+    Feed,
+    Thread,
+    Post,
+    Actor,
+    Profile,
+    Notifications,
+    PostLikes,
+    PostReposts,
+    Search,
+    FacetModal,
+    Outbox,
+    SavedFeeds,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<&KeyEvent> for KeyPress {
+    fn from(key: &KeyEvent) -> Self {
+        KeyPress::new(key.code, key.modifiers)
+    }
+}
+
+impl KeyPress {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character (or of BackTab)
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => {
+                modifiers.difference(KeyModifiers::SHIFT)
+            }
+            _ => modifiers,
+        };
+        let modifiers =
+            modifiers.intersection(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        KeyPress { code, modifiers }
+    }
+
+    fn hint(&self) -> String {
+        let mut hint = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            hint += "C-";
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            hint += "A-";
+        }
+        match self.code {
+            KeyCode::Char(' ') => hint += "⎵",
+            KeyCode::Char(c) => hint.push(c),
+            KeyCode::Enter => hint += "↵",
+            KeyCode::Backspace => hint += "⌫",
+            KeyCode::Tab => hint += "⇥",
+            KeyCode::BackTab => hint += "⇤",
+            KeyCode::Up => hint += "↑",
+            KeyCode::Down => hint += "↓",
+            KeyCode::Left => hint += "←",
+            KeyCode::Right => hint += "→",
+            KeyCode::F(n) => hint += &format!("f{}", n),
+            code => hint += &format!("{:?}", code).to_lowercase(),
+        }
+        return hint;
+    }
+
+    // "j", "G", "space", "enter", "C-r", "A-x", "f5"
+    pub fn parse(s: &str) -> Result<KeyPress, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            if rest.len() <= 2 {
+                break;
+            }
+            if let Some(r) = rest.strip_prefix("C-") {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = r;
+            } else if let Some(r) =
+                rest.strip_prefix("A-").or_else(|| rest.strip_prefix("M-"))
+            {
+                modifiers |= KeyModifiers::ALT;
+                rest = r;
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "backspace" => KeyCode::Backspace,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" => KeyCode::Esc,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                f if f.starts_with('f') => match f[1..].parse::<u8>() {
+                    Ok(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{}`", s)),
+                },
+                _ => return Err(format!("unknown key `{}`", s)),
+            },
+        };
+        Ok(KeyPress::new(code, modifiers))
+    }
+}
+
+// Keys of a sequence are separated by whitespace, e.g. "g g"
+fn parse_sequence(s: &str) -> Result<Vec<KeyPress>, String> {
+    let keys = s
+        .split_whitespace()
+        .map(KeyPress::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(keys)
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn sequences(&self) -> Vec<&str> {
+        match self {
+            KeySpec::One(s) => vec![s.as_str()],
+            KeySpec::Many(v) => v.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+pub type KeysConfig = HashMap<Context, HashMap<Action, KeySpec>>;
+
+const DEFAULT_BINDINGS: &[(Context, Action, &str)] = &[
+    (Context::Global, Action::Quit, "q"),
+    (Context::Global, Action::Back, "backspace"),
+    (Context::Global, Action::Next, "j"),
+    (Context::Global, Action::Previous, "k"),
+    (Context::Global, Action::Top, "g g"),
+    (Context::Global, Action::Bottom, "G"),
+    (Context::Global, Action::Select, "enter"),
+    (Context::Global, Action::SwitchFocus, "tab"),
+    (Context::Global, Action::FocusLeft, "h"),
+    (Context::Global, Action::FocusRight, "l"),
+    (Context::Global, Action::Workspace1, "1"),
+    (Context::Global, Action::Workspace2, "2"),
+    (Context::Global, Action::Workspace3, "3"),
+    (Context::Global, Action::Workspace4, "4"),
+    (Context::Global, Action::Workspace5, "5"),
+    (Context::Global, Action::Workspace6, "6"),
+    (Context::Global, Action::Workspace7, "7"),
+    (Context::Global, Action::Workspace8, "8"),
+    (Context::Global, Action::Workspace9, "9"),
+    (Context::Global, Action::CommandLine, ":"),
+    (Context::Global, Action::NextTab, "]"),
+    (Context::Global, Action::PreviousTab, "["),
+    (Context::Feed, Action::OpenThread, "enter"),
+    (Context::Feed, Action::NewPost, "n"),
+    (Context::Feed, Action::Notifications, "b"),
+    (Context::Feed, Action::MyProfile, "B"),
+    (Context::Feed, Action::Search, "/"),
+    (Context::Feed, Action::ReposterProfile, "A"),
+    (Context::Feed, Action::SavedFeeds, "s"),
+    (Context::Thread, Action::OpenThread, "enter"),
+    (Context::Thread, Action::Facets, "f"),
+    (Context::Post, Action::LikeToggle, "space"),
+    (Context::Post, Action::Repost, "o"),
+    (Context::Post, Action::Reply, "u"),
+    (Context::Post, Action::Quote, "i"),
+    (Context::Post, Action::OpenInBrowser, "p"),
+    (Context::Post, Action::AuthorProfile, "a"),
+    (Context::Post, Action::OpenMedia, "m"),
+    (Context::Post, Action::Facets, "f"),
+    (Context::Post, Action::Likes, "F"),
+    (Context::Post, Action::Reposts, "O"),
+    (Context::Post, Action::Quotes, "Q"),
+    (Context::Post, Action::ViewRaw, "R"),
+    (Context::Post, Action::Reveal, "v"),
+    (Context::Actor, Action::Follow, "enter"),
+    (Context::Actor, Action::OpenMedia, "m"),
+    (Context::Actor, Action::OpenInBrowser, "p"),
+    (Context::Profile, Action::OpenThread, "enter"),
+    (Context::Profile, Action::Lists, "L"),
+    (Context::Notifications, Action::OpenThread, "enter"),
+    (Context::Notifications, Action::NotificationAuthor, "A"),
+    (Context::PostLikes, Action::AuthorProfile, "a"),
+    (Context::PostReposts, Action::AuthorProfile, "a"),
+    (Context::Outbox, Action::Retry, "r"),
+    (Context::Outbox, Action::Discard, "d"),
+    (Context::SavedFeeds, Action::PinToggle, "space"),
+    (Context::SavedFeeds, Action::MoveUp, "K"),
+    (Context::SavedFeeds, Action::MoveDown, "J"),
+    (Context::SavedFeeds, Action::OpenMedia, "m"),
+];
+
+pub enum Resolution {
+    Action(Action),
+    // The keys so far are the start of a longer sequence
+    Pending,
+    Unbound,
+}
+
+pub struct Keymap {
+    bindings: HashMap<Context, Vec<(Vec<KeyPress>, Action)>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeysConfig::new()).expect("Invalid default key bindings")
+    }
+}
+
+impl Keymap {
+    // Bindings in config replace the default bindings of the same action in
+    // the same context, and the default bindings whose keys they reuse. An
+    // empty list unbinds the action. Two actions bound to overlapping keys in
+    // the same context are an error.
+    pub fn new(config: &KeysConfig) -> Result<Keymap, String> {
+        let mut bindings: HashMap<Context, Vec<(Vec<KeyPress>, Action)>> =
+            HashMap::new();
+        for (context, actions) in config {
+            let mut user = vec![];
+            for (action, spec) in actions {
+                for keys in spec.sequences() {
+                    let name =
+                        format!("keys.{}.{}", context.name(), action.name());
+                    let parsed = parse_sequence(keys)
+                        .map_err(|e| format!("{}: {}", name, e))?;
+                    user.push((parsed, *action, name, keys));
+                }
+            }
+            // the same error whatever the order of the config
+            let mut sorted = user.iter().collect::<Vec<_>>();
+            sorted.sort_by(|a, b| (&a.2, a.3).cmp(&(&b.2, b.3)));
+            for (i, (keys, action, name, raw)) in sorted.iter().enumerate() {
+                let conflict = sorted[i + 1..]
+                    .iter()
+                    .find(|(k, a, ..)| a != action && overlap(k, keys));
+                if let Some((_, _, other, other_raw)) = conflict {
+                    return Err(format!(
+                        "{}: `{}` conflicts with `{}` of {}",
+                        name, raw, other_raw, other
+                    ));
+                }
+            }
+            bindings.insert(
+                *context,
+                user.into_iter().map(|(k, a, ..)| (k, a)).collect(),
+            );
+        }
+
+        for (context, action, keys) in DEFAULT_BINDINGS {
+            let overridden = config
+                .get(context)
+                .is_some_and(|actions| actions.contains_key(action));
+            if overridden {
+                continue;
+            }
+            let keys = parse_sequence(keys)?;
+            let bindings = bindings.entry(*context).or_default();
+            if bindings.iter().any(|(k, _)| overlap(k, &keys)) {
+                continue;
+            }
+            bindings.push((keys, *action));
+        }
+
+        Ok(Keymap { bindings })
+    }
+
+    // Keys of the first binding of the action in the context, as shown next
+    // to what the action does. None if the action is unbound.
+    pub fn hint(&self, context: Context, action: Action) -> Option<String> {
+        let (keys, _) =
+            self.bindings.get(&context)?.iter().find(|(_, a)| *a == action)?;
+        return Some(keys.iter().map(KeyPress::hint).collect());
+    }
+
+    pub fn resolve(
+        &self,
+        contexts: &[Context],
+        keys: &[KeyPress],
+    ) -> Resolution {
+        for context in contexts {
+            let Some(bindings) = self.bindings.get(context) else {
+                continue;
+            };
+            let exact = bindings.iter().find(|(k, _)| k == keys);
+            let prefix = bindings
+                .iter()
+                .any(|(k, _)| k.len() > keys.len() && k.starts_with(keys));
+            match (exact, prefix) {
+                (Some((_, action)), false) => {
+                    return Resolution::Action(*action)
+                }
+                (_, true) => return Resolution::Pending,
+                (None, false) => {}
+            }
+        }
+        Resolution::Unbound
+    }
+}
+
+// Whether one sequence starts with the other, so that only one of them can
+// ever be resolved
+fn overlap(a: &[KeyPress], b: &[KeyPress]) -> bool {
+    return a.starts_with(b) || b.starts_with(a);
+}
+
+impl Context {
+    fn name(&self) -> &'static str {
+        match self {
+            Context::Global => "global",
+            Context::Feed => "feed",
+            Context::Thread => "thread",
+            Context::Post => "post",
+            Context::Actor => "actor",
+            Context::Profile => "profile",
+            Context::Notifications => "notifications",
+            Context::PostLikes => "post_likes",
+            Context::PostReposts => "post_reposts",
+            Context::Search => "search",
+            Context::FacetModal => "facet_modal",
+            Context::Outbox => "outbox",
+            Context::SavedFeeds => "saved_feeds",
+        }
+    }
+}
+
+impl Action {
+    // Index of the workspace switched to by the action
+    pub fn workspace(&self) -> Option<usize> {
+        let i = match self {
+            Action::Workspace1 => 0,
+            Action::Workspace2 => 1,
+            Action::Workspace3 => 2,
+            Action::Workspace4 => 3,
+            Action::Workspace5 => 4,
+            Action::Workspace6 => 5,
+            Action::Workspace7 => 6,
+            Action::Workspace8 => 7,
+            Action::Workspace9 => 8,
+            _ => return None,
+        };
+        Some(i)
+    }
+
+    fn name(&self) -> String {
+        let debug = format!("{:?}", self);
+        let mut name = String::new();
+        for c in debug.chars() {
+            if c.is_uppercase() && !name.is_empty() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+}
+
+// Keys typed so far that form the start of a binding
+#[derive(Default)]
+pub struct PendingKeys {
+    keys: Vec<KeyPress>,
+}
+
+impl PendingKeys {
+    pub fn push(
+        &mut self,
+        keymap: &Keymap,
+        contexts: &[Context],
+        key: KeyPress,
+    ) -> Option<Action> {
+        self.keys.push(key);
+        match keymap.resolve(contexts, &self.keys) {
+            Resolution::Action(action) => {
+                self.keys.clear();
+                Some(action)
+            }
+            Resolution::Pending => None,
+            Resolution::Unbound => {
+                // Broken sequence, try the last key on its own
+                let retry = self.keys.len() > 1;
+                self.keys.clear();
+                if retry {
+                    self.push(keymap, contexts, key)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! keymap {
+    () => {
+        $crate::KEYMAP.read().unwrap()
+    };
+}
+
+// "(keys)" of the first binding of the action, None if it is unbound
+pub fn key_hint(context: Context, action: Action) -> Option<String> {
+    return keymap!().hint(context, action).map(|keys| format!("({})", keys));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(s: &str) -> KeyPress {
+        KeyPress::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(key("j").code, KeyCode::Char('j'));
+        assert_eq!(key("G").code, KeyCode::Char('G'));
+        assert_eq!(key("space").code, KeyCode::Char(' '));
+        assert_eq!(key("Enter").code, KeyCode::Enter);
+        assert_eq!(key("f5").code, KeyCode::F(5));
+        let ctrl_r = key("C-r");
+        assert_eq!(ctrl_r.code, KeyCode::Char('r'));
+        assert_eq!(ctrl_r.modifiers, KeyModifiers::CONTROL);
+        assert!(KeyPress::parse("spce").is_err());
+        assert!(parse_sequence("").is_err());
+    }
+
+    #[test]
+    fn shift_is_ignored_for_chars() {
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyPress::from(&event), key("G"));
+    }
+
+    #[test]
+    fn sequence() {
+        let keymap = Keymap::default();
+        let contexts = [Context::Feed, Context::Post, Context::Global];
+        let mut pending = PendingKeys::default();
+        assert_eq!(pending.push(&keymap, &contexts, key("g")), None);
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("g")),
+            Some(Action::Top)
+        );
+        assert_eq!(pending.push(&keymap, &contexts, key("g")), None);
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("j")),
+            Some(Action::Next)
+        );
+    }
+
+    #[test]
+    fn context_priority() {
+        let keymap = Keymap::default();
+        let mut pending = PendingKeys::default();
+        let feed = [Context::Feed, Context::Post, Context::Global];
+        let actor = [Context::Actor, Context::Global];
+        let modal = [Context::FacetModal, Context::Global];
+        assert_eq!(
+            pending.push(&keymap, &feed, key("enter")),
+            Some(Action::OpenThread)
+        );
+        assert_eq!(
+            pending.push(&keymap, &actor, key("enter")),
+            Some(Action::Follow)
+        );
+        assert_eq!(
+            pending.push(&keymap, &modal, key("enter")),
+            Some(Action::Select)
+        );
+    }
+
+    #[test]
+    fn config_overrides_default() {
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [post]
+            like_toggle = ["l", "C-l"]
+
+            [global]
+            next = "down"
+            top = []
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::new(&config).unwrap();
+        let contexts = [Context::Post, Context::Global];
+        let mut pending = PendingKeys::default();
+        assert_eq!(pending.push(&keymap, &contexts, key("space")), None);
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("C-l")),
+            Some(Action::LikeToggle)
+        );
+        assert_eq!(pending.push(&keymap, &contexts, key("j")), None);
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("down")),
+            Some(Action::Next)
+        );
+        assert_eq!(pending.push(&keymap, &contexts, key("g")), None);
+        assert_eq!(pending.push(&keymap, &contexts, key("g")), None);
+    }
+
+    #[test]
+    fn config_replaces_default_with_same_keys() {
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [post]
+            reply = "space"
+
+            [global]
+            bottom = "g"
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::new(&config).unwrap();
+        let contexts = [Context::Post, Context::Global];
+        let mut pending = PendingKeys::default();
+        // like_toggle keeps no key
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("space")),
+            Some(Action::Reply)
+        );
+        // the default "g g" would keep "g" pending
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("g")),
+            Some(Action::Bottom)
+        );
+        assert_eq!(
+            pending.push(&keymap, &contexts, key("o")),
+            Some(Action::Repost)
+        );
+    }
+
+    #[test]
+    fn conflicting_config() {
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [post]
+            reply = "r"
+            quote = ["x", "r"]
+            "#,
+        )
+        .unwrap();
+        let e = Keymap::new(&config).err().unwrap();
+        assert_eq!(
+            e,
+            "keys.post.quote: `r` conflicts with `r` of keys.post.reply"
+        );
+
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [feed]
+            search = "s"
+            saved_feeds = "s s"
+            "#,
+        )
+        .unwrap();
+        let e = Keymap::new(&config).err().unwrap();
+        assert!(e.starts_with("keys.feed.saved_feeds: `s s` conflicts"));
+
+        // another context, or the same action twice
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [feed]
+            search = ["s", "s"]
+
+            [post]
+            reply = "s"
+            "#,
+        )
+        .unwrap();
+        assert!(Keymap::new(&config).is_ok());
+    }
+
+    #[test]
+    fn hints() {
+        let config: KeysConfig = toml::from_str(
+            r#"
+            [post]
+            like_toggle = ["C-l", "l"]
+            reply = []
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::new(&config).unwrap();
+        let hint = |context, action| keymap.hint(context, action);
+        assert_eq!(hint(Context::Post, Action::LikeToggle).unwrap(), "C-l");
+        assert_eq!(hint(Context::Post, Action::Reply), None);
+        assert_eq!(hint(Context::Post, Action::Repost).unwrap(), "o");
+        assert_eq!(hint(Context::Global, Action::Top).unwrap(), "gg");
+        assert_eq!(hint(Context::Actor, Action::Follow).unwrap(), "↵");
+
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.hint(Context::Post, Action::LikeToggle).unwrap(),
+            "⎵"
+        );
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config: KeysConfig =
+            toml::from_str("[feed]\nnew_post = \"C-spce\"").unwrap();
+        let e = Keymap::new(&config).err().unwrap();
+        assert!(e.contains("keys.feed.new_post"));
+        assert!(toml::from_str::<KeysConfig>("[feed]\nfly = \"x\"").is_err());
+    }
+}
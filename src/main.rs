@@ -1,27 +1,45 @@
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+
 mod app;
+mod client;
+mod clock;
 mod columns;
+mod command;
 mod components;
+mod config;
+mod keymap;
+mod moderation;
+mod outbox;
+mod search_query;
+mod snapshot;
+mod workers;
 
 use std::{
     env, fs,
     io::stdout,
     path::PathBuf,
-    sync::{mpsc, RwLock},
+    sync::{Arc, RwLock},
+    time::Duration,
 };
 
+use atrium_api::types::string::Did;
 use bsky_sdk::{
-    agent::config::{Config, FileStore},
+    agent::config::{Config as SessionConfig, FileStore},
+    error::GenericXrpcError,
     BskyAgent,
 };
-use components::{
-    logger::LOGGER,
-    post_manager::{self, PostManager},
-};
+use components::{logger::LOGGER, post_manager::PostManager};
 use dotenvy::dotenv;
+use futures::StreamExt;
 use lazy_static::lazy_static;
 use ratatui::{
+    backend::TestBackend,
     crossterm::{
-        event::{self, DisableBracketedPaste, EnableBracketedPaste},
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+            EnableMouseCapture, Event, EventStream, MouseEvent, MouseEventKind,
+        },
         execute,
         terminal::{
             disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
@@ -31,22 +49,44 @@ use ratatui::{
     prelude::CrosstermBackend,
     DefaultTerminal, Terminal,
 };
+use tokio::sync::Notify;
 
 use crate::{
     app::{App, AppEvent, EventReceiver},
+    client::{
+        cached::CachedApi, fake::FakeApi, http::RateLimitClient, Agent, Client,
+    },
     columns::{
-        updating_feed::{self, UpdatingFeed},
-        Column, ColumnStack,
+        updating_feed::FeedSource, Column, ColumnKind, ColumnStack, Workspace,
     },
+    config::Config,
+    keymap::Keymap,
+    moderation::Moderation,
+    outbox::Outbox,
+    workers::Workers,
 };
 
 lazy_static! {
     static ref POST_MANAGER: RwLock<PostManager> =
         RwLock::new(PostManager::new());
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+    static ref KEYMAP: RwLock<Keymap> = RwLock::new(Keymap::default());
+    static ref REDRAW: Notify = Notify::new();
+    static ref WORKERS: Workers = Workers::new();
+    static ref OUTBOX: Outbox = Outbox::new();
+    static ref MODERATION: Moderation = Moderation::new();
     static ref SESSION_FILE: String = {
         let home = env::var("HOME").unwrap();
         format!("{}/.local/share/tsky/session.json", home)
     };
+    static ref CACHE_DIR: PathBuf = {
+        let home = env::var("HOME").unwrap();
+        PathBuf::from(format!("{}/.local/share/tsky/cache", home))
+    };
+    static ref OUTBOX_FILE: PathBuf = {
+        let home = env::var("HOME").unwrap();
+        PathBuf::from(format!("{}/.local/share/tsky/outbox.json", home))
+    };
 }
 
 #[tokio::main]
@@ -54,112 +94,297 @@ async fn main() {
     log::set_logger(&LOGGER).unwrap();
     log::set_max_level(log::LevelFilter::Debug);
 
-    eprintln!("Logging in");
-    let agent = login().await;
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    *KEYMAP.write().unwrap() = Keymap::new(&config.keys)
+        .expect("Key bindings are checked when loading config");
+    *CONFIG.write().unwrap() = config;
 
-    let mut terminal = init_term().expect("Cannot init term");
-    terminal
-        .draw(|f| {
-            f.render_widget("Creating column (starting workers)", f.area())
-        })
-        .unwrap();
-    let (tx, rx) = mpsc::channel();
-    let var_name = UpdatingFeed::new(tx);
-    let feed = var_name;
-    feed.spawn_feed_autoupdate(agent.clone());
-    feed.spawn_request_worker(agent.clone(), rx);
+    let dump_screen = env::args()
+        .find_map(|a| dump_screen_size(&a))
+        .transpose()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+    // --demo runs against fixtures without logging in
+    let (agent, session): (Client, _) = if env::args().any(|a| a == "--demo") {
+        let now = chrono::DateTime::parse_from_rfc3339(client::fake::NOW)
+            .expect("Invalid fixture time");
+        clock::freeze(now.into());
+        (Arc::new(FakeApi::new()), None)
+    } else {
+        // writes left unsent by the last run, demo writes are not kept
+        if let Err(e) = OUTBOX.load(OUTBOX_FILE.clone()) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        eprintln!("Logging in");
+        let cache = config!().cache.enabled;
+        match login().await {
+            Ok(session) if cache => {
+                let agent = Arc::new(session.clone());
+                let cached =
+                    match CachedApi::new(agent, CACHE_DIR.clone()).await {
+                        Ok(cached) => cached,
+                        Err(e) => {
+                            eprintln!("Cannot open cache: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                (Arc::new(cached), Some(session))
+            }
+            Ok(session) => (Arc::new(session.clone()), Some(session)),
+            // the session is kept as is for the next start
+            Err((e, did)) if cache => {
+                eprintln!("Cannot reach the server: {}\r", e);
+                eprintln!("Reading from cache\r");
+                (Arc::new(CachedApi::offline(did, CACHE_DIR.clone())), None)
+            }
+            Err((e, _)) => {
+                eprintln!("Cannot resume session: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
 
+    // posts are drawn as they are until the muted words are loaded
+    let agent_ = agent.clone();
+    workers!().spawn(async move {
+        if let Err(e) = MODERATION.load(&agent_).await {
+            log::error!("Cannot load muted words: {}", e);
+        }
+    });
+
+    // Prints a single frame instead of running interactively
+    if let Some((width, height)) = dump_screen {
+        POST_MANAGER.write().unwrap().spawn_worker(agent.clone());
+        let mut app = App::new(open_workspaces(agent.clone()).await);
+        wait_for_first_page().await;
+        app.refresh().await;
+
+        let mut terminal = Terminal::new(TestBackend::new(width, height))
+            .expect("Cannot create test backend");
+        app.render(&mut terminal, agent.is_stale()).await;
+        print!("{}", snapshot::buffer_to_string(terminal.backend().buffer()));
+
+        workers!().shutdown().await;
+        return;
+    }
+
+    let mut terminal = init_term().expect("Cannot init term");
     terminal
         .draw(|f| f.render_widget("Starting post manager worker", f.area()))
         .unwrap();
     {
         POST_MANAGER.write().unwrap().spawn_worker(agent.clone());
     }
+    let agent_ = agent.clone();
+    workers!().spawn(async move { OUTBOX.run(agent_).await });
 
-    let mut app = App::new(ColumnStack::from(vec![Column::UpdatingFeed(feed)]));
+    terminal
+        .draw(|f| {
+            f.render_widget("Creating columns (starting workers)", f.area())
+        })
+        .unwrap();
+    let mut app = App::new(open_workspaces(agent.clone()).await);
 
+    let mut events = EventStream::new();
     loop {
         app.refresh().await;
-        app.render(&mut terminal).await;
+        app.render(&mut terminal, agent.is_stale()).await;
 
-        if !event::poll(std::time::Duration::from_millis(500))
-            .expect("Error polling event")
-        {
-            continue;
-        }
-        let event = event::read().expect("Cannot read event");
+        // Sleeps until a key is pressed or a worker has something new to show
+        let event = tokio::select! {
+            event = next_event(&mut events) => event,
+            _ = REDRAW.notified() => continue,
+        };
+        let Some(event) = event else {
+            break;
+        };
+        let event = event.expect("Cannot read event");
         match (&mut app).handle_events(event, agent.clone()).await {
             AppEvent::None => {}
 
             AppEvent::Quit => {
-                for col in &app.column.stack {
-                    match col {
-                        Column::UpdatingFeed(feed) => feed
-                            .request_worker_tx
-                            .send(updating_feed::RequestMsg::Close)
-                            .expect("Cannot close worker"),
-                        _ => {}
-                    }
-                }
                 break;
             }
 
             AppEvent::ColumnNewLayer(view) => {
-                app.column.push(view);
+                app.focused_stack().push(view);
             }
 
             AppEvent::ColumnPopLayer => {
-                app.column.pop();
+                app.focused_stack().pop_layer();
             }
         };
     }
 
-    post_manager_tx!()
-        .send(post_manager::RequestMsg::Close)
-        .expect("Cannot close worker");
+    workers!().shutdown().await;
     restore_term().expect("Cannot restore term");
 
-    agent
-        .to_config()
-        .await
-        .save(&FileStore::new(SESSION_FILE.as_str()))
-        .await
-        .expect(
-            format!("Cannot save session file {}", SESSION_FILE.as_str())
-                .as_str(),
-        );
+    if let Some(session) = session {
+        session
+            .to_config()
+            .await
+            .save(&FileStore::new(SESSION_FILE.as_str()))
+            .await
+            .unwrap_or_else(|_| {
+                panic!("Cannot save session file {}", SESSION_FILE.as_str())
+            });
+    }
+}
+
+async fn open_workspaces(agent: Client) -> Vec<Workspace> {
+    let layout = config!().workspaces();
+    let mut workspaces = Vec::new();
+    for (name, columns) in layout {
+        let mut stacks = Vec::new();
+        for (kind, width, filter) in columns {
+            let column = match (kind, filter) {
+                (ColumnKind::Feed, Some(filter)) => Column::filtered_feed(
+                    FeedSource::Following,
+                    &filter,
+                    agent.clone(),
+                ),
+                (kind, _) => match Column::open(kind, agent.clone()).await {
+                    Ok(column) => column,
+                    Err(e) => {
+                        log::error!("Cannot open column: {}", e);
+                        Column::feed(FeedSource::Following, agent.clone())
+                    }
+                },
+            };
+            stacks.push(ColumnStack::from(vec![column], width));
+        }
+        workspaces.push(Workspace::new(name, stacks));
+    }
+    return workspaces;
+}
+
+// Longest wait for the first page of `--dump-screen`
+const DUMP_TIMEOUT: Duration = Duration::from_secs(10);
+// Redraws closer than this are taken as the same page landing in several
+// columns
+const DUMP_QUIET: Duration = Duration::from_millis(50);
+
+// Waits for the workers to draw the first page, until they stop redrawing
+async fn wait_for_first_page() {
+    let deadline = tokio::time::Instant::now() + DUMP_TIMEOUT;
+    if tokio::time::timeout_at(deadline, REDRAW.notified()).await.is_err() {
+        log::warn!("Nothing fetched in {}s", DUMP_TIMEOUT.as_secs());
+        return;
+    }
+    while tokio::time::Instant::now() < deadline {
+        let quiet = tokio::time::timeout(DUMP_QUIET, REDRAW.notified());
+        if quiet.await.is_err() {
+            return;
+        }
+    }
+}
+
+// Parses `--dump-screen` or `--dump-screen=WIDTHxHEIGHT`
+fn dump_screen_size(arg: &str) -> Option<Result<(u16, u16), String>> {
+    if arg == "--dump-screen" {
+        return Some(Ok((120, 40)));
+    }
+    let size = arg.strip_prefix("--dump-screen=")?;
+    let parsed = size
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .ok_or(format!("Invalid screen size {}, expected WIDTHxHEIGHT", size));
+    return Some(parsed);
 }
 
 fn init_term() -> std::io::Result<DefaultTerminal> {
     let hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
-        post_manager_tx!()
-            .send(post_manager::RequestMsg::Close)
-            .expect("Cannot close worker");
         restore_term().expect("Cannot restore term");
         hook(info);
     }));
     enable_raw_mode()?;
-    execute!(stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
+    execute!(
+        stdout(),
+        EnterAlternateScreen,
+        EnableBracketedPaste,
+        EnableMouseCapture
+    )?;
     let backend = CrosstermBackend::new(stdout());
     Terminal::new(backend)
 }
 
 fn restore_term() -> std::io::Result<()> {
     disable_raw_mode()?;
-    execute!(stdout(), LeaveAlternateScreen, DisableBracketedPaste)?;
+    execute!(
+        stdout(),
+        LeaveAlternateScreen,
+        DisableBracketedPaste,
+        DisableMouseCapture
+    )?;
     Ok(())
 }
 
-async fn login() -> BskyAgent {
-    match Config::load(&FileStore::new(SESSION_FILE.as_str())).await {
+// Skips mouse movements, nothing reacts to them and rendering on every one of
+// them is wasteful
+async fn next_event(
+    events: &mut EventStream,
+) -> Option<std::io::Result<Event>> {
+    loop {
+        let event = events.next().await;
+        if let Some(Ok(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            ..
+        }))) = event
+        {
+            continue;
+        }
+        return event;
+    }
+}
+
+// The server could not be reached, as opposed to it refusing the session.
+// atrium keeps only the debug output of errors from the http client.
+fn is_connection_error(e: &bsky_sdk::Error) -> bool {
+    let bsky_sdk::Error::Xrpc(e) = e else {
+        return false;
+    };
+    return matches!(
+        &**e,
+        GenericXrpcError::Other(s) if s.starts_with("HttpClient(")
+    );
+}
+
+// Fails with the did of the saved session when the server cannot be reached,
+// other errors exit
+async fn login() -> Result<Agent, (String, Did)> {
+    match SessionConfig::load(&FileStore::new(SESSION_FILE.as_str())).await {
         Ok(config) => {
+            let did = config.session.as_ref().map(|s| s.did.clone());
             let agent = BskyAgent::builder()
+                .client(RateLimitClient::new(&config.endpoint))
                 .config(config)
                 .build()
-                .await
-                .expect("Cannot create bsky agent from session file");
-            return agent;
+                .await;
+            return match (agent, did) {
+                (Ok(agent), _) => Ok(agent),
+                (Err(e), Some(did)) if is_connection_error(&e) => {
+                    Err((e.to_string(), did))
+                }
+                // an expired or revoked session or a bad endpoint, the cache
+                // would only hide it
+                (Err(e), _) => {
+                    eprintln!("Cannot resume session: {}\r", e);
+                    eprintln!(
+                        "Remove {} to log in again\r",
+                        SESSION_FILE.as_str()
+                    );
+                    std::process::exit(1);
+                }
+            };
         }
         Err(e) => {
             eprintln!(
@@ -178,6 +403,9 @@ async fn login() -> BskyAgent {
             let password = env::var("password").expect("Cannot get $password");
 
             let agent = BskyAgent::builder()
+                .client(RateLimitClient::new(
+                    &SessionConfig::default().endpoint,
+                ))
                 .build()
                 .await
                 .expect("Cannot create bsky agent");
@@ -186,27 +414,40 @@ async fn login() -> BskyAgent {
             let path = PathBuf::from(SESSION_FILE.as_str());
             let dir = path.parent().unwrap();
             if !dir.exists() {
-                fs::create_dir_all(dir).expect(
-                    format!(
-                        "Cannot create directory {}",
-                        dir.to_str().unwrap()
-                    )
-                    .as_str(),
-                );
+                fs::create_dir_all(dir).unwrap_or_else(|_| {
+                    panic!("Cannot create directory {}", dir.to_str().unwrap())
+                });
             }
             agent
                 .to_config()
                 .await
                 .save(&FileStore::new(SESSION_FILE.as_str()))
                 .await
-                .expect(
-                    format!(
-                        "Cannot save session file {}",
-                        SESSION_FILE.as_str()
-                    )
-                    .as_str(),
-                );
-            return agent;
+                .unwrap_or_else(|_| {
+                    panic!("Cannot save session file {}", SESSION_FILE.as_str())
+                });
+            return Ok(agent);
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use atrium_api::xrpc::{self, error::XrpcError, http::StatusCode};
+
+    use super::*;
+
+    #[test]
+    fn connection_errors() {
+        let refused =
+            xrpc::Error::<()>::HttpClient("connection refused".into());
+        assert!(is_connection_error(&refused.into()));
+
+        let expired = xrpc::Error::<()>::XrpcResponse(XrpcError {
+            status: StatusCode::BAD_REQUEST,
+            error: None,
+        });
+        assert!(!is_connection_error(&expired.into()));
+        assert!(!is_connection_error(&bsky_sdk::Error::NotLoggedIn));
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use atrium_api::{
+    app::bsky::actor::defs::{
+        MutedWord, MutedWordData, MutedWordsPrefData, PreferencesItem,
+    },
+    types::{string::Did, Union},
+};
+use chrono::DateTime;
+
+use crate::{
+    client::{self, Client},
+    clock,
+    components::{
+        embed::{Embed, EmbededPostMedia, Record},
+        post::Post,
+    },
+    redraw,
+};
+
+#[derive(Default)]
+struct State {
+    me: Option<Did>,
+    muted_words: Vec<MutedWord>,
+    // uris of posts hidden by the user
+    hidden_posts: HashSet<String>,
+    // uris of posts shown anyway until tsky quits
+    revealed: HashSet<String>,
+}
+
+// Muted words and hidden posts from the user's preferences. Posts they match
+// are drawn collapsed until revealed.
+pub struct Moderation {
+    state: Mutex<State>,
+}
+
+impl Moderation {
+    pub fn new() -> Moderation {
+        Moderation { state: Mutex::new(State::default()) }
+    }
+
+    pub async fn load(&self, agent: &Client) -> Result<(), String> {
+        let me = agent.did().await?;
+        let preferences = client::preferences(agent).await?;
+        let mut state = self.state.lock().unwrap();
+        state.me = Some(me);
+        for p in preferences {
+            match p {
+                Union::Refs(PreferencesItem::MutedWordsPref(p)) => {
+                    state.muted_words = p.data.items;
+                }
+                Union::Refs(PreferencesItem::HiddenPostsPref(p)) => {
+                    state.hidden_posts = p.data.items.into_iter().collect();
+                }
+                _ => {}
+            }
+        }
+        drop(state);
+        redraw!();
+        return Ok(());
+    }
+
+    // Why the post is collapsed, None if it is shown
+    pub fn reason(&self, post: &Post) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        if state.revealed.contains(&post.uri)
+            || state.me.as_ref() == Some(&post.author.did)
+        {
+            return None;
+        }
+        if state.hidden_posts.contains(&post.uri) {
+            return Some("Hidden post".to_string());
+        }
+        let now = clock::now();
+        let word = state.muted_words.iter().find(|word| {
+            let expired = word
+                .expires_at
+                .as_ref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t.as_str()).ok())
+                .is_some_and(|t| t < now);
+            let excluded = word.actor_target.as_deref()
+                == Some("exclude-following")
+                && post.author.following;
+            !expired && !excluded && matches(word, post)
+        })?;
+        return Some(format!("Muted word: {}", word.value));
+    }
+
+    pub fn reveal(&self, uri: &str) {
+        self.state.lock().unwrap().revealed.insert(uri.to_string());
+        redraw!();
+    }
+
+    pub fn muted_words(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        return state.muted_words.iter().map(|w| w.value.clone()).collect();
+    }
+
+    // Mutes a word in text, tags and alt text of posts by anyone
+    pub async fn mute(
+        &self,
+        agent: &Client,
+        value: &str,
+    ) -> Result<(), String> {
+        let value = value.strip_prefix('#').unwrap_or(value);
+        let word = MutedWordData {
+            actor_target: Some("all".to_string()),
+            expires_at: None,
+            id: None,
+            targets: vec!["content".to_string(), "tag".to_string()],
+            value: value.to_string(),
+        };
+        return self
+            .change(agent, |words| {
+                if words.iter().any(|w| w.value.eq_ignore_ascii_case(value)) {
+                    return Err(format!("{} is already muted", value));
+                }
+                words.push(word.into());
+                return Ok(());
+            })
+            .await;
+    }
+
+    pub async fn unmute(
+        &self,
+        agent: &Client,
+        value: &str,
+    ) -> Result<(), String> {
+        let value = value.strip_prefix('#').unwrap_or(value);
+        return self
+            .change(agent, |words| {
+                let len = words.len();
+                words.retain(|w| !w.value.eq_ignore_ascii_case(value));
+                if words.len() == len {
+                    return Err(format!("{} is not muted", value));
+                }
+                return Ok(());
+            })
+            .await;
+    }
+
+    // Changes the muted words and writes them back, restoring them if the
+    // write fails
+    async fn change(
+        &self,
+        agent: &Client,
+        f: impl FnOnce(&mut Vec<MutedWord>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let (previous, items) = {
+            let mut state = self.state.lock().unwrap();
+            let previous = state.muted_words.clone();
+            f(&mut state.muted_words)?;
+            (previous, state.muted_words.clone())
+        };
+        redraw!();
+        let pref = PreferencesItem::MutedWordsPref(Box::new(
+            MutedWordsPrefData { items }.into(),
+        ));
+        if let Err(e) = client::put_preference(agent, pref).await {
+            self.state.lock().unwrap().muted_words = previous;
+            redraw!();
+            return Err(e);
+        }
+        return Ok(());
+    }
+}
+
+// Whether the muted word appears in the post. Words match whole words of the
+// text, phrases and words with punctuation or non-latin letters match
+// anywhere.
+fn matches(word: &MutedWord, post: &Post) -> bool {
+    let value = word.value.trim_start_matches('#').to_lowercase();
+    if value.is_empty() {
+        return false;
+    }
+    let tag = word.targets.iter().any(|t| t == "tag");
+    if tag && post.tags.iter().any(|t| t.to_lowercase() == value) {
+        return true;
+    }
+    if !word.targets.iter().any(|t| t == "content") {
+        return false;
+    }
+
+    let whole_word = value.chars().all(|c| c.is_ascii_alphanumeric());
+    return texts(post).iter().any(|text| {
+        let text = text.to_lowercase();
+        if whole_word {
+            text.split(|c: char| !c.is_alphanumeric()).any(|w| w == value)
+        } else {
+            text.contains(&value)
+        }
+    });
+}
+
+// Text and alt text of images and videos, with those of the quoted post
+fn texts(post: &Post) -> Vec<&str> {
+    let mut texts = vec![post.text.as_str()];
+    let media = match &post.embed {
+        Some(Embed::Images(images)) => {
+            texts.extend(images.iter().map(|i| i.alt.as_str()));
+            return texts;
+        }
+        Some(Embed::Video(video)) => {
+            texts.push(&video.alt);
+            return texts;
+        }
+        Some(Embed::Record(Record::Post(post))) => {
+            texts.push(&post.text);
+            &post.media
+        }
+        _ => return texts,
+    };
+    match media {
+        Some(EmbededPostMedia::Images(images)) => {
+            texts.extend(images.iter().map(|i| i.alt.as_str()))
+        }
+        Some(EmbededPostMedia::Video(video)) => texts.push(&video.alt),
+        _ => {}
+    }
+    return texts;
+}
+
+#[macro_export]
+macro_rules! moderation {
+    () => {
+        $crate::MODERATION
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use atrium_api::{app::bsky::feed::get_posts, types::string::Datetime};
+
+    use super::*;
+    use crate::client::fake::FakeApi;
+
+    async fn post(agent: &Client, uri: &str) -> Post {
+        let output = agent
+            .get_posts(
+                get_posts::ParametersData { uris: vec![uri.to_string()] }
+                    .into(),
+            )
+            .await
+            .unwrap();
+        return Post::try_from(&output.data.posts[0]).unwrap();
+    }
+
+    fn word(value: &str, targets: &[&str]) -> MutedWord {
+        return MutedWordData {
+            actor_target: None,
+            expires_at: None,
+            id: None,
+            targets: targets.iter().map(|t| t.to_string()).collect(),
+            value: value.to_string(),
+        }
+        .into();
+    }
+
+    #[tokio::test]
+    async fn muted_words_match() {
+        let agent: Client = Arc::new(FakeApi::new());
+        // "A plain text post by Bob.\nIt has two lines."
+        let bob = post(&agent, "at://did:plc:bob/app.bsky.feed.post/3").await;
+        let content = ["content", "tag"];
+        assert!(matches(&word("plain", &content), &bob));
+        assert!(matches(&word("BOB", &content), &bob));
+        assert!(matches(&word("two lines", &content), &bob));
+        assert!(!matches(&word("pla", &content), &bob));
+        assert!(!matches(&word("plain", &["tag"]), &bob));
+
+        // tagged #demo
+        let alice =
+            post(&agent, "at://did:plc:alice/app.bsky.feed.post/1").await;
+        assert!(matches(&word("#demo", &["tag"]), &alice));
+        assert!(!matches(&word("demos", &content), &alice));
+
+        let mut tagged = bob.clone();
+        tagged.tags.push("Spoiler".to_string());
+        assert!(matches(&word("#spoiler", &["tag"]), &tagged));
+        assert!(!matches(&word("#spoiler", &["content"]), &tagged));
+
+        // quotes Alice's "Hello from the tsky demo timeline! ..."
+        let quote =
+            post(&agent, "at://did:plc:tskyme/app.bsky.feed.post/4").await;
+        assert!(matches(&word("hello", &content), &quote));
+        assert!(!matches(&word("hello", &["tag"]), &quote));
+    }
+
+    #[tokio::test]
+    async fn reason() {
+        let agent: Client = Arc::new(FakeApi::new());
+        let moderation = Moderation::new();
+        moderation.load(&agent).await.unwrap();
+        let bob = post(&agent, "at://did:plc:bob/app.bsky.feed.post/3").await;
+        assert_eq!(moderation.reason(&bob), None);
+
+        moderation.mute(&agent, "plain").await.unwrap();
+        assert!(moderation.mute(&agent, "Plain").await.is_err());
+        assert_eq!(
+            moderation.reason(&bob),
+            Some("Muted word: plain".to_string())
+        );
+        // written back
+        let reloaded = Moderation::new();
+        reloaded.load(&agent).await.unwrap();
+        assert!(reloaded.muted_words().contains(&"plain".to_string()));
+
+        {
+            let mut state = moderation.state.lock().unwrap();
+            let word = state.muted_words.last_mut().unwrap();
+            word.expires_at = Some(Datetime::new(
+                DateTime::parse_from_rfc3339("2000-01-01T00:00:00Z").unwrap(),
+            ));
+        }
+        assert_eq!(moderation.reason(&bob), None);
+
+        moderation.unmute(&agent, "plain").await.unwrap();
+        assert!(moderation.unmute(&agent, "plain").await.is_err());
+        moderation.state.lock().unwrap().hidden_posts.insert(bob.uri.clone());
+        assert_eq!(moderation.reason(&bob), Some("Hidden post".to_string()));
+        moderation.reveal(&bob.uri);
+        assert_eq!(moderation.reason(&bob), None);
+    }
+}
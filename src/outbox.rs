@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use atrium_api::record::KnownRecord;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Notify};
+
+use crate::{
+    client::{http::rate_limit_reset, Client},
+    redraw,
+};
+
+// attempts before an action is marked failed
+const MAX_ATTEMPTS: u32 = 10;
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+// Write sent to the PDS
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Op {
+    Create(KnownRecord),
+    // at:// uri of the record
+    Delete(String),
+}
+
+impl Op {
+    pub fn describe(&self) -> String {
+        match self {
+            Op::Create(KnownRecord::AppBskyFeedLike(r)) => {
+                return format!("Like {}", r.subject.uri)
+            }
+            Op::Create(KnownRecord::AppBskyFeedRepost(r)) => {
+                return format!("Repost {}", r.subject.uri)
+            }
+            Op::Create(KnownRecord::AppBskyGraphFollow(r)) => {
+                return format!("Follow {}", r.subject.as_str())
+            }
+            Op::Create(KnownRecord::AppBskyFeedPost(r)) => {
+                return format!("Post \"{}\"", r.text.replace('\n', " "))
+            }
+            Op::Create(_) => return "Create record".to_string(),
+            Op::Delete(uri) => {
+                let action = match uri.split('/').nth(3) {
+                    Some("app.bsky.feed.like") => "Unlike",
+                    Some("app.bsky.feed.repost") => "Unrepost",
+                    Some("app.bsky.graph.follow") => "Unfollow",
+                    Some("app.bsky.feed.post") => "Delete post",
+                    _ => "Delete",
+                };
+                return format!("{} {}", action, uri);
+            }
+        }
+    }
+}
+
+// uri of the created record, None for deletions
+pub type Outcome = Result<Option<String>, String>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Item {
+    pub id: u64,
+    pub op: Op,
+    pub attempts: u32,
+    // of the last attempt
+    pub error: Option<String>,
+    // given up until retried by the user
+    pub failed: bool,
+    // not before, sent as soon as possible after a restart
+    #[serde(skip)]
+    pub retry_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct State {
+    items: Vec<Item>,
+    next_id: u64,
+    // not kept on disk if None
+    path: Option<PathBuf>,
+    waiters: HashMap<u64, oneshot::Sender<Outcome>>,
+    // bumped on every snapshot, an older queue is never written over a newer
+    // one
+    version: u64,
+}
+
+// Queue taken under the lock, written to disk after it is released
+struct Snapshot {
+    path: PathBuf,
+    json: Vec<u8>,
+    version: u64,
+}
+
+impl State {
+    fn snapshot(&mut self) -> Option<Snapshot> {
+        let path = self.path.clone()?;
+        let json = match serde_json::to_vec(&self.items) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("Cannot serialize outbox: {}", e);
+                return None;
+            }
+        };
+        self.version += 1;
+        return Some(Snapshot { path, json, version: self.version });
+    }
+
+    fn finish(&mut self, id: u64, outcome: Outcome) {
+        if let Some(waiter) = self.waiters.remove(&id) {
+            let _ = waiter.send(outcome);
+        }
+    }
+}
+
+// Queue of writes, sent one at a time by a worker. Failed writes are tried
+// again with exponential backoff, waiting for the rate limit to reset when
+// the PDS answered 429. The queue is kept on disk so that writes made while
+// offline are sent after a restart.
+pub struct Outbox {
+    state: Mutex<State>,
+    wake: Notify,
+    // version of the queue on disk, held while writing
+    written: Mutex<u64>,
+}
+
+// path with `suffix` appended to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    return PathBuf::from(name);
+}
+
+// Written next to `path` and renamed over it, so that a crash leaves either
+// the old or the new file whole
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp = with_suffix(path, ".tmp");
+    std::fs::write(&tmp, data)?;
+    return std::fs::rename(&tmp, path);
+}
+
+impl Outbox {
+    pub fn new() -> Outbox {
+        Outbox {
+            state: Mutex::new(State::default()),
+            wake: Notify::new(),
+            written: Mutex::new(0),
+        }
+    }
+
+    fn save(&self, snapshot: Option<Snapshot>) {
+        let Some(Snapshot { path, json, version }) = snapshot else {
+            return;
+        };
+        let mut written = self.written.lock().unwrap();
+        if *written >= version {
+            return;
+        }
+        if let Err(e) = write_atomic(&path, &json) {
+            log::error!("Cannot save outbox {}: {}", path.display(), e);
+            return;
+        }
+        *written = version;
+    }
+
+    // Reads writes left by the last run and keeps the queue at `path`. A
+    // queue that cannot be parsed is moved to `*.corrupt` and started empty.
+    pub fn load(&self, path: PathBuf) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                format!("Cannot create {}: {}", dir.display(), e)
+            })?;
+        }
+        if path.exists() {
+            let json = std::fs::read(&path).map_err(|e| {
+                format!("Cannot read outbox {}: {}", path.display(), e)
+            })?;
+            state.items = match serde_json::from_slice(&json) {
+                Ok(items) => items,
+                Err(e) => {
+                    let corrupt = with_suffix(&path, ".corrupt");
+                    log::error!(
+                        "Invalid outbox {}, moved to {}: {}",
+                        path.display(),
+                        corrupt.display(),
+                        e
+                    );
+                    std::fs::rename(&path, &corrupt).map_err(|e| {
+                        format!("Cannot move {}: {}", path.display(), e)
+                    })?;
+                    vec![]
+                }
+            };
+            state.next_id =
+                state.items.iter().map(|i| i.id + 1).max().unwrap_or(0);
+        }
+        state.path = Some(path);
+        self.wake.notify_one();
+        return Ok(());
+    }
+
+    pub fn submit(&self, op: Op) -> oneshot::Receiver<Outcome> {
+        let (tx, rx) = oneshot::channel();
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.items.push(Item {
+            id,
+            op,
+            attempts: 0,
+            error: None,
+            failed: false,
+            retry_at: None,
+        });
+        state.waiters.insert(id, tx);
+        let snapshot = state.snapshot();
+        drop(state);
+        self.save(snapshot);
+        self.wake.notify_one();
+        redraw!();
+        return rx;
+    }
+
+    pub fn items(&self) -> Vec<Item> {
+        return self.state.lock().unwrap().items.clone();
+    }
+
+    // Sends a failed or waiting write now
+    pub fn retry(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        let Some(item) = state.items.iter_mut().find(|i| i.id == id) else {
+            return;
+        };
+        item.failed = false;
+        item.attempts = 0;
+        item.retry_at = None;
+        let snapshot = state.snapshot();
+        drop(state);
+        self.save(snapshot);
+        self.wake.notify_one();
+    }
+
+    pub fn discard(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.items.retain(|i| i.id != id);
+        state.finish(id, Err("Discarded".to_string()));
+        let snapshot = state.snapshot();
+        drop(state);
+        self.save(snapshot);
+    }
+
+    pub async fn run(&self, agent: Client) {
+        loop {
+            match self.send_next(&agent).await {
+                Some(wait) if wait.is_zero() => {}
+                Some(wait) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = self.wake.notified() => {}
+                    }
+                }
+                None => self.wake.notified().await,
+            }
+        }
+    }
+
+    // Sends the first write due. Returns how long to wait for the next one,
+    // None if there is nothing to send.
+    async fn send_next(&self, agent: &Client) -> Option<Duration> {
+        let (id, op) = {
+            let state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let pending = state.items.iter().filter(|i| !i.failed);
+            let wait = pending
+                .clone()
+                .map(|i| i.retry_at.map_or(Duration::ZERO, |t| t - now))
+                .min()?;
+            let reset = rate_limit_reset()
+                .and_then(|t| t.duration_since(SystemTime::now()).ok())
+                .unwrap_or(Duration::ZERO);
+            if !wait.max(reset).is_zero() {
+                return Some(wait.max(reset));
+            }
+            let item = pending
+                .clone()
+                .find(|i| i.retry_at.is_none_or(|t| t <= now))?;
+            (item.id, item.op.clone())
+        };
+
+        let outcome = match &op {
+            Op::Create(record) => agent
+                .create_record(record.clone())
+                .await
+                .map(|output| Some(output.uri.clone())),
+            Op::Delete(uri) => {
+                agent.delete_record(uri.clone()).await.map(|_| None)
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        // discarded while being sent
+        let Some(i) = state.items.iter().position(|i| i.id == id) else {
+            return Some(Duration::ZERO);
+        };
+        match outcome {
+            Ok(uri) => {
+                state.items.remove(i);
+                state.finish(id, Ok(uri));
+            }
+            Err(e) => {
+                let item = &mut state.items[i];
+                item.attempts += 1;
+                item.error = Some(e.clone());
+                if item.attempts >= MAX_ATTEMPTS {
+                    item.failed = true;
+                    log::error!("{} failed: {}", op.describe(), e);
+                    state.finish(id, Err(e));
+                } else {
+                    let backoff = Duration::from_secs(1 << (item.attempts - 1))
+                        .min(MAX_BACKOFF);
+                    item.retry_at = Some(Instant::now() + backoff);
+                }
+            }
+        }
+        let snapshot = state.snapshot();
+        drop(state);
+        self.save(snapshot);
+        redraw!();
+        return Some(Duration::ZERO);
+    }
+}
+
+#[macro_export]
+macro_rules! outbox {
+    () => {
+        $crate::OUTBOX
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use atrium_api::{
+        app::bsky::feed::like,
+        com::atproto::repo::strong_ref,
+        types::string::{Cid, Datetime},
+    };
+
+    use super::*;
+    use crate::client::{cached::CachedApi, fake::FakeApi};
+
+    fn like(uri: &str) -> Op {
+        let cid = "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"
+            .parse::<Cid>()
+            .unwrap();
+        return Op::Create(
+            like::RecordData {
+                created_at: Datetime::now(),
+                subject: strong_ref::MainData { cid, uri: uri.to_string() }
+                    .into(),
+            }
+            .into(),
+        );
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "tsky-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        return path;
+    }
+
+    #[tokio::test]
+    async fn sends_and_keeps_queue_on_disk() {
+        let path = temp_file("outbox");
+        let outbox = Outbox::new();
+        outbox.load(path.clone()).unwrap();
+        let mut sent =
+            outbox.submit(like("at://did:plc:a/app.bsky.feed.post/1"));
+        outbox.submit(Op::Delete(
+            "at://did:plc:me/app.bsky.feed.like/2".to_string(),
+        ));
+
+        // left for the next run
+        let restored = Outbox::new();
+        restored.load(path.clone()).unwrap();
+        let items = restored.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1].op.describe(),
+            "Unlike at://did:plc:me/app.bsky.feed.like/2"
+        );
+
+        let agent: Client = Arc::new(FakeApi::new());
+        assert_eq!(outbox.send_next(&agent).await, Some(Duration::ZERO));
+        let uri = sent.try_recv().unwrap().unwrap().unwrap();
+        assert!(uri.contains("app.bsky.feed.like"));
+        assert_eq!(outbox.send_next(&agent).await, Some(Duration::ZERO));
+        assert_eq!(outbox.send_next(&agent).await, None);
+
+        let restored = Outbox::new();
+        restored.load(path.clone()).unwrap();
+        assert!(restored.items().is_empty());
+        assert!(!with_suffix(&path, ".tmp").exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn corrupt_queue_is_moved_aside() {
+        let path = temp_file("corrupt");
+        let corrupt = with_suffix(&path, ".corrupt");
+        // cut short by a crash
+        std::fs::write(&path, "[{\"id\":0,\"op\":").unwrap();
+
+        let outbox = Outbox::new();
+        outbox.load(path.clone()).unwrap();
+        assert!(outbox.items().is_empty());
+        assert!(!path.exists());
+        assert!(std::fs::read_to_string(&corrupt).unwrap().starts_with("[{"));
+
+        outbox.submit(Op::Delete(
+            "at://did:plc:me/app.bsky.feed.like/1".to_string(),
+        ));
+        let restored = Outbox::new();
+        restored.load(path.clone()).unwrap();
+        assert_eq!(restored.items().len(), 1);
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&corrupt);
+    }
+
+    #[tokio::test]
+    async fn backs_off_then_fails() {
+        let outbox = Outbox::new();
+        let mut sent =
+            outbox.submit(like("at://did:plc:a/app.bsky.feed.post/1"));
+        let agent: Client = Arc::new(FakeApi::new());
+        let offline: Client = Arc::new(CachedApi::offline(
+            agent.did().await.unwrap(),
+            temp_file("offline"),
+        ));
+
+        assert_eq!(outbox.send_next(&offline).await, Some(Duration::ZERO));
+        let wait = outbox.send_next(&offline).await.unwrap();
+        assert!(wait > Duration::from_millis(500));
+        assert_eq!(outbox.items()[0].attempts, 1);
+
+        for attempt in 2..=MAX_ATTEMPTS {
+            outbox.retry_at_now();
+            outbox.send_next(&offline).await;
+            assert_eq!(outbox.items()[0].attempts, attempt);
+        }
+        assert!(outbox.items()[0].failed);
+        assert_eq!(sent.try_recv().unwrap(), Err("Offline".to_string()));
+        assert_eq!(outbox.send_next(&offline).await, None);
+
+        let id = outbox.items()[0].id;
+        outbox.retry(id);
+        assert_eq!(outbox.send_next(&agent).await, Some(Duration::ZERO));
+        assert!(outbox.items().is_empty());
+    }
+
+    #[tokio::test]
+    async fn discard_reports_to_waiter() {
+        let outbox = Outbox::new();
+        let mut sent = outbox.submit(Op::Delete(
+            "at://did:plc:me/app.bsky.feed.repost/1".to_string(),
+        ));
+        outbox.discard(outbox.items()[0].id);
+        assert!(outbox.items().is_empty());
+        assert_eq!(sent.try_recv().unwrap(), Err("Discarded".to_string()));
+    }
+
+    impl Outbox {
+        fn retry_at_now(&self) {
+            let mut state = self.state.lock().unwrap();
+            state.items.iter_mut().for_each(|i| i.retry_at = None);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use atrium_api::{
+    app::bsky::feed::search_posts,
+    types::string::{AtIdentifier, Did, Handle, Language},
+};
+use chrono::{DateTime, NaiveDate};
+
+// Parameters of searchPosts from a query typed the way the Bluesky app takes
+// it, e.g. `cats "black cat" from:alice.bsky.social since:2024-01-01 lang:ja`.
+// Operators are taken out of the query, unknown ones are left in it.
+pub fn parse(
+    query: &str,
+    me: &Did,
+) -> Result<search_posts::ParametersData, String> {
+    let mut params = search_posts::ParametersData {
+        author: None,
+        cursor: None,
+        domain: None,
+        lang: None,
+        limit: Some(50.try_into().unwrap()),
+        mentions: None,
+        q: String::new(),
+        since: None,
+        sort: None,
+        tag: None,
+        until: None,
+        url: None,
+    };
+    let mut words = Vec::new();
+
+    for word in split(query) {
+        if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            params.tag.get_or_insert_with(Vec::new).push(tag.to_string());
+            continue;
+        }
+        let Some((operator, value)) =
+            word.split_once(':').filter(|_| !word.starts_with('"'))
+        else {
+            words.push(word);
+            continue;
+        };
+        let known = [
+            "from", "to", "mentions", "since", "until", "lang", "domain",
+            "url", "tag", "sort",
+        ];
+        if !known.contains(&operator) {
+            words.push(word);
+            continue;
+        }
+        if value.is_empty() {
+            return Err(format!("{}: needs a value", operator));
+        }
+        match operator {
+            "from" => params.author = Some(actor(value, me)?),
+            "to" | "mentions" => params.mentions = Some(actor(value, me)?),
+            "since" => params.since = Some(date(operator, value)?),
+            "until" => params.until = Some(date(operator, value)?),
+            "lang" => {
+                params.lang = Some(
+                    Language::new(value.to_string())
+                        .map_err(|_| format!("Invalid language {}", value))?,
+                )
+            }
+            "domain" => params.domain = Some(value.to_string()),
+            "url" => params.url = Some(value.to_string()),
+            "tag" => params
+                .tag
+                .get_or_insert_with(Vec::new)
+                .push(value.strip_prefix('#').unwrap_or(value).to_string()),
+            "sort" if value == "top" || value == "latest" => {
+                params.sort = Some(value.to_string())
+            }
+            "sort" => return Err("sort: is top or latest".to_string()),
+            _ => unreachable!(),
+        }
+    }
+
+    // the AppView understands the operators too, a query of only operators
+    // is sent as typed
+    params.q = if words.is_empty() {
+        query.trim().to_string()
+    } else {
+        words.join(" ")
+    };
+    if params.q.is_empty() {
+        return Err("Empty query".to_string());
+    }
+    return Ok(params);
+}
+
+// Words of the query, keeping "quoted phrases" together with their quotes
+fn split(query: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in query.char_indices() {
+        match (start, c) {
+            (_, '"') => {
+                quoted = !quoted;
+                start.get_or_insert(i);
+            }
+            (Some(s), c) if c.is_whitespace() && !quoted => {
+                words.push(&query[s..i]);
+                start = None;
+            }
+            (None, c) if !c.is_whitespace() => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push(&query[s..]);
+    }
+    return words;
+}
+
+// "me", a did or a handle with or without @
+fn actor(value: &str, me: &Did) -> Result<AtIdentifier, String> {
+    if value == "me" {
+        return Ok(AtIdentifier::Did(me.clone()));
+    }
+    let value = value.strip_prefix('@').unwrap_or(value);
+    if value.starts_with("did:") {
+        return Did::new(value.to_string())
+            .map(AtIdentifier::Did)
+            .map_err(|e| format!("Invalid did {}: {}", value, e));
+    }
+    return Handle::new(value.to_string())
+        .map(AtIdentifier::Handle)
+        .map_err(|e| format!("Invalid handle {}: {}", value, e));
+}
+
+// YYYY-MM-DD or a full datetime
+fn date(operator: &str, value: &str) -> Result<String, String> {
+    if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        || DateTime::parse_from_rfc3339(value).is_ok()
+    {
+        return Ok(value.to_string());
+    }
+    return Err(format!("{}: takes a date like 2024-01-31", operator));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn me() -> Did {
+        Did::new("did:plc:tskyme".to_string()).unwrap()
+    }
+
+    fn handle(h: &str) -> Option<AtIdentifier> {
+        Some(AtIdentifier::Handle(Handle::new(h.to_string()).unwrap()))
+    }
+
+    #[test]
+    fn operators() {
+        let p = parse(
+            "cats  from:@alice.bsky.social since:2024-01-01 lang:ja \
+             until:2024-02-01T00:00:00Z sort:latest",
+            &me(),
+        )
+        .unwrap();
+        assert_eq!(p.q, "cats");
+        assert_eq!(p.author, handle("alice.bsky.social"));
+        assert_eq!(p.since.as_deref(), Some("2024-01-01"));
+        assert_eq!(p.until.as_deref(), Some("2024-02-01T00:00:00Z"));
+        assert_eq!(p.lang, Language::new("ja".to_string()).ok());
+        assert_eq!(p.sort.as_deref(), Some("latest"));
+
+        let p = parse(
+            "#rust tag:tui mentions:me domain:github.com url:https://x.test/a",
+            &me(),
+        )
+        .unwrap();
+        assert_eq!(p.tag, Some(vec!["rust".to_string(), "tui".to_string()]));
+        assert_eq!(p.mentions, Some(AtIdentifier::Did(me())));
+        assert_eq!(p.domain.as_deref(), Some("github.com"));
+        assert_eq!(p.url.as_deref(), Some("https://x.test/a"));
+        // only operators, sent as typed
+        assert_eq!(
+            p.q,
+            "#rust tag:tui mentions:me domain:github.com url:https://x.test/a"
+        );
+
+        let p = parse("\"black cat\" to:did:plc:bob is:reply", &me()).unwrap();
+        assert_eq!(p.q, "\"black cat\" is:reply");
+        assert_eq!(
+            p.mentions,
+            Some(AtIdentifier::Did(Did::new("did:plc:bob".into()).unwrap()))
+        );
+        assert_eq!(parse("\"from:alice\"", &me()).unwrap().author, None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("", &me()).is_err());
+        assert!(parse("  ", &me()).is_err());
+        assert!(parse("cats from:", &me()).is_err());
+        assert!(parse("cats since:yesterday", &me()).is_err());
+        assert!(parse("cats sort:new", &me()).is_err());
+        assert!(parse("cats lang:!!", &me()).is_err());
+        assert!(parse("cats from:not_a_handle", &me()).is_err());
+    }
+}
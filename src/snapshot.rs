@@ -0,0 +1,255 @@
+use ratatui::buffer::Buffer;
+#[cfg(test)]
+use ratatui::{backend::TestBackend, Frame, Terminal};
+use unicode_width::UnicodeWidthStr;
+
+#[cfg(test)]
+use crate::{
+    app,
+    columns::{Column, ColumnStack},
+};
+
+// Renders into an in-memory buffer of the given size
+#[cfg(test)]
+pub fn render<F>(width: u16, height: u16, f: F) -> String
+where
+    F: FnOnce(&mut Frame),
+{
+    let mut terminal = Terminal::new(TestBackend::new(width, height))
+        .expect("TestBackend never fails");
+    terminal.draw(f).expect("TestBackend never fails");
+    return buffer_to_string(terminal.backend().buffer());
+}
+
+// Renders the column as the app draws a stack holding only it, with the
+// column name on the first line
+#[cfg(test)]
+pub fn render_column(width: u16, height: u16, column: Column) -> String {
+    let mut stack = ColumnStack::from(vec![column], width);
+    return render(width, height, |f| {
+        app::render_stack(f, &mut stack, f.area(), true)
+    });
+}
+
+// Symbols of the buffer, one line per row with trailing spaces trimmed.
+// Styles are ignored.
+pub fn buffer_to_string(buf: &Buffer) -> String {
+    let area = buf.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut x = area.left();
+        while x < area.right() {
+            let symbol = buf[(x, y)].symbol();
+            line += symbol;
+            // cells covered by a wide character hold a placeholder
+            x += symbol.width().max(1) as u16;
+        }
+        text += line.trim_end();
+        text += "\n";
+    }
+    return text;
+}
+
+#[cfg(test)]
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path =
+        format!("{}/src/snapshots/{}.txt", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::write(&path, actual)
+            .unwrap_or_else(|_| panic!("Cannot write snapshot {}", path));
+        return;
+    }
+    let Ok(expected) = std::fs::read_to_string(&path) else {
+        panic!(
+            "Snapshot {} is missing, rerun with UPDATE_SNAPSHOTS=1 to \
+            create it\n--- actual\n{}",
+            name, actual
+        );
+    };
+    assert!(
+        expected == actual,
+        "Snapshot {} does not match, rerun with UPDATE_SNAPSHOTS=1 to \
+        accept\n--- expected\n{}\n--- actual\n{}",
+        name,
+        expected,
+        actual
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use atrium_api::app::bsky::feed::{defs::FeedViewPost, get_timeline};
+    use chrono::DateTime;
+    use ratatui::{
+        layout::{Position, Rect},
+        widgets::Widget,
+    };
+
+    use super::*;
+    use crate::{
+        client::fake,
+        clock,
+        columns::updating_feed::{FeedSource, UpdatingFeed},
+        components::{
+            feed::{FeedPost, FeedPostWidget, PostFeed},
+            feed_filter::FeedFilter,
+            paragraph::Paragraph,
+            post::FacetType,
+        },
+    };
+
+    fn timeline() -> Vec<FeedPost> {
+        clock::freeze(DateTime::parse_from_rfc3339(fake::NOW).unwrap().into());
+        let timeline: get_timeline::OutputData =
+            serde_json::from_str(include_str!("client/fixtures/timeline.json"))
+                .unwrap();
+        return timeline
+            .feed
+            .iter()
+            .map(|p| FeedPost::try_from(p).unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn feed() {
+        let mut feed = PostFeed::default();
+        feed.insert_new_posts(timeline().into_iter());
+        let screen = render(60, 40, |f| f.render_widget(&mut feed, f.area()));
+        assert_snapshot("feed", &screen);
+    }
+
+    #[test]
+    fn feed_column() {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let column = UpdatingFeed::new(
+            FeedSource::Following,
+            &FeedFilter::default(),
+            tx,
+        );
+        column.feed.lock().unwrap().insert_new_posts(timeline().into_iter());
+        let screen = render_column(60, 30, Column::UpdatingFeed(column));
+        assert_snapshot("feed_column", &screen);
+    }
+
+    #[test]
+    fn feed_narrow() {
+        let mut feed = PostFeed::default();
+        feed.insert_new_posts(timeline().into_iter());
+        let screen = render(30, 40, |f| f.render_widget(&mut feed, f.area()));
+        assert_snapshot("feed_narrow", &screen);
+    }
+
+    // The bottom border must land on the last line the post claims
+    #[test]
+    fn feed_post_line_count() {
+        for post in timeline() {
+            for width in [20, 33, 60, 100] {
+                let widget = FeedPostWidget::new(&post);
+                let height = widget.line_count(width);
+                let screen = render(width, height + 1, |f| {
+                    widget
+                        .render(Rect::new(0, 0, width, height), f.buffer_mut())
+                });
+                let lines = screen.lines().collect::<Vec<_>>();
+                assert!(
+                    lines[height as usize - 1].starts_with('╰')
+                        && lines[height as usize].is_empty(),
+                    "{} at width {}:\n{}",
+                    post.post_uri,
+                    width,
+                    screen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn item_at() {
+        let mut feed = PostFeed::default();
+        feed.insert_new_posts(timeline().into_iter());
+        render(60, 40, |f| f.render_widget(&mut feed, f.area()));
+        assert_eq!(
+            feed.state.item_at(Position::new(5, 0)),
+            Some((0, Position::new(5, 0)))
+        );
+        assert_eq!(
+            feed.state.item_at(Position::new(7, 13)),
+            Some((1, Position::new(7, 2)))
+        );
+        assert_eq!(feed.state.item_at(Position::new(5, 36)), None);
+        assert_eq!(feed.state.item_at(Position::new(60, 0)), None);
+
+        // selecting by clicking does not scroll
+        feed.state.select_visible(2);
+        render(60, 40, |f| f.render_widget(&mut feed, f.area()));
+        assert_eq!(feed.state.selected, Some(2));
+        assert_eq!(
+            feed.state.item_at(Position::new(5, 19)),
+            Some((2, Position::new(5, 0)))
+        );
+    }
+
+    #[test]
+    fn facet_at() {
+        let posts = timeline();
+        let widget = FeedPostWidget::new(&posts[2]);
+        let Some(FacetType::Link(url)) =
+            widget.facet_at(60, 5, 4).map(|f| f.r#type)
+        else {
+            panic!("No link under the cursor");
+        };
+        assert_eq!(url, "https://github.com/lokxii/tsky");
+        assert!(widget.facet_at(60, 5, 3).is_none());
+        assert!(widget.facet_at(60, 0, 4).is_none());
+        // columns too narrow for the borders
+        for width in 0..4 {
+            assert!(widget.facet_at(width, 1, 4).is_none());
+        }
+    }
+
+    // Types from newer lexicons are shown as placeholders instead of
+    // crashing
+    #[test]
+    fn unsupported() {
+        let mut timeline: serde_json::Value =
+            serde_json::from_str(include_str!("client/fixtures/timeline.json"))
+                .unwrap();
+        let item = &mut timeline["feed"][3];
+        item["post"]["uri"] = "at://did:plc:bob/app.bsky.feed.post/new".into();
+        item["reason"] = serde_json::json!({"$type": "app.bsky.feed.defs#x"});
+        item["post"]["embed"] =
+            serde_json::json!({"$type": "app.bsky.embed.x#view", "a": 1});
+        let item: FeedViewPost = serde_json::from_value(item.clone()).unwrap();
+
+        let post = FeedPost::try_from(&item).unwrap();
+        let unsupported = post.unsupported();
+        assert_eq!(unsupported.len(), 2);
+        assert!(unsupported[1].json.contains("\"a\": 1"));
+
+        let widget = FeedPostWidget::new(&post);
+        let height = widget.line_count(50);
+        let screen = render(50, height, |f| {
+            widget.render(Rect::new(0, 0, 50, height), f.buffer_mut())
+        });
+        assert_snapshot("unsupported", &screen);
+
+        let mut item = item.clone();
+        item.post.record = serde_json::from_value(
+            serde_json::json!({"$type": "app.bsky.feed.x", "text": 1}),
+        )
+        .unwrap();
+        assert!(FeedPost::try_from(&item).is_err());
+    }
+
+    #[test]
+    fn wide_characters() {
+        let screen = render(10, 2, |f| {
+            f.render_widget(
+                Paragraph::new("日本語のテキスト"),
+                Rect::new(0, 0, 10, 2),
+            )
+        });
+        assert_eq!(screen, "日本語のテ\nキスト\n");
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use tokio::{sync::broadcast, task::JoinHandle};
+
+// Background tasks fetching data for columns. Every task is stopped at its
+// next await once `shutdown` is called.
+pub struct Workers {
+    shutdown: broadcast::Sender<()>,
+    // set once `shutdown` is called
+    stopping: AtomicBool,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Workers {
+    pub fn new() -> Workers {
+        let (shutdown, _) = broadcast::channel(1);
+        Workers {
+            shutdown,
+            stopping: AtomicBool::new(false),
+            handles: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn spawn<F>(&self, worker: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut shutdown = self.shutdown.subscribe();
+        let handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = worker => {}
+                _ = shutdown.recv() => {}
+            }
+        });
+
+        let mut handles = self.handles.lock().unwrap();
+        handles.retain(|h| !h.is_finished());
+        handles.push(handle);
+    }
+
+    // For workers about to do something that should not outlive tsky
+    pub fn stopping(&self) -> bool {
+        return self.stopping.load(Ordering::Relaxed);
+    }
+
+    // Stops every worker and waits for them to finish
+    pub async fn shutdown(&self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        let _ = self.shutdown.send(());
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap());
+        for handle in handles {
+            if let Err(e) = handle.await {
+                log::error!("Worker panicked: {}", e);
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! workers {
+    () => {
+        $crate::WORKERS
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn shutdown_stops_workers() {
+        let workers = Workers::new();
+        let ticks = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            let ticks = Arc::clone(&ticks);
+            workers.spawn(async move {
+                loop {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            });
+        }
+        workers.spawn(async {});
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert!(!workers.stopping());
+        workers.shutdown().await;
+        assert!(workers.stopping());
+        let stopped_at = ticks.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), stopped_at);
+        assert!(workers.handles.lock().unwrap().is_empty());
+    }
+}